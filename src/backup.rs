@@ -0,0 +1,74 @@
+use std::{fs, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}};
+use serde_json::json;
+
+use crate::{error::Error, format_size, format_time, live, output::Output};
+
+/// the name of the world without .brdb, which every backup of it starts with
+fn stem(world: &Path) -> String {
+    world.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_else(|| String::from("world"))
+}
+
+/// when a backup this command made of a world was taken, from its name like world-2025-01-31_12-00-00.brdb.
+/// a second backup in the same second gets a number on the end, like .2, which comes after the time
+fn backup_time(name: &str, stem: &str) -> Option<(String, u32)> {
+    let time = name.strip_prefix(&format!("{stem}-"))?.strip_suffix(".brdb")?;
+    let (time, n) = match time.split_once('.') {
+        Some((time, n)) => (time, n.parse().ok()?),
+        None => (time, 1),
+    };
+    let valid = time.len() == 19 && time.chars().all(|c| c.is_ascii_digit() || c == '-' || c == '_');
+    valid.then(|| (time.to_string(), n))
+}
+
+/// copy the world into `dir` (a backups folder next to it by default) as a new backup named after the time,
+/// then delete the oldest backups of it there until only `keep` are left
+pub fn backup(world: &Path, dir: Option<&Path>, keep: Option<usize>) -> Result<Output, Error> {
+    if keep == Some(0) {
+        return Err(Error::Other(String::from("--keep has to be at least 1, or the new backup would go too")));
+    }
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => world.parent().unwrap_or(Path::new(".")).join("backups"),
+    };
+    fs::create_dir_all(&dir)?;
+
+    let stem = stem(world);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as i64);
+    let time = format_time(now).replace(' ', "_").replace(':', "-");
+    let mut dest = dir.join(format!("{stem}-{time}.brdb"));
+    for n in 2.. {
+        if !dest.exists() {
+            break;
+        }
+        dest = dir.join(format!("{stem}-{time}.{n}.brdb"));
+    }
+    live::copy_to(world, &dest)?;
+    let size = fs::metadata(&dest)?.len();
+
+    let mut backups: Vec<((String, u32), PathBuf)> = fs::read_dir(&dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((backup_time(&entry.file_name().to_string_lossy(), &stem)?, entry.path())))
+        .collect();
+    // oldest first
+    backups.sort();
+    let backups: Vec<PathBuf> = backups.into_iter().map(|(_, path)| path).collect();
+    let mut removed = Vec::new();
+    if let Some(keep) = keep {
+        for old in backups.iter().take(backups.len().saturating_sub(keep)) {
+            fs::remove_file(old)?;
+            removed.push(old.clone());
+        }
+    }
+
+    let mut text = format!("backed up {} to {} ({})", world.display(), dest.display(), format_size(size));
+    if !removed.is_empty() {
+        text.push_str(&format!(", removed {} older backups", removed.len()));
+    }
+    let json = json!({
+        "backup": dest,
+        "bytes": size,
+        "removed": removed,
+        "backups": backups.len() - removed.len(),
+    });
+    Ok(Output::new(text, json))
+}
@@ -0,0 +1,504 @@
+use std::{collections::{HashMap, HashSet}, fs, path::Path, sync::Arc};
+use indexmap::IndexMap;
+use brdb::{
+    BrFsError, BrFsReader, BrReader, Brdb, Brick, BrickChunkSoA, BrickType, ChunkIndex, Owner, Position, World,
+    assets::materials,
+    fs::BrFs,
+    schema::{BrdbSchema, BrdbSchemaGlobalData, ReadBrdbSchema},
+    schemas,
+    tables::BrBlob,
+};
+use rayon::prelude::*;
+use serde_json::{Value, json};
+
+use crate::{
+    chunks::chunk_bounds,
+    confirm,
+    cli::BrickFilter,
+    error::Error,
+    mesh::brick_bounds,
+    output::{Output, table},
+    owners::{find_owner, owner_table},
+    progress::Progress,
+    rewrite::{Edit, rewrite_main_grid},
+    strings_to_lines, traverse,
+};
+
+/// a brick along with the chunk it's saved in and where it is in that chunk.
+/// together they make its id, like 0_0_0:12
+pub struct SavedBrick {
+    pub chunk: ChunkIndex,
+    pub index: usize,
+    pub brick: Brick,
+}
+
+impl SavedBrick {
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.chunk, self.index)
+    }
+}
+
+/// the ids of every brick grid in the world. 1 is the main grid, the others belong to entities
+pub fn grid_ids<T: BrFsReader>(db: &BrReader<T>) -> Result<Vec<usize>, Error> {
+    let fs = db.get_fs()?;
+    let mut ids = Vec::new();
+    if let Ok(BrFs::Folder(_, map)) = traverse(&fs, "World/0/Bricks/Grids") {
+        ids.extend(map.keys().filter_map(|name| name.parse::<usize>().ok()));
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+/// every brick in a grid, chunk by chunk in the order they're saved. stops reading chunks once it has `limit` of them
+pub fn grid_bricks<T: BrFsReader>(db: &BrReader<T>, grid: usize, limit: Option<usize>) -> Result<Vec<SavedBrick>, Error> {
+    let path = format!("World/0/Bricks/Grids/{grid}");
+    if db.find_file_by_path(format!("{path}/ChunkIndex.mps"))?.is_none() {
+        return Err(Error::NotFound(format!("/{path}")));
+    }
+
+    let chunks: Vec<ChunkIndex> = db.brick_chunk_index(grid)?.into_iter().map(|chunk| chunk.index).collect();
+    // with a limit the chunks are decoded a few at a time, so no more are read than needed
+    let batch = match limit {
+        Some(_) => rayon::current_num_threads(),
+        None => chunks.len().max(1),
+    };
+    let mut bricks = Vec::new();
+    for batch in chunks.chunks(batch) {
+        if limit.is_some_and(|limit| bricks.len() >= limit) {
+            break;
+        }
+        for (&chunk, in_chunk) in batch.iter().zip(decode_chunks(db, grid, batch)?) {
+            bricks.extend(in_chunk.into_iter().enumerate().map(|(index, brick)| SavedBrick { chunk, index, brick }));
+        }
+    }
+    if let Some(limit) = limit {
+        bricks.truncate(limit);
+    }
+    Ok(bricks)
+}
+
+/// a chunk's bricks file, read out of the world but not decoded yet
+struct RawChunk {
+    index: ChunkIndex,
+    schema: Arc<BrdbSchema>,
+    blob: BrBlob,
+}
+
+impl RawChunk {
+    fn decode(self, global_data: &Arc<BrdbSchemaGlobalData>) -> Result<Vec<Brick>, Error> {
+        let data = self.blob.read()?;
+        let soa = BrickChunkSoA::try_from(&data.as_slice().read_brdb(&self.schema, schemas::BRICK_CHUNK_SOA)?)?;
+        soa.iter_bricks(self.index, global_data.clone()).map(|brick| Ok(brick?)).collect()
+    }
+}
+
+/// the bricks in some chunks of a grid, in the same order as `chunks`. every chunk is a file of its own, so theyre
+/// decompressed and decoded on all cores at once. only reading them out of the world happens one by one,
+/// since sqlite cant be shared between threads
+pub fn decode_chunks<T: BrFsReader>(db: &BrReader<T>, grid: usize, chunks: &[ChunkIndex]) -> Result<Vec<Vec<Brick>>, Error> {
+    let global_data = db.global_data()?;
+    let mut raw = Vec::new();
+    for &index in chunks {
+        let path = format!("World/0/Bricks/Grids/{grid}/Chunks/{index}.mps");
+        log::trace!("reading /{path}");
+        let found = db.find_file_by_path(&path)?.ok_or(BrFsError::NotFound(path))?;
+        // each chunk is decoded with the schema that was live when it was saved
+        let schema = db.bricks_schema_rev(found.created_at)?;
+        raw.push(RawChunk { index, schema, blob: db.find_blob(found.blob_id)? });
+    }
+    log::debug!("decoding {} chunks of grid {grid} on {} threads", raw.len(), rayon::current_num_threads().min(raw.len()));
+    let progress = Progress::new("decoding", raw.len(), "chunks");
+    // collecting keeps the chunks in order, however the threads get through them
+    raw.into_par_iter()
+        .map(|chunk| {
+            let bricks = chunk.decode(&global_data);
+            progress.tick();
+            bricks
+        })
+        .collect()
+}
+
+/// every brick in one chunk of a grid, in the order they're saved
+pub fn chunk_bricks<T: BrFsReader>(db: &BrReader<T>, grid: usize, chunk: ChunkIndex) -> Result<Vec<SavedBrick>, Error> {
+    let soa = db.brick_chunk_soa(grid, chunk)?;
+    soa.iter_bricks(chunk, db.global_data()?)
+        .enumerate()
+        .map(|(index, brick)| Ok(SavedBrick { chunk, index, brick: brick? }))
+        .collect()
+}
+
+/// the name of a brick's asset, and its size if it's a procedural brick that can be resized
+pub fn asset(brick: &Brick) -> (String, Option<[u16; 3]>) {
+    match &brick.asset {
+        BrickType::Basic(asset) => (asset.to_string(), None),
+        BrickType::Procedural { asset, size } => (asset.to_string(), Some([size.x, size.y, size.z])),
+    }
+}
+
+/// a brick's color as #rrggbb
+pub fn color(brick: &Brick) -> String {
+    format!("#{:02x}{:02x}{:02x}", brick.color.r, brick.color.g, brick.color.b)
+}
+
+/// everything about a brick that's worth showing, as json
+pub fn brick_json(saved: &SavedBrick) -> Value {
+    let brick = &saved.brick;
+    let (asset, size) = asset(brick);
+    json!({
+        "id": saved.id(),
+        "chunk": saved.chunk.to_string(),
+        "index": saved.index,
+        "asset": asset,
+        "position": [brick.position.x, brick.position.y, brick.position.z],
+        "size": size,
+        "direction": format!("{:?}", brick.direction),
+        "rotation": format!("{:?}", brick.rotation),
+        "color": color(brick),
+        "material": brick.material.to_string(),
+        "material_intensity": brick.material_intensity,
+        "visible": brick.visible,
+        "owner": brick.owner_index,
+    })
+}
+
+/// the bricks in a grid as a table, one brick per line
+pub fn bricks_table(bricks: &[SavedBrick]) -> String {
+    let mut rows = vec![
+        ["ID", "POSITION", "SIZE", "ASSET", "COLOR", "MATERIAL", "OWNER"].map(String::from).to_vec(),
+    ];
+    for saved in bricks {
+        let brick = &saved.brick;
+        let (asset, size) = asset(brick);
+        rows.push(vec![
+            saved.id(),
+            format!("{},{},{}", brick.position.x, brick.position.y, brick.position.z),
+            size.map_or_else(|| String::from("-"), |[x, y, z]| format!("{x}x{y}x{z}")),
+            asset,
+            color(brick),
+            brick.material.to_string(),
+            brick.owner_index.map_or_else(|| String::from("-"), |owner| owner.to_string()),
+        ]);
+    }
+    table(&rows)
+}
+
+/// list the bricks in a grid of the world
+pub fn bricks<T: BrFsReader>(db: &BrReader<T>, grid: usize, limit: Option<usize>) -> Result<Output, Error> {
+    let bricks = grid_bricks(db, grid, limit)?;
+    let json = Value::Array(bricks.iter().map(brick_json).collect());
+    Ok(Output::new(bricks_table(&bricks), json))
+}
+
+/// save some bricks to a new world, or to a json file if `out` ends in .json, where they go under "bricks" in `json`.
+/// bricks keep their positions and owners, but their components and wires aren't carried over yet
+pub fn save_bricks<T: BrFsReader>(db: &BrReader<T>, bricks: Vec<SavedBrick>, out: &Path, mut json: Value) -> Result<(), Error> {
+    if out.exists() {
+        return Err(Error::Other(format!("{} already exists", out.display())));
+    }
+    match out.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => {
+            json["bricks"] = bricks.iter().map(brick_json).collect();
+            fs::write(out, serde_json::to_vec_pretty(&json)?)?;
+        }
+        _ => {
+            // owner 0 is always PUBLIC, which every new world starts with, so the rest keep their index
+            let owners = owner_table(db)?;
+            let mut world = World::new();
+            for i in 1..owners.user_ids.len() {
+                world.owners.insert(
+                    owners.user_ids[i],
+                    Owner {
+                        user_id: owners.user_ids[i],
+                        user_name: owners.user_names[i].clone(),
+                        display_name: owners.display_names[i].clone(),
+                    },
+                );
+            }
+            world.add_bricks(bricks.into_iter().map(|saved| saved.brick));
+            world.write_brdb(out)?;
+        }
+    }
+    Ok(())
+}
+
+/// whether a brick passes every filter that was given. `owner` is the index of the --owner filter's player
+pub fn matches(saved: &SavedBrick, filter: &BrickFilter, owner: Option<usize>) -> bool {
+    let brick = &saved.brick;
+    let close = |[r, g, b]: [u8; 3]| {
+        [r.abs_diff(brick.color.r), g.abs_diff(brick.color.g), b.abs_diff(brick.color.b)]
+            .iter()
+            .all(|difference| *difference <= filter.tolerance)
+    };
+    let position = [brick.position.x, brick.position.y, brick.position.z];
+    let above = |min: Position| position.iter().zip([min.x, min.y, min.z]).all(|(value, min)| *value >= min);
+    let below = |max: Position| position.iter().zip([max.x, max.y, max.z]).all(|(value, max)| *value <= max);
+    let material = brick.material.to_string();
+
+    filter.rgb.is_none_or(close)
+        && owner.is_none_or(|owner| brick.owner_index.unwrap_or(0) == owner)
+        && filter.min.is_none_or(above)
+        && filter.max.is_none_or(below)
+        && filter.chunk.is_none_or(|chunk| chunk == saved.chunk)
+        && filter.asset.as_ref().is_none_or(|name| asset(brick).0.eq_ignore_ascii_case(name))
+        && filter.material.as_ref().is_none_or(|name| same_material(&material, name))
+}
+
+/// whether a material is the one a user named. they can be given with or without the BMC_ in front, like glow or BMC_Glow
+pub fn same_material(material: &str, name: &str) -> bool {
+    material.eq_ignore_ascii_case(name) || material.strip_prefix("BMC_").is_some_and(|short| short.eq_ignore_ascii_case(name))
+}
+
+/// the bricks in a grid that pass the filters. chunks that cant have any of them in it arent read at all
+pub fn filtered_bricks<T: BrFsReader>(db: &BrReader<T>, grid: usize, filter: &BrickFilter) -> Result<Vec<SavedBrick>, Error> {
+    let path = format!("World/0/Bricks/Grids/{grid}");
+    if db.find_file_by_path(format!("{path}/ChunkIndex.mps"))?.is_none() {
+        return Err(Error::NotFound(format!("/{path}")));
+    }
+    if let (Some(min), Some(max)) = (filter.min, filter.max)
+        && (min.x > max.x || min.y > max.y || min.z > max.z)
+    {
+        return Err(Error::Other(String::from("--min has to be below --max on every axis")));
+    }
+    let owner = match &filter.owner {
+        Some(name_or_id) => Some(find_owner(&owner_table(db)?, name_or_id)?),
+        None => None,
+    };
+
+    let mut chunks = Vec::new();
+    for chunk in db.brick_chunk_index(grid)? {
+        let (low, high) = chunk_bounds(&chunk);
+        let skip = filter.chunk.is_some_and(|index| index != chunk.index)
+            || filter.min.is_some_and(|min| [min.x, min.y, min.z].iter().zip(high).any(|(min, high)| *min >= high))
+            || filter.max.is_some_and(|max| [max.x, max.y, max.z].iter().zip(low).any(|(max, low)| *max < low));
+        if !skip {
+            chunks.push(chunk.index);
+        }
+    }
+
+    let mut bricks = Vec::new();
+    for (&chunk, in_chunk) in chunks.iter().zip(decode_chunks(db, grid, &chunks)?) {
+        let in_chunk = in_chunk.into_iter().enumerate().map(|(index, brick)| SavedBrick { chunk, index, brick });
+        bricks.extend(in_chunk.filter(|saved| matches(saved, filter, owner)));
+    }
+    Ok(bricks)
+}
+
+/// the bricks in a grid that pass the filters, followed by the box around them and how many are in each chunk.
+/// they can also be saved to a new world or json file
+pub fn find<T: BrFsReader>(db: &BrReader<T>, grid: usize, filter: &BrickFilter, export: Option<&Path>) -> Result<Output, Error> {
+    let bricks = filtered_bricks(db, grid, filter)?;
+
+    let mut chunks: IndexMap<String, usize> = IndexMap::new();
+    let (mut min, mut max) = ([i32::MAX; 3], [i32::MIN; 3]);
+    for saved in &bricks {
+        *chunks.entry(saved.chunk.to_string()).or_default() += 1;
+        let (low, high) = brick_bounds(&saved.brick);
+        min = [0, 1, 2].map(|axis| min[axis].min(low[axis].floor() as i32));
+        max = [0, 1, 2].map(|axis| max[axis].max(high[axis].ceil() as i32));
+    }
+    chunks.sort_by(|a, a_count, b, b_count| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut text = bricks_table(&bricks);
+    let mut json = json!({ "count": bricks.len(), "chunks": chunks, "bricks": bricks.iter().map(brick_json).collect::<Vec<_>>() });
+    if !bricks.is_empty() {
+        let in_chunks: Vec<String> = chunks.iter().map(|(chunk, count)| format!("{chunk} ({count})")).collect();
+        text.push_str(&format!(
+            "\n\n{} bricks, from {},{},{} to {},{},{}\nchunks: {}",
+            bricks.len(),
+            min[0],
+            min[1],
+            min[2],
+            max[0],
+            max[1],
+            max[2],
+            in_chunks.join(", ")
+        ));
+        json["min"] = json!(min);
+        json["max"] = json!(max);
+    }
+    if let Some(out) = export {
+        let count = bricks.len();
+        save_bricks(db, bricks, out, json!({}))?;
+        text.push_str(&format!("\nexported {count} bricks to {}", out.display()));
+    }
+    Ok(Output::new(text, json))
+}
+
+/// delete the bricks on the main grid that pass the filters, as a new revision. asks first unless `yes` is set,
+/// and with `dry_run` only lists them
+pub fn remove(db: &BrReader<Brdb>, filter: &BrickFilter, dry_run: bool, yes: bool) -> Result<Output, Error> {
+    if filter.is_empty() {
+        return Err(Error::Other(String::from("give at least one filter, like --owner, so not every brick gets removed")));
+    }
+    let bricks = filtered_bricks(db, 1, filter)?;
+    let ids: Vec<String> = bricks.iter().map(SavedBrick::id).collect();
+    if bricks.is_empty() {
+        return Ok(Output::new("no bricks match", json!({ "removed": 0, "bricks": ids })));
+    }
+    if dry_run {
+        let text = format!("{}\n\nwould remove {} bricks", bricks_table(&bricks), bricks.len());
+        return Ok(Output::new(text, json!({ "removed": 0, "bricks": ids })));
+    }
+    if !yes && !confirm(&format!("remove {} bricks?", bricks.len()), "--yes")? {
+        return Ok(Output::new("nothing removed", json!({ "removed": 0, "bricks": [] })));
+    }
+
+    let remove: HashSet<(ChunkIndex, usize)> = bricks.iter().map(|saved| (saved.chunk, saved.index)).collect();
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| match remove.contains(&(saved.chunk, saved.index)) {
+        true => Edit::Remove,
+        false => Edit::Keep,
+    })?;
+    db.write_pending(format!("Remove {} bricks", rewritten.removed), pending)?;
+
+    let text = format!(
+        "removed {} bricks, {} components and {} wires",
+        rewritten.removed, rewritten.components, rewritten.wires
+    );
+    let json = json!({ "removed": rewritten.removed, "components": rewritten.components, "wires": rewritten.wires, "bricks": ids });
+    Ok(Output::new(text, json))
+}
+
+/// swap colors on the main grid as a new revision. each color in `from` turns into the one at the same place in `to`,
+/// all at the same time, so two colors can trade places
+pub fn recolor(db: &BrReader<Brdb>, from: &[[u8; 3]], to: &[[u8; 3]]) -> Result<Output, Error> {
+    if from.len() != to.len() {
+        return Err(Error::Other(format!("got {} --from colors but {} --to colors, they have to pair up", from.len(), to.len())));
+    }
+    let hex = |[r, g, b]: [u8; 3]| format!("#{r:02x}{g:02x}{b:02x}");
+    let mut swaps: HashMap<[u8; 3], [u8; 3]> = HashMap::new();
+    for (from, to) in from.iter().zip(to) {
+        if swaps.insert(*from, *to).is_some() {
+            return Err(Error::Other(format!("{} is given as --from more than once, so it cant tell which --to it turns into", hex(*from))));
+        }
+    }
+    let mut counts: IndexMap<[u8; 3], usize> = from.iter().map(|color| (*color, 0)).collect();
+
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| {
+        let color = &saved.brick.color;
+        match swaps.get(&[color.r, color.g, color.b]) {
+            Some(&[r, g, b]) => {
+                *counts.entry([color.r, color.g, color.b]).or_default() += 1;
+                Edit::Replace(Brick { color: brdb::Color::new(r, g, b), ..saved.brick.clone() })
+            }
+            None => Edit::Keep,
+        }
+    })?;
+    if rewritten.replaced > 0 {
+        db.write_pending(format!("Recolor {} bricks", rewritten.replaced), pending)?;
+    }
+
+    let mut lines = Vec::new();
+    let mut json = Vec::new();
+    for (color, count) in &counts {
+        lines.push(format!("{} -> {}: {count} bricks", hex(*color), hex(swaps[color])));
+        json.push(json!({ "from": hex(*color), "to": hex(swaps[color]), "bricks": count }));
+    }
+    lines.push(match rewritten.replaced {
+        0 => String::from("no bricks have those colors, nothing changed"),
+        count => format!("recolored {count} bricks"),
+    });
+    Ok(Output::new(strings_to_lines(lines.iter()), json!({ "recolored": rewritten.replaced, "colors": json })))
+}
+
+/// swap one material for another on the bricks of the main grid that pass the filters, as a new revision.
+/// `to` has to be a material the game has
+pub fn rematerial(db: &BrReader<Brdb>, from: &str, to: &str, filter: &BrickFilter) -> Result<Output, Error> {
+    let known = [materials::PLASTIC, materials::GLASS, materials::TRANSLUCENT_PLASTIC, materials::GLOW, materials::METALLIC, materials::HOLOGRAM];
+    let Some(to) = known.iter().find(|material| same_material(material.as_ref(), to)).cloned() else {
+        let names: Vec<String> = known.iter().map(|material| material.as_ref().trim_start_matches("BMC_").to_lowercase()).collect();
+        return Err(Error::Other(format!("{to} isnt a material the game has, it can be one of {}", names.join(", "))));
+    };
+
+    let matching: HashSet<(ChunkIndex, usize)> = filtered_bricks(db, 1, filter)?
+        .into_iter()
+        .filter(|saved| same_material(saved.brick.material.as_ref(), from))
+        .map(|saved| (saved.chunk, saved.index))
+        .collect();
+    if matching.is_empty() {
+        return Ok(Output::new(format!("no bricks are {from}, nothing changed"), json!({ "rematerialed": 0, "to": to.as_ref() })));
+    }
+
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| match matching.contains(&(saved.chunk, saved.index)) {
+        true => Edit::Replace(Brick { material: to.clone(), ..saved.brick.clone() }),
+        false => Edit::Keep,
+    })?;
+    db.write_pending(format!("Change {} bricks to {to}", rewritten.replaced), pending)?;
+
+    let text = format!("changed {} bricks from {from} to {to}", rewritten.replaced);
+    Ok(Output::new(text, json!({ "rematerialed": rewritten.replaced, "to": to.as_ref() })))
+}
+
+#[cfg(test)]
+mod tests {
+    use brdb::IntoReader;
+
+    use super::*;
+    use crate::{rewrite::read_components, testing::world};
+
+    /// how many bricks, components and wires the main grid's chunk index says there are
+    fn counts(db: &BrReader<Brdb>) -> (u32, u32, u32) {
+        let chunks = db.brick_chunk_index(1).unwrap();
+        chunks.iter().fold((0, 0, 0), |(b, c, w), chunk| (b + chunk.num_bricks, c + chunk.num_components, w + chunk.num_wires))
+    }
+
+    #[test]
+    fn filtered_bricks_keeps_their_chunk_and_index() {
+        let db = world();
+        let filter = BrickFilter { min: Some(Position::new(10, 0, 0)), ..Default::default() };
+        let ids: Vec<_> = filtered_bricks(&db, 1, &filter).unwrap().iter().map(SavedBrick::id).collect();
+        assert_eq!(ids, ["0_0_0:1"]);
+        let filter = BrickFilter { chunk: Some(ChunkIndex { x: 1, y: 0, z: 0 }), ..Default::default() };
+        assert!(filtered_bricks(&db, 1, &filter).unwrap().is_empty());
+    }
+
+    #[test]
+    fn remove_by_owner_takes_the_components_and_wires_on_those_bricks_along() {
+        let db = world();
+        let filter = BrickFilter { owner: Some(String::from("builder")), ..Default::default() };
+        remove(&db, &filter, false, true).unwrap();
+
+        assert_eq!(counts(&db), (1, 1, 0));
+        let owners = owner_table(&db).unwrap();
+        assert_eq!((owners.brick_counts, owners.component_counts), (vec![2, 0], vec![1, 0]));
+        // the gate is the first brick in its chunk now, and the wire to the rerouter went with the rerouter
+        let components = read_components(&db, "World/0/Bricks/Grids/1/Components/0_0_0.mps").unwrap();
+        let types = db.global_data().unwrap();
+        let left: Vec<_> = (components.entries.iter())
+            .map(|(ty, brick, _)| (types.component_type_names[*ty as usize].as_str(), *brick))
+            .collect();
+        assert_eq!(left.len(), 1);
+        assert!(!left[0].0.contains("Rerouter") && left[0].1 == 0, "{left:?}");
+        assert!(db.find_file_by_path("World/0/Bricks/Grids/1/Wires/0_0_0.mps").unwrap().is_none());
+    }
+
+    #[test]
+    fn recolor_swaps_colors_on_the_main_grid_only() {
+        let db = world();
+        recolor(&db, &[[255, 255, 255]], &[[255, 0, 0]]).unwrap();
+        let colors = |grid| grid_bricks(&db, grid, None).unwrap().into_iter().map(|saved| color(&saved.brick)).collect::<Vec<_>>();
+        assert_eq!(colors(1), ["#ff0000", "#ff0000"]);
+        assert_eq!(colors(2), ["#ffffff"]);
+        assert_eq!(counts(&db), (2, 2, 1));
+    }
+
+    #[test]
+    fn recolor_refuses_the_same_from_color_twice() {
+        let db = world();
+        let white = [255, 255, 255];
+        assert!(matches!(recolor(&db, &[white, white], &[[255, 0, 0], [0, 0, 255]]), Err(Error::Other(_))));
+        assert_eq!(color(&grid_bricks(&db, 1, None).unwrap()[0].brick), "#ffffff");
+    }
+
+    #[test]
+    fn rematerial_changes_the_material_and_keeps_components_and_wires() {
+        let db = world();
+        rematerial(&db, "plastic", "glow", &BrickFilter::default()).unwrap();
+        // the reader keeps the global data it read before glow was added to it, so a new one reads the bricks
+        let bricks = grid_bricks(&(&*db).into_reader(), 1, None).unwrap();
+        assert!(bricks.iter().all(|saved| saved.brick.material.as_ref() == materials::GLOW.as_ref()));
+        assert_eq!(counts(&db), (2, 2, 1));
+    }
+}
@@ -0,0 +1,560 @@
+use std::{
+    fs,
+    io::{Read, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use brdb::{
+    BrFsReader, BrReader, Brdb, Brick, BrickSize, BrickType, Collision, Color, Guid, Owner, OwnerTableSoA, Position,
+    byte_to_orientation,
+};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use indexmap::IndexSet;
+use serde_json::json;
+
+use crate::{
+    bricks::{asset, grid_bricks},
+    error::Error,
+    merge::{add_main_grid_bricks, owner_index},
+    output::Output,
+    owners::owner_table,
+};
+
+/// the last version of the .brs format, from before brickadia moved to .brdb
+const BRS_VERSION: u16 = 10;
+
+/// every world has this one, since brdb doesnt keep physical materials per brick
+const PHYSICAL_MATERIAL: &str = "BPMC_Default";
+
+/// writes values the way unreal serializes them, one bit at a time, lowest bit of each byte first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn bit(&mut self, bit: bool) {
+        if self.bits.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            *self.bytes.last_mut().unwrap() |= 1 << (self.bits % 8);
+        }
+        self.bits += 1;
+    }
+
+    /// skip to the start of the next byte
+    fn align(&mut self) {
+        self.bits = self.bytes.len() * 8;
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            for i in 0..8 {
+                self.bit(byte & (1 << i) != 0);
+            }
+        }
+    }
+
+    /// a number below `max`, in as few bits as it takes to write `max - 1`
+    fn uint(&mut self, value: u32, max: u32) {
+        let (mut written, mut mask) = (0u32, 1u32);
+        while written + mask < max && mask != 0 {
+            self.bit(value & mask != 0);
+            if value & mask != 0 {
+                written |= mask;
+            }
+            mask <<= 1;
+        }
+    }
+
+    /// a number in 7 bit groups, each with a bit saying whether another one follows
+    fn uint_packed(&mut self, mut value: u32) {
+        loop {
+            let group = (value & 0x7f) as u8;
+            value >>= 7;
+            self.bytes(&[group << 1 | (value != 0) as u8]);
+            if value == 0 {
+                break;
+            }
+        }
+    }
+
+    /// a signed number, as its size with the lowest bit set if it isnt negative
+    fn int_packed(&mut self, value: i32) {
+        self.uint_packed(value.unsigned_abs() << 1 | (value >= 0) as u32);
+    }
+}
+
+/// reads what [`BitWriter`] writes
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize,
+}
+
+impl BitReader<'_> {
+    fn bit(&mut self) -> Result<bool, Error> {
+        let Some(byte) = self.bytes.get(self.bits / 8) else {
+            return Err(Error::Other(String::from("the bricks in the save end too early")));
+        };
+        let bit = byte & (1 << (self.bits % 8)) != 0;
+        self.bits += 1;
+        Ok(bit)
+    }
+
+    fn align(&mut self) {
+        self.bits = self.bits.div_ceil(8) * 8;
+    }
+
+    fn at_end(&self) -> bool {
+        self.bits / 8 >= self.bytes.len()
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        let mut bytes = [0; N];
+        for byte in &mut bytes {
+            for i in 0..8 {
+                *byte |= (self.bit()? as u8) << i;
+            }
+        }
+        Ok(bytes)
+    }
+
+    fn uint(&mut self, max: u32) -> Result<u32, Error> {
+        let (mut value, mut mask) = (0u32, 1u32);
+        while value + mask < max && mask != 0 {
+            if self.bit()? {
+                value |= mask;
+            }
+            mask <<= 1;
+        }
+        Ok(value)
+    }
+
+    fn uint_packed(&mut self) -> Result<u32, Error> {
+        let mut value = 0;
+        for group in 0..5 {
+            let [byte] = self.bytes::<1>()?;
+            value |= ((byte >> 1) as u32) << (7 * group);
+            if byte & 1 == 0 {
+                break;
+            }
+        }
+        Ok(value)
+    }
+
+    fn int_packed(&mut self) -> Result<i32, Error> {
+        let value = self.uint_packed()?;
+        let size = (value >> 1) as i32;
+        Ok(if value & 1 != 0 { size } else { -size })
+    }
+}
+
+/// reads the values in the sections of a save that arent bit packed
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    at: usize,
+}
+
+impl ByteReader<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8], Error> {
+        let Some(bytes) = self.bytes.get(self.at..self.at + len) else {
+            return Err(Error::Other(String::from("the save ends too early")));
+        };
+        self.at += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn i32(&mut self) -> Result<i32, Error> {
+        Ok(i32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.i32()?;
+        let text = match len {
+            0.. => String::from_utf8_lossy(self.take(len as usize)?).into_owned(),
+            _ => {
+                let units: Vec<u16> = self
+                    .take(len.unsigned_abs() as usize * 2)?
+                    .chunks(2)
+                    .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                    .collect();
+                String::from_utf16_lossy(&units)
+            }
+        };
+        Ok(text.trim_end_matches('\0').to_string())
+    }
+
+    fn guid(&mut self) -> Result<Guid, Error> {
+        Ok(Guid { a: self.i32()? as u32, b: self.i32()? as u32, c: self.i32()? as u32, d: self.i32()? as u32 })
+    }
+
+    /// an array, prefixed with how many things are in it
+    fn array<T>(&mut self, mut read: impl FnMut(&mut Self) -> Result<T, Error>) -> Result<Vec<T>, Error> {
+        let len = self.i32()?.max(0);
+        (0..len).map(|_| read(self)).collect()
+    }
+
+    /// a section of the file, which is zlib compressed unless its compressed size is 0
+    fn section(&mut self) -> Result<Vec<u8>, Error> {
+        let (len, compressed) = (self.i32()?.max(0) as usize, self.i32()?.max(0) as usize);
+        if compressed == 0 {
+            return Ok(self.take(len)?.to_vec());
+        }
+        let mut data = Vec::with_capacity(len);
+        ZlibDecoder::new(self.take(compressed)?).read_to_end(&mut data)?;
+        Ok(data)
+    }
+}
+
+/// the max an index into a list of `len` things is written with, which is never below 2
+fn list_max(len: usize) -> u32 {
+    len.max(2) as u32
+}
+
+/// an unreal string: its length with the null at the end, then the text, as utf-16 if it isnt ascii
+fn write_string(out: &mut Vec<u8>, text: &str) {
+    if text.is_ascii() {
+        out.extend((text.len() as i32 + 1).to_le_bytes());
+        out.extend(text.as_bytes());
+        out.push(0);
+    } else {
+        let units: Vec<u16> = text.encode_utf16().chain([0]).collect();
+        out.extend((-(units.len() as i32)).to_le_bytes());
+        out.extend(units.iter().flat_map(|unit| unit.to_le_bytes()));
+    }
+}
+
+fn write_guid(out: &mut Vec<u8>, guid: Guid) {
+    for part in [guid.a, guid.b, guid.c, guid.d] {
+        out.extend(part.to_le_bytes());
+    }
+}
+
+/// an array of strings, prefixed with how many there are
+fn write_strings<'a>(out: &mut Vec<u8>, strings: impl ExactSizeIterator<Item = &'a str>) {
+    out.extend((strings.len() as i32).to_le_bytes());
+    for string in strings {
+        write_string(out, string);
+    }
+}
+
+/// a section of the file, zlib compressed unless that would make it bigger
+fn write_section(out: &mut Vec<u8>, data: &[u8]) -> Result<(), Error> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    let compressed = encoder.finish()?;
+    out.extend((data.len() as i32).to_le_bytes());
+    match compressed.len() < data.len() {
+        true => {
+            out.extend((compressed.len() as i32).to_le_bytes());
+            out.extend(compressed);
+        }
+        false => {
+            out.extend(0i32.to_le_bytes());
+            out.extend(data);
+        }
+    }
+    Ok(())
+}
+
+/// a user id from Bundle.json, like 00112233-4455-6677-8899-aabbccddeeff
+fn parse_guid(id: &str) -> Guid {
+    let value = u128::from_str_radix(&id.replace('-', ""), 16).unwrap_or(0);
+    Guid { a: (value >> 96) as u32, b: (value >> 64) as u32, c: (value >> 32) as u32, d: value as u32 }
+}
+
+/// the current time as unreal ticks, 100ns each since the year 1
+fn save_time() -> i64 {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs() as i64);
+    (seconds + 62_135_596_800) * 10_000_000
+}
+
+/// save the bricks on the main grid as a .brs file, for tools that only read the old save format.
+/// components, wires and entities aren't carried over
+pub fn export(db: &BrReader<Brdb>, out: &Path) -> Result<Output, Error> {
+    let bundle = db.bundle_json()?;
+    let map = db.world_json()?.map_or_else(|| String::from("Plate"), |world| world.environment);
+    let owners = owner_table(db)?;
+    let bricks = grid_bricks(db, 1, None)?;
+
+    let mut assets = IndexSet::new();
+    let mut materials = IndexSet::new();
+    for saved in &bricks {
+        assets.insert(asset(&saved.brick).0);
+        materials.insert(saved.brick.material.to_string());
+    }
+
+    // owner 0 is PUBLIC in both formats, and .brs leaves it out of the list
+    let mut owner_bricks = vec![0i32; owners.user_ids.len()];
+    for saved in &bricks {
+        if let Some(count) = owner_bricks.get_mut(saved.brick.owner_index.unwrap_or(0)) {
+            *count += 1;
+        }
+    }
+
+    let mut file = b"BRS".to_vec();
+    file.extend(BRS_VERSION.to_le_bytes());
+    // the game version is saved as a changelist, like CL11891
+    let game_version = bundle.game_version.trim_start_matches("CL").parse::<i32>().unwrap_or(0);
+    file.extend(game_version.to_le_bytes());
+
+    let (author, author_id) = match bundle.authors.first() {
+        Some(author) => (author.name.as_str(), parse_guid(&author.id)),
+        None => ("", Guid { a: 0, b: 0, c: 0, d: 0 }),
+    };
+    let mut header = Vec::new();
+    write_string(&mut header, &map);
+    write_string(&mut header, author);
+    write_string(&mut header, &bundle.description);
+    write_guid(&mut header, author_id);
+    write_string(&mut header, author);
+    write_guid(&mut header, author_id);
+    header.extend(save_time().to_le_bytes());
+    header.extend((bricks.len() as i32).to_le_bytes());
+    write_section(&mut file, &header)?;
+
+    let mut header = Vec::new();
+    write_strings(&mut header, [].into_iter());
+    write_strings(&mut header, assets.iter().map(String::as_str));
+    // every brick gets its own color, so the palette stays empty
+    header.extend(0i32.to_le_bytes());
+    write_strings(&mut header, materials.iter().map(String::as_str));
+    header.extend((owners.user_ids.len().saturating_sub(1) as i32).to_le_bytes());
+    for (i, count) in owner_bricks.iter().enumerate().skip(1) {
+        write_guid(&mut header, owners.user_ids[i]);
+        write_string(&mut header, &owners.user_names[i]);
+        header.extend(count.to_le_bytes());
+    }
+    write_strings(&mut header, [PHYSICAL_MATERIAL].into_iter());
+    write_section(&mut file, &header)?;
+
+    // no preview image
+    file.push(0);
+
+    let mut bits = BitWriter::default();
+    for saved in &bricks {
+        let brick = &saved.brick;
+        let (asset, size) = asset(brick);
+        bits.align();
+        bits.uint(assets.get_index_of(&asset).unwrap_or(0) as u32, list_max(assets.len()));
+        bits.bit(size.is_some());
+        for size in size.into_iter().flatten() {
+            bits.uint_packed(size as u32);
+        }
+        for position in [brick.position.x, brick.position.y, brick.position.z] {
+            bits.int_packed(position);
+        }
+        bits.uint((brick.direction as u32) << 2 | brick.rotation as u32, 24);
+        let collision = brick.collision;
+        for flag in [collision.player, collision.weapon, collision.interact, collision.tool] {
+            bits.bit(flag);
+        }
+        bits.bit(brick.visible);
+        bits.uint(materials.get_index_of(&brick.material.to_string()).unwrap_or(0) as u32, list_max(materials.len()));
+        // the one physical material
+        bits.uint(0, list_max(1));
+        bits.uint(brick.material_intensity.min(10) as u32, 11);
+        bits.bit(true);
+        bits.bytes(&[brick.color.r, brick.color.g, brick.color.b]);
+        bits.uint_packed(brick.owner_index.unwrap_or(0) as u32);
+    }
+    write_section(&mut file, &bits.bytes)?;
+
+    // no components
+    write_section(&mut file, &0i32.to_le_bytes())?;
+
+    fs::write(out, &file)?;
+    let text = format!("exported {} bricks to {}", bricks.len(), out.display());
+    Ok(Output::new(text, json!({ "out": out, "bricks": bricks.len(), "bytes": file.len() })))
+}
+
+/// the materials every brick could use before saves listed them
+const OLD_MATERIALS: [&str; 5] = ["BMC_Hologram", "BMC_Plastic", "BMC_Glow", "BMC_Metallic", "BMC_Glass"];
+
+/// the bricks in a .brs file, with the owners they point at as indices into the owner table they're added to.
+/// also returns how many kinds of components were in the save, since those get left out
+fn read_bricks(data: &[u8], owners: &mut OwnerTableSoA) -> Result<(Vec<Brick>, i32), Error> {
+    let mut file = ByteReader { bytes: data, at: 0 };
+    if file.take(3)? != b"BRS" {
+        return Err(Error::Other(String::from("it doesnt start with BRS")));
+    }
+    let version = file.u16()?;
+    if version > BRS_VERSION {
+        return Err(Error::Other(format!("version {version} is newer than brdb_cmd knows about")));
+    }
+    if version >= 8 {
+        file.i32()?;
+    }
+
+    let header = file.section()?;
+    let mut header = ByteReader { bytes: &header, at: 0 };
+    for _ in 0..3 {
+        header.string()?;
+    }
+    header.guid()?;
+    if version >= 8 {
+        header.string()?;
+        header.guid()?;
+    }
+    if version >= 4 {
+        header.take(8)?;
+    }
+    let brick_count = header.i32()?.max(0) as usize;
+
+    let header = file.section()?;
+    let mut header = ByteReader { bytes: &header, at: 0 };
+    header.array(ByteReader::string)?;
+    let assets = header.array(ByteReader::string)?;
+    // colors are saved as bgra
+    let colors = header.array(|reader| {
+        let [b, g, r, _] = reader.take(4)?.try_into().unwrap();
+        Ok(Color::new(r, g, b))
+    })?;
+    let materials = match version {
+        2.. => header.array(ByteReader::string)?,
+        _ => OLD_MATERIALS.map(String::from).to_vec(),
+    };
+    // .brs owner 0 is PUBLIC, which is 0 in the owner table too
+    let mut owner_map = vec![0];
+    if version >= 3 {
+        for owner in header.array(|reader| {
+            let (user_id, user_name) = (reader.guid()?, reader.string()?);
+            if version >= 8 {
+                reader.i32()?;
+            }
+            Ok(Owner { user_id, display_name: user_name.clone(), user_name })
+        })? {
+            owner_map.push(owner_index(owners, &owner));
+        }
+    }
+    let physical_materials = match version {
+        9.. => header.array(ByteReader::string)?.len(),
+        _ => 0,
+    };
+
+    if version >= 8 && file.u8()? != 0 {
+        let len = file.i32()?.max(0) as usize;
+        file.take(len)?;
+    }
+
+    let section = file.section()?;
+    let mut bits = BitReader { bytes: &section, bits: 0 };
+    let mut bricks = Vec::new();
+    loop {
+        bits.align();
+        if bricks.len() >= brick_count || bits.at_end() {
+            break;
+        }
+        let asset = bits.uint(list_max(assets.len()))? as usize;
+        let size = match bits.bit()? {
+            true => Some([bits.uint_packed()?, bits.uint_packed()?, bits.uint_packed()?]),
+            false => None,
+        };
+        let position = Position::new(bits.int_packed()?, bits.int_packed()?, bits.int_packed()?);
+        let (direction, rotation) = byte_to_orientation(bits.uint(24)? as u8);
+        let collision = match version {
+            10.. => {
+                let (player, weapon, interact, tool) = (bits.bit()?, bits.bit()?, bits.bit()?, bits.bit()?);
+                Collision { player, weapon, interact, tool, ..Collision::default() }
+            }
+            _ => {
+                let all = bits.bit()?;
+                Collision { player: all, weapon: all, interact: all, tool: all, ..Collision::default() }
+            }
+        };
+        let visible = bits.bit()?;
+        let material = match version {
+            8.. => bits.uint(list_max(materials.len()))? as usize,
+            _ => match bits.bit()? {
+                true => bits.uint_packed()? as usize,
+                false => 1,
+            },
+        };
+        let material_intensity = match version {
+            9.. => {
+                bits.uint(list_max(physical_materials))?;
+                bits.uint(11)? as u8
+            }
+            _ => 5,
+        };
+        let color = match (bits.bit()?, version) {
+            (true, 9..) => {
+                let [r, g, b] = bits.bytes()?;
+                Color::new(r, g, b)
+            }
+            (true, _) => {
+                let [b, g, r, _] = bits.bytes()?;
+                Color::new(r, g, b)
+            }
+            (false, _) => colors.get(bits.uint(colors.len() as u32)? as usize).copied().unwrap_or_default(),
+        };
+        let owner = match version {
+            3.. => owner_map.get(bits.uint_packed()? as usize).copied().unwrap_or(0),
+            _ => 0,
+        };
+
+        let name = assets.get(asset).cloned().unwrap_or_else(|| String::from("PB_DefaultBrick"));
+        bricks.push(Brick {
+            asset: match size {
+                Some([x, y, z]) => {
+                    BrickType::Procedural { asset: name.into(), size: BrickSize::new(x as u16, y as u16, z as u16) }
+                }
+                None => BrickType::Basic(name.into()),
+            },
+            owner_index: Some(owner),
+            position,
+            rotation,
+            direction,
+            collision,
+            visible,
+            color,
+            material: materials.get(material).cloned().unwrap_or_else(|| String::from("BMC_Plastic")).into(),
+            material_intensity,
+            ..Brick::default()
+        });
+    }
+
+    let components = match version {
+        8.. => {
+            let section = file.section()?;
+            ByteReader { bytes: &section, at: 0 }.i32().unwrap_or(0)
+        }
+        _ => 0,
+    };
+    Ok((bricks, components))
+}
+
+/// add the bricks from a .brs file to the main grid of a world, moved by `offset`, as a new revision.
+/// owners are matched up with the ones already in the world by user id
+pub fn import(db: &BrReader<Brdb>, input: &Path, offset: Position) -> Result<Output, Error> {
+    let data = fs::read(input)?;
+    let mut owners = owner_table(db)?;
+    let (mut bricks, components) =
+        read_bricks(&data, &mut owners).map_err(|error| Error::BadWorld(input.to_path_buf(), error.to_string()))?;
+    for brick in &mut bricks {
+        brick.position += offset;
+        owners.inc_bricks(brick.owner_index.unwrap_or(0));
+    }
+
+    let count = bricks.len();
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    add_main_grid_bricks(db, &mut pending, bricks, &owners)?;
+    let name = input.file_name().map_or_else(|| input.display().to_string(), |name| name.to_string_lossy().into_owned());
+    db.write_pending(format!("Import {name}"), pending)?;
+
+    let mut text = format!("imported {count} bricks from {}", input.display());
+    if components > 0 {
+        text.push_str(&format!("\nskipped the {components} kinds of components in the save"));
+    }
+    Ok(Output::new(text, json!({ "input": input, "bricks": count, "skipped": { "components": components } })))
+}
@@ -0,0 +1,39 @@
+use brdb::{BrReader, Brdb, fs::BrFs};
+use serde_json::{Value, json};
+
+use crate::{edit::to_text, error::Error, output::Output, pattern, traverse};
+
+/// print the decoded contents of several files one after another
+pub fn cat(db: &BrReader<Brdb>, fs: &BrFs, paths: &[String], header: bool) -> Result<Output, Error> {
+    let mut files = Vec::new();
+    for path in paths {
+        for path in pattern::expand(fs, path)? {
+            match traverse(fs, &path)? {
+                BrFs::File(_) => files.push(path),
+                // globs can match folders too, those just get skipped
+                _ if pattern::is_pattern(path.as_str()) => (),
+                _ => return Err(Error::Other(format!("/{path} is a folder"))),
+            }
+        }
+    }
+
+    let mut text = Vec::new();
+    let mut json = Vec::new();
+    for (i, path) in files.iter().enumerate() {
+        let ext = path.rsplit_once(".").map(|(_, ext)| ext).unwrap_or("");
+        let content = to_text(db, path, ext)?;
+
+        if header {
+            if i > 0 {
+                text.push(b'\n');
+            }
+            text.extend(format!("==> /{path} <==\n").as_bytes());
+        }
+        text.extend(&content);
+        if !content.ends_with(b"\n") {
+            text.push(b'\n');
+        }
+        json.push(json!({ "path": format!("/{path}"), "content": String::from_utf8_lossy(&content) }));
+    }
+    Ok(Output::new(text, Value::Array(json)))
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+use brdb::{BrFsReader, BrReader, Brdb, compression::decompress, tables::BrBlob};
+use indexmap::IndexMap;
+use serde_json::json;
+
+use crate::{
+    bricks::{chunk_bricks, grid_ids},
+    error::Error,
+    files_in, mps,
+    output::{Output, table},
+    traverse,
+};
+
+/// something wrong with the world, found by one of the checks
+struct Problem {
+    check: &'static str,
+    at: String,
+    problem: String,
+}
+
+/// run a query that finds broken rows and turn each one into a problem
+fn broken_rows(db: &Brdb, check: &'static str, sql: &str, problems: &mut Vec<Problem>) -> Result<(), Error> {
+    let mut statement = db.conn.prepare(sql)?;
+    let rows = statement.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+    for row in rows {
+        let (at, problem) = row?;
+        problems.push(Problem { check, at, problem });
+    }
+    Ok(())
+}
+
+/// every row points at things that exist, nothing is deleted before it was made, and revisions go forward in time
+fn check_tables(db: &Brdb, problems: &mut Vec<Problem>) -> Result<(), Error> {
+    let queries = [
+        (
+            "files",
+            "SELECT 'file ' || name || ' (' || file_id || ')', 'points at blob ' || content_id || ', which doesnt exist'
+            FROM files WHERE content_id IS NOT NULL AND content_id NOT IN (SELECT blob_id FROM blobs)",
+        ),
+        (
+            "files",
+            "SELECT 'file ' || name || ' (' || file_id || ')', 'is in folder ' || parent_id || ', which doesnt exist'
+            FROM files WHERE parent_id IS NOT NULL AND parent_id NOT IN (SELECT folder_id FROM folders)",
+        ),
+        (
+            "folders",
+            "SELECT 'folder ' || name || ' (' || folder_id || ')', 'is in folder ' || parent_id || ', which doesnt exist'
+            FROM folders WHERE parent_id IS NOT NULL AND parent_id NOT IN (SELECT folder_id FROM folders)",
+        ),
+        (
+            "files",
+            "SELECT 'file ' || name || ' (' || file_id || ')', 'was deleted before it was made'
+            FROM files WHERE deleted_at < created_at",
+        ),
+        (
+            "folders",
+            "SELECT 'folder ' || name || ' (' || folder_id || ')', 'was deleted before it was made'
+            FROM folders WHERE deleted_at < created_at",
+        ),
+        (
+            "blobs",
+            "SELECT 'blob ' || blob_id, 'is stored as a change from blob ' || delta_base_id || ', which doesnt exist'
+            FROM blobs WHERE delta_base_id IS NOT NULL AND delta_base_id NOT IN (SELECT blob_id FROM blobs)",
+        ),
+        (
+            "revisions",
+            "SELECT 'revision ' || later.revision_id, 'was saved before revision ' || earlier.revision_id || ', which comes before it'
+            FROM revisions later JOIN revisions earlier
+            ON earlier.revision_id = (SELECT MAX(revision_id) FROM revisions WHERE revision_id < later.revision_id)
+            WHERE later.created_at < earlier.created_at",
+        ),
+    ];
+    for (check, sql) in queries {
+        broken_rows(db, check, sql, problems)?;
+    }
+    let revisions: i64 = db.conn.query_row("SELECT COUNT(*) FROM revisions", [], |row| row.get(0))?;
+    if revisions == 0 {
+        problems.push(Problem { check: "revisions", at: String::from("world"), problem: String::from("has no revisions") });
+    }
+    Ok(())
+}
+
+/// every blob can be unpacked, is as big as it says and matches its hash. returns the ids of the broken ones
+/// and how many blobs there are
+fn check_blobs(db: &Brdb, problems: &mut Vec<Problem>) -> Result<(HashSet<i64>, usize), Error> {
+    let ids = db
+        .conn
+        .prepare("SELECT blob_id FROM blobs ORDER BY blob_id")?
+        .query_map([], |row| row.get::<_, i64>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut broken = HashSet::new();
+    for &id in &ids {
+        // brdb checks the hash itself when reading, so the blob is unpacked here to tell the problems apart
+        let blob = db.find_blob(id)?;
+        let content = match blob.compression {
+            0 => Ok(blob.content),
+            _ => decompress(&blob.content, blob.size_uncompressed as usize),
+        };
+        let problem = match content {
+            Err(error) => format!("couldnt be unpacked: {error}"),
+            Ok(content) if content.len() as i64 != blob.size_uncompressed => {
+                format!("is {} bytes, but says it's {}", content.len(), blob.size_uncompressed)
+            }
+            Ok(content) if BrBlob::hash(&content).as_slice() != blob.hash => String::from("doesnt match its hash"),
+            Ok(_) => continue,
+        };
+        problems.push(Problem { check: "blobs", at: format!("blob {id}"), problem });
+        broken.insert(id);
+    }
+    Ok((broken, ids.len()))
+}
+
+/// every .mps file brdb_cmd knows the layout of decodes with its schema. files in a broken blob are already
+/// reported, and files that fail the same way are reported together, since that's usually a broken schema.
+/// returns how many files there are
+fn check_files(db: &BrReader<Brdb>, broken: &HashSet<i64>, problems: &mut Vec<Problem>) -> Result<usize, Error> {
+    let fs = db.get_fs()?;
+    let files = files_in(&fs, "");
+    let mut failed: IndexMap<String, Vec<String>> = IndexMap::new();
+    for (path, file) in &files {
+        if !mps::known(path) || file.content_id.is_some_and(|id| broken.contains(&id)) {
+            continue;
+        }
+        if let Err(error) = mps::decode(db, path) {
+            failed.entry(error.to_string()).or_default().push(format!("/{path}"));
+        }
+    }
+    for (problem, paths) in failed {
+        let at = match paths.len() {
+            1 => paths[0].clone(),
+            n => format!("{} and {} more files", paths[0], n - 1),
+        };
+        problems.push(Problem { check: "files", at, problem });
+    }
+    Ok(files.len())
+}
+
+/// every chunk a grid's index lists has the files it needs, with as many bricks, components and wires in
+/// them as the index says, and there are no chunk files the index doesnt list
+fn check_chunks(db: &BrReader<Brdb>, problems: &mut Vec<Problem>) -> Result<(), Error> {
+    let root = db.get_fs()?;
+    for grid_id in grid_ids(db)? {
+        let grid = format!("World/0/Bricks/Grids/{grid_id}");
+        if db.find_file_by_path(format!("{grid}/ChunkIndex.mps"))?.is_none() {
+            continue;
+        }
+        // a broken index already shows up as a file that doesnt decode
+        let Ok(chunks) = db.brick_chunk_index(grid_id) else {
+            continue;
+        };
+        let mut problem = |at: String, problem: String| problems.push(Problem { check: "chunks", at, problem });
+        let count = |path: &str, props: &[&str]| -> Option<usize> {
+            let file = mps::decode(db, path).ok()?;
+            Some(props.iter().filter_map(|prop| file["soa"][prop].as_array().or(file[prop].as_array())).map(Vec::len).sum())
+        };
+        for chunk in &chunks {
+            let at = format!("grid {grid_id} chunk {}", chunk.index);
+            let path = |folder: &str| format!("{grid}/{folder}/{}.mps", chunk.index);
+            let mut compare = |what: &str, found: Option<usize>, expected: u32| {
+                if let Some(found) = found.filter(|found| *found != expected as usize) {
+                    problem(at.clone(), format!("has {found} {what}, but the index says {expected}"));
+                }
+            };
+            if db.find_file_by_path(path("Chunks"))?.is_none() {
+                compare("bricks", Some(0), chunk.num_bricks);
+            } else {
+                compare("bricks", chunk_bricks(db, grid_id, chunk.index).ok().map(|bricks| bricks.len()), chunk.num_bricks);
+            }
+            for (folder, props, what, expected) in [
+                ("Components", &["ComponentBrickIndices"][..], "components", chunk.num_components),
+                ("Wires", &["LocalWireSources", "RemoteWireSources"][..], "wires", chunk.num_wires),
+            ] {
+                match db.find_file_by_path(path(folder))? {
+                    Some(_) => compare(what, count(&path(folder), props), expected),
+                    None => compare(what, Some(0), expected),
+                }
+            }
+        }
+
+        let listed: HashSet<String> = chunks.iter().map(|chunk| format!("{}.mps", chunk.index)).collect();
+        for folder in ["Chunks", "Components", "Wires"] {
+            let Ok(fs) = traverse(&root, &format!("{grid}/{folder}")) else {
+                continue;
+            };
+            for (name, _) in files_in(fs, "").into_iter().filter(|(name, _)| !listed.contains(name)) {
+                problem(format!("/{grid}/{folder}/{name}"), String::from("isnt a chunk the index lists"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// look through the whole world for anything broken: rows that point nowhere, blobs that dont match their
+/// hash, .mps files that dont decode, chunks that dont match their index and revisions out of order.
+/// brdb_cmd exits with 1 if anything is found
+pub fn check(db: &BrReader<Brdb>) -> Result<Output, Error> {
+    let mut problems = Vec::new();
+    check_tables(db, &mut problems)?;
+    let (broken, blobs) = check_blobs(db, &mut problems)?;
+    let files = check_files(db, &broken, &mut problems)?;
+    check_chunks(db, &mut problems)?;
+
+    let json = json!({
+        "files": files,
+        "blobs": blobs,
+        "problems": problems
+            .iter()
+            .map(|problem| json!({ "check": problem.check, "at": problem.at, "problem": problem.problem }))
+            .collect::<Vec<_>>(),
+    });
+    if problems.is_empty() {
+        return Ok(Output::new(format!("no problems found in {files} files and {blobs} blobs"), json));
+    }
+    let mut rows = vec![vec![String::from("CHECK"), String::from("WHERE"), String::from("PROBLEM")]];
+    rows.extend(problems.iter().map(|problem| vec![problem.check.to_string(), problem.at.clone(), problem.problem.clone()]));
+    let text = format!("{}\n\nfound {} problems", table(&rows), problems.len());
+    Ok(Output::new(text, json).failed())
+}
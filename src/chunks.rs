@@ -0,0 +1,87 @@
+use std::cmp::Reverse;
+use brdb::{BrFsReader, BrReader, Brdb, ChunkIndex, ChunkMeta, fs::BrFs};
+use serde_json::{Value, json};
+
+use crate::{
+    blob_sizes,
+    bricks::{brick_json, bricks_table, chunk_bricks},
+    error::Error,
+    format_size,
+    output::{Output, table},
+    traverse,
+};
+
+/// the corners of the space a chunk covers in the world, from its lowest corner up to (not including) its highest
+pub fn chunk_bounds(chunk: &ChunkMeta) -> ([i32; 3], [i32; 3]) {
+    let size = chunk.chunk_size;
+    let min = [chunk.x as i32 * size, chunk.y as i32 * size, chunk.z as i32 * size];
+    (min, min.map(|min| min + size))
+}
+
+/// list the brick chunks of a grid with where they are, what's in them and how big they are
+pub fn list(db: &BrReader<Brdb>, grid: usize, by_size: bool) -> Result<Output, Error> {
+    let path = format!("World/0/Bricks/Grids/{grid}");
+    if db.find_file_by_path(format!("{path}/ChunkIndex.mps"))?.is_none() {
+        return Err(Error::NotFound(format!("/{path}")));
+    }
+    let root = db.get_fs()?;
+
+    let mut chunks = Vec::new();
+    for chunk in db.brick_chunk_index(grid)? {
+        let (compressed, size) = match traverse(&root, &format!("{path}/Chunks/{}.mps", chunk.index)) {
+            Ok(BrFs::File(file)) => match file.content_id {
+                Some(blob_id) => blob_sizes(db, blob_id)?,
+                None => (0, 0),
+            },
+            _ => (0, 0),
+        };
+        chunks.push((chunk, size, compressed));
+    }
+    if by_size {
+        chunks.sort_by_key(|(_, size, _)| Reverse(*size));
+    }
+
+    let mut rows = vec![["CHUNK", "BRICKS", "COMPONENTS", "WIRES", "SIZE", "BOUNDS"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for (chunk, size, compressed) in chunks {
+        let (min, max) = chunk_bounds(&chunk);
+        rows.push(vec![
+            chunk.index.to_string(),
+            chunk.num_bricks.to_string(),
+            chunk.num_components.to_string(),
+            chunk.num_wires.to_string(),
+            format_size(size),
+            format!("{},{},{} to {},{},{}", min[0], min[1], min[2], max[0], max[1], max[2]),
+        ]);
+        json.push(json!({
+            "chunk": chunk.index.to_string(),
+            "coordinate": [chunk.x, chunk.y, chunk.z],
+            "bricks": chunk.num_bricks,
+            "components": chunk.num_components,
+            "wires": chunk.num_wires,
+            "size": size,
+            "compressed": compressed,
+            "min": min,
+            "max": max,
+        }));
+    }
+    Ok(Output::new(table(&rows), Value::Array(json)))
+}
+
+/// list the bricks in the chunk at a coordinate, so nobody has to work out its file name
+pub fn get(db: &BrReader<Brdb>, grid: usize, chunk: ChunkIndex) -> Result<Output, Error> {
+    let path = format!("World/0/Bricks/Grids/{grid}");
+    if db.find_file_by_path(format!("{path}/ChunkIndex.mps"))?.is_none() {
+        return Err(Error::NotFound(format!("/{path}")));
+    }
+    if !db.brick_chunk_index(grid)?.iter().any(|meta| meta.index == chunk) {
+        return Err(Error::NotFound(format!("chunk {chunk} in grid {grid}")));
+    }
+    let bricks = chunk_bricks(db, grid, chunk)?;
+    let json = json!({
+        "chunk": chunk.to_string(),
+        "file": format!("/{path}/Chunks/{chunk}.mps"),
+        "bricks": bricks.iter().map(brick_json).collect::<Vec<_>>(),
+    });
+    Ok(Output::new(bricks_table(&bricks), json))
+}
@@ -0,0 +1,1312 @@
+use std::path::PathBuf;
+use brdb::{ChunkIndex, Position};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+/// the value name of the argument a --script fills in with its world. clap gives it to every argument
+/// called `world`, and commands that call theirs something else set it themselves
+pub const WORLD: &str = "WORLD";
+
+/// read and edit files inside a brickadia brdb world file
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// run the commands in a file one after another on a single world, one per line and without the
+    /// world's path. - reads them from stdin
+    #[arg(long, value_name = "FILE", requires = "world")]
+    pub script: Option<PathBuf>,
+    /// the world a --script runs on
+    #[arg(requires = "script", value_parser = existing_file)]
+    pub world: Option<PathBuf>,
+    /// keep running a --script after a command in it fails, instead of stopping there
+    #[arg(long, requires = "script")]
+    pub keep_going: bool,
+    /// look at the world as it was at this revision (see the revisions command)
+    #[arg(long, global = true, value_name = "ID")]
+    pub revision: Option<i64>,
+    /// show the output as json, for scripts and other programs. short for --output-format json
+    #[arg(long, global = true, conflicts_with = "output_format")]
+    pub json: bool,
+    /// show the output as text or json, whatever the config file says
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    pub output_format: Option<OutputFormat>,
+    /// when to color the output. auto colors it when it goes to a terminal
+    #[arg(long, global = true, value_enum, value_name = "WHEN", default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+    /// print long output straight to the terminal instead of through $PAGER
+    #[arg(long, global = true)]
+    pub no_pager: bool,
+    /// read a copy of the world, for worlds that are open in a running server
+    #[arg(long, global = true)]
+    pub snapshot: bool,
+    /// never open the world for writing, so commands that would change it refuse to run
+    #[arg(long, global = true)]
+    pub read_only: bool,
+    /// show what's going on: -v for the worlds that are opened, -vv for the files read and decoded in them, -vvv for everything
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// how to write what -v shows, and warnings
+    #[arg(long, global = true, value_enum, value_name = "FORMAT", default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+    /// dont show progress while long commands run
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+    /// read settings from this file instead of ~/.config/brdb_cmd/config.toml
+    #[arg(long, global = true, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+}
+
+/// when to color the output
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+/// how to show the output
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// how to write log lines on stderr
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    /// one json object per line
+    Json,
+}
+
+/// how to write out a graph
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// for graphviz, like `dot -Tsvg`
+    Dot,
+    Json,
+}
+
+/// what the nodes in an exported gltf file are
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GltfGroup {
+    /// one node per player, with everything they own
+    Owner,
+    /// one node per brick chunk
+    Chunk,
+}
+
+/// what colors the pixels of a rendered map
+#[derive(Clone, Copy, ValueEnum)]
+pub enum MapColor {
+    /// the color of the highest brick
+    Brick,
+    /// how high the highest brick is, from blue for low to red for high
+    Height,
+}
+
+/// which checksum hash uses
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum HashAlgo {
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgo {
+    pub fn name(self) -> &'static str {
+        match self {
+            HashAlgo::Sha256 => "sha256",
+            HashAlgo::Blake3 => "blake3",
+        }
+    }
+
+    /// what the manifest puts in front of each line
+    pub fn tag(self) -> &'static str {
+        match self {
+            HashAlgo::Sha256 => "SHA256",
+            HashAlgo::Blake3 => "BLAKE3",
+        }
+    }
+}
+
+/// which axis transform mirror flips
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum MirrorAxis {
+    X,
+    Y,
+}
+
+/// how read shows files that hold structured data
+#[derive(Default, Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    #[default]
+    Json,
+    Yaml,
+}
+
+/// which kind of entries ls shows
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum EntryType {
+    File,
+    Folder,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// list the files in a folder of the world
+    Ls {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// folder inside the world to list
+        #[arg(default_value = "/")]
+        path: String,
+        /// list everything in the subfolders too
+        #[arg(short = 'R', long)]
+        recursive: bool,
+        /// show the type, compressed and uncompressed size, and blob id of every entry
+        #[arg(short, long)]
+        long: bool,
+        /// put a / after folders
+        #[arg(short = 'F', long)]
+        classify: bool,
+        /// only show files, or only folders
+        #[arg(long = "type", value_name = "TYPE")]
+        kind: Option<EntryType>,
+        /// only show files with one of these extensions, like mps or mps,json,schema
+        #[arg(long, value_delimiter = ',', value_name = "EXT")]
+        ext: Vec<String>,
+    },
+    /// show every folder and file below a folder of the world as a tree
+    Tree {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// folder inside the world to start from
+        #[arg(default_value = "/")]
+        path: String,
+        /// show the size of each file
+        #[arg(short, long)]
+        sizes: bool,
+    },
+    /// search a folder of the world for files and folders by name
+    Find {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// folder inside the world to search in
+        root: String,
+        /// glob pattern the names have to match, like '*.mps'
+        pattern: String,
+        /// treat the pattern as a regular expression instead
+        #[arg(short = 'e', long)]
+        regex: bool,
+    },
+    /// search the contents of files for a regex. json and .mps files are searched as pretty json
+    Grep {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// regular expression to search for
+        pattern: String,
+        /// file, folder or glob pattern to search in
+        #[arg(default_value = "/")]
+        path: String,
+        /// ignore upper and lower case
+        #[arg(short, long)]
+        ignore_case: bool,
+        /// only print the paths of files that match
+        #[arg(short = 'l', long)]
+        files_with_matches: bool,
+    },
+    /// print the contents of a file in the world
+    Read {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file inside the world to read
+        path: String,
+        /// how to show json and .mps files
+        #[arg(long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        /// print json and .mps files exactly as they're stored, without decoding them
+        #[arg(long, conflicts_with = "format")]
+        raw: bool,
+        /// show any file as a hex dump, like xxd
+        #[arg(long, conflicts_with_all = ["format", "raw"])]
+        hex: bool,
+        /// save the output to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// print the decoded contents of several files one after another
+    Cat {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// files inside the world to print, which can be glob patterns
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// print a line with the path before each file
+        #[arg(short = 'H', long)]
+        header: bool,
+        /// save the output to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// show a summary of the world: its name, description, dates, game version, revisions, size and bricks
+    Info {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// save the world's preview image to a png file, or replace it with `thumbnail set`
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Thumbnail {
+        #[command(subcommand)]
+        command: Option<ThumbnailCommand>,
+        /// path to the world file
+        #[arg(value_parser = existing_file, required = true)]
+        world: Option<PathBuf>,
+        /// png file to save the thumbnail to
+        #[arg(required = true)]
+        output: Option<PathBuf>,
+    },
+    /// show the size, hash, blob and last revision of a file or folder in the world
+    Stat {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file or folder inside the world
+        path: String,
+    },
+    /// show how much space each entry in a folder of the world takes up, biggest first
+    Du {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// folder inside the world to measure
+        #[arg(default_value = "/")]
+        path: String,
+    },
+    /// print a checksum of every file in the world, or below a folder in it, of what's in them once unpacked.
+    /// the output is a manifest that --check can compare the world against later
+    Hash {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// folder or file inside the world to hash
+        #[arg(default_value = "/", conflicts_with = "check")]
+        path: String,
+        /// which hash to use, sha256 if not given. --check uses the one the manifest says it was made with
+        #[arg(long, value_enum)]
+        algo: Option<HashAlgo>,
+        /// compare the world against a manifest instead, exiting with 1 if any file doesnt match
+        #[arg(long, value_name = "MANIFEST")]
+        check: Option<PathBuf>,
+    },
+    /// open a file in $EDITOR and save it back into the world as a new revision
+    Edit {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file inside the world to edit
+        path: String,
+    },
+    /// copy a file from disk into the world as a new revision, replacing it if it exists
+    Write {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file on disk to copy into the world
+        #[arg(value_parser = existing_file)]
+        local: PathBuf,
+        /// where to put it inside the world. missing folders are created
+        path: String,
+    },
+    /// copy every file in the world into a folder on disk
+    Extract {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// folder to extract into. it's created if it doesn't exist
+        output: PathBuf,
+        /// decode .mps files to json, saved next to where the .mps would be as `<name>.mps.json`
+        #[arg(short, long)]
+        decode: bool,
+    },
+    /// copy a file or folder to somewhere else in the world, as a new revision
+    Cp {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file or folder inside the world to copy
+        src: String,
+        /// where to copy it to. if this is a folder, the copy goes inside it
+        dst: String,
+        /// copy folders and everything in them
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// copy a file or folder from one world into another, as a new revision in the other world
+    CopyTo {
+        /// path to the world file to copy from
+        #[arg(value_name = WORLD, value_parser = existing_file)]
+        from: PathBuf,
+        /// file or folder inside that world to copy
+        src: String,
+        /// path to the world file to copy into
+        #[arg(value_parser = existing_file)]
+        to: PathBuf,
+        /// where to put it in that world. if this is a folder, the copy goes inside it
+        dst: String,
+        /// copy folders and everything in them
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// put a file back the way it is in a backup or another world, as a new revision, without rolling back
+    /// anything else
+    Restore {
+        /// path to the backup's world file. --revision applies to this one
+        #[arg(value_parser = existing_file)]
+        backup: PathBuf,
+        /// file or folder inside the backup to restore, which goes back to the same place
+        path: String,
+        /// path to the world file to restore it into
+        #[arg(long, value_parser = existing_file)]
+        into: PathBuf,
+        /// restore folders and everything in them
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// move or rename a file or folder in the world, as a new revision
+    #[command(alias = "rename")]
+    Mv {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file or folder inside the world to move
+        src: String,
+        /// where to move it to. if this is a folder, it goes inside it
+        dst: String,
+    },
+    /// delete a file or folder from the world, as a new revision
+    Rm {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// file or folder inside the world to delete, which can be a glob pattern
+        path: String,
+        /// delete folders and everything in them
+        #[arg(short, long)]
+        recursive: bool,
+        /// dont ask before deleting
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// write the files from a folder made by extract into a world, as a new revision
+    Pack {
+        /// folder to read the files from. .mps.json files are encoded back to .mps
+        input: PathBuf,
+        /// path to the world file. it's created if it doesn't exist
+        #[arg(value_parser = world_path)]
+        world: PathBuf,
+    },
+    /// list every saved revision of the world
+    Revisions {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// save a new revision that matches an older one, undoing everything after it without losing history
+    Rollback {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// revision to go back to (see the revisions command)
+        id: i64,
+    },
+    /// clear out chunks without bricks, components and wires on bricks that are gone, and chunk files the
+    /// world doesnt use anymore, as a new revision
+    Prune {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// shrink the world file by dropping data nothing uses anymore, and optionally old revisions
+    Compact {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// forget every revision but the newest N, along with what only they could see. this cant be undone
+        #[arg(long, value_name = "N")]
+        keep_revisions: Option<usize>,
+        /// dont ask before forgetting revisions
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// copy the world into a backups folder, safely even while a server has it open, under a name with the time
+    Backup {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the folder to put backups in, a backups folder next to the world by default
+        #[arg(long, value_name = "DIR")]
+        dir: Option<PathBuf>,
+        /// delete the oldest backups of this world in the folder until only the newest N are left
+        #[arg(long, value_name = "N")]
+        keep: Option<usize>,
+    },
+    /// look for anything broken in the world, like blobs that dont match their hash, files that dont decode
+    /// or chunks that dont match their index. exits with 1 if anything is found
+    Check {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// fully decode .mps files with the world's schemas, showing the struct and byte offset any of them fail at
+    Validate {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// files or folders inside the world to validate, which can be glob patterns
+        #[arg(default_value = "/")]
+        paths: Vec<String>,
+    },
+    /// show which files are only in one of two worlds, or different between them
+    Diff {
+        /// path to the world file to compare from. --revision applies to this one
+        #[arg(value_name = WORLD, value_parser = existing_file)]
+        a: PathBuf,
+        /// path to the world file to compare to
+        #[arg(value_parser = existing_file)]
+        b: PathBuf,
+        /// also show what changed inside json and .mps files
+        #[arg(short, long)]
+        content: bool,
+        /// save the output to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// show which files were added, removed or changed between two revisions of the world
+    DiffRev {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// revision to compare from
+        a: i64,
+        /// revision to compare to
+        b: i64,
+        /// also show what changed inside json and .mps files
+        #[arg(short, long)]
+        content: bool,
+        /// save the output to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// keep watching a world, like one a server is running, and print which files changed every time it saves
+    Watch {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// how many seconds to wait between looking at the file
+        #[arg(long, value_name = "SECONDS", default_value_t = 2)]
+        interval: u64,
+    },
+    /// combine the bricks, entities, owners and other files of two worlds into a new world
+    Merge {
+        /// path to the world to merge into. --revision applies to this one
+        #[arg(value_name = WORLD, value_parser = existing_file)]
+        base: PathBuf,
+        /// path to the world whose bricks and entities get added to it
+        #[arg(value_parser = existing_file)]
+        other: PathBuf,
+        /// path to write the merged world to
+        out: PathBuf,
+        /// when a file is different in both worlds, keep the one from the other world instead of the base
+        #[arg(long)]
+        prefer_other: bool,
+        /// move the other world's bricks and entities by this much, like 1000,0,0
+        #[arg(long, value_name = "X,Y,Z", value_parser = offset, default_value = "0,0,0", allow_hyphen_values = true)]
+        offset: Position,
+    },
+    /// list the bricks in the world with their position, size, asset, color and owner, or search and remove them with `bricks find` and `bricks remove`
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Bricks {
+        #[command(subcommand)]
+        command: Option<BricksCommand>,
+        /// path to the world file
+        #[arg(value_parser = existing_file, required = true)]
+        world: Option<PathBuf>,
+        /// which brick grid to list. 1 is the main one, the others belong to entities
+        #[arg(long, default_value_t = 1)]
+        grid: usize,
+        /// only list this many bricks
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+    /// list the players that own bricks and entities in the world, or hand one's bricks to another with `owners reassign`
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+    Owners {
+        #[command(subcommand)]
+        command: Option<OwnersCommand>,
+        /// path to the world file
+        #[arg(value_parser = existing_file, required = true)]
+        world: Option<PathBuf>,
+    },
+    /// work with the chunks the bricks of a grid are saved in
+    Chunks {
+        #[command(subcommand)]
+        command: ChunksCommand,
+    },
+    /// save the world in another format
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+    /// add a save in another format to the world
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+    /// draw pictures of the world
+    Render {
+        #[command(subcommand)]
+        command: RenderCommand,
+    },
+    /// move, turn or mirror everything in the world, as a new revision
+    Transform {
+        #[command(subcommand)]
+        command: TransformCommand,
+    },
+    /// copy the bricks in part of the world out of it
+    Region {
+        #[command(subcommand)]
+        command: RegionCommand,
+    },
+    /// show the components on bricks, like lights and wire gates, with their properties
+    Components {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// a brick id from the bricks command, like 0_0_0:12, or a components chunk file.
+        /// leave it out to show every component in the grid
+        target: Option<String>,
+        /// which brick grid the brick is in. 1 is the main one, the others belong to entities
+        #[arg(long, default_value_t = 1)]
+        grid: usize,
+    },
+    /// work with the wires between components
+    Wires {
+        #[command(subcommand)]
+        command: WiresCommand,
+    },
+    /// show or change the world's environment settings
+    Env {
+        #[command(subcommand)]
+        command: EnvCommand,
+    },
+    /// list the minigames saved in a world, or move one to another world
+    Minigames {
+        #[command(subcommand)]
+        command: MinigamesCommand,
+    },
+    /// list the entities in the world, or show everything about one of them
+    Entities {
+        #[command(subcommand)]
+        command: EntitiesCommand,
+    },
+    /// look at the schemas that describe how the .mps files in the world are laid out
+    Schema {
+        #[command(subcommand)]
+        command: SchemaCommand,
+    },
+    /// count things in the world
+    Stats {
+        #[command(subcommand)]
+        command: StatsCommand,
+    },
+    /// run a sql query against the sqlite database the world is stored in, like 'SELECT * FROM revisions'
+    Sql {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the query to run. only queries that dont change anything are allowed
+        query: String,
+    },
+    /// open the world once and explore it with an interactive shell
+    Shell {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// browse the world's files in two panes, with a preview of the selected file
+    Tui {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// show the world's files as a read-only folder that any program can open, until ctrl-c is pressed
+    #[cfg(feature = "mount")]
+    Mount {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the empty folder to show them in
+        mountpoint: PathBuf,
+        /// put a decoded <name>.mps.json next to every .mps file
+        #[arg(short, long)]
+        decode: bool,
+    },
+    /// answer http requests about the world with json, for dashboards and bots, until ctrl-c is pressed
+    Serve {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the port to listen on
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        /// the address to listen on. use 0.0.0.0 to let other computers connect
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+    },
+    /// find the worlds brickadia has saved
+    Worlds {
+        #[command(subcommand)]
+        command: WorldsCommand,
+    },
+    /// show where the config file is and what's set in it
+    Config,
+    /// print a script that lets your shell tab complete commands and options
+    Completions {
+        /// which shell to make the script for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// list the files and folders in a world that start with a partial path, used by the completion scripts
+    #[command(name = "__complete-path", hide = true)]
+    CompletePath {
+        /// path to the world file
+        world: PathBuf,
+        /// the path typed so far
+        #[arg(default_value = "")]
+        partial: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BricksCommand {
+    /// list the bricks that match every filter given
+    Find {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// which brick grid to search. 1 is the main one, the others belong to entities
+        #[arg(long, default_value_t = 1)]
+        grid: usize,
+        #[command(flatten)]
+        filter: BrickFilter,
+        /// also save the bricks found to a new world, or to a json file if it ends in .json
+        #[arg(long, value_name = "FILE")]
+        export: Option<PathBuf>,
+    },
+    /// delete the bricks on the main grid that match every filter given, as a new revision. their components
+    /// and wires go with them
+    Remove {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        #[command(flatten)]
+        filter: BrickFilter,
+        /// only list the bricks that would be removed
+        #[arg(long)]
+        dry_run: bool,
+        /// dont ask before removing
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// swap one color for another on every brick of the main grid, as a new revision
+    Recolor {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the color to replace, as #rrggbb. give it more than once to swap several colors at the same time
+        #[arg(long, value_name = "COLOR", value_parser = hex_color, required = true)]
+        from: Vec<[u8; 3]>,
+        /// the color it turns into. every --from needs a --to, and they pair up in the order they're given
+        #[arg(long, value_name = "COLOR", value_parser = hex_color, required = true)]
+        to: Vec<[u8; 3]>,
+    },
+    /// swap one material for another on the bricks of the main grid, as a new revision. the filters narrow
+    /// down which bricks change, like only one player's or only the ones in a box
+    Rematerial {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the material to replace, like glass or BMC_Glass
+        #[arg(long)]
+        from: String,
+        /// the material it turns into
+        #[arg(long)]
+        to: String,
+        #[command(flatten)]
+        filter: BrickFilter,
+    },
+}
+
+/// which bricks a command works on. a brick has to pass every filter that's given
+#[derive(Args, Default)]
+pub struct BrickFilter {
+    /// bricks with this color, as #rrggbb. its not called --color since that one decides whether output is colored
+    #[arg(long, value_name = "COLOR", value_parser = hex_color)]
+    pub rgb: Option<[u8; 3]>,
+    /// how far each of red, green and blue can be from --rgb, from 0 to 255
+    #[arg(long, default_value_t = 0, requires = "rgb")]
+    pub tolerance: u8,
+    /// bricks placed by this player, by name or user id
+    #[arg(long, value_name = "NAME_OR_ID")]
+    pub owner: Option<String>,
+    /// bricks with this asset, like PB_DefaultBrick
+    #[arg(long)]
+    pub asset: Option<String>,
+    /// bricks with this material, like glow or BMC_Glow
+    #[arg(long)]
+    pub material: Option<String>,
+    /// bricks at or above this corner, in world units
+    #[arg(long, value_name = "X,Y,Z", value_parser = offset, allow_hyphen_values = true)]
+    pub min: Option<Position>,
+    /// bricks at or below this corner
+    #[arg(long, value_name = "X,Y,Z", value_parser = offset, allow_hyphen_values = true)]
+    pub max: Option<Position>,
+    /// bricks saved in this chunk, as x,y,z or x_y_z
+    #[arg(long, value_parser = chunk, allow_hyphen_values = true)]
+    pub chunk: Option<ChunkIndex>,
+}
+
+impl BrickFilter {
+    /// whether no filter was given, so every brick would match
+    pub fn is_empty(&self) -> bool {
+        self.rgb.is_none()
+            && self.owner.is_none()
+            && self.asset.is_none()
+            && self.material.is_none()
+            && self.min.is_none()
+            && self.max.is_none()
+            && self.chunk.is_none()
+    }
+}
+
+#[derive(Subcommand)]
+pub enum OwnersCommand {
+    /// make every brick on the main grid that one player owns belong to another, as a new revision.
+    /// who originally placed them is kept, and so are the owners of entities
+    Reassign {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the player whose bricks are handed over, by name or user id
+        from: String,
+        /// the player who gets them
+        to: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChunksCommand {
+    /// list every chunk with its coordinate, what's in it, its size and the space it covers
+    List {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// which brick grid to list the chunks of. 1 is the main one, the others belong to entities
+        #[arg(long, default_value_t = 1)]
+        grid: usize,
+        /// sort the chunks by size, biggest first
+        #[arg(short = 'S', long)]
+        by_size: bool,
+    },
+    /// list the bricks in one chunk, found by its coordinate
+    Get {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the chunk's coordinate, like 0,0,0 or -1_0_2
+        #[arg(value_name = "X,Y,Z", value_parser = chunk, allow_hyphen_values = true)]
+        chunk: ChunkIndex,
+        /// which brick grid the chunk is in. 1 is the main one, the others belong to entities
+        #[arg(long, default_value_t = 1)]
+        grid: usize,
+        /// save the bricks to this file instead of printing them
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommand {
+    /// save the bricks on the main grid as a .brs file, the save format brickadia used before .brdb
+    Brs {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the .brs file to save to
+        out: PathBuf,
+    },
+    /// save the bricks on the main grid as a triangle mesh in a .obj file, for blender and other 3d programs
+    Obj {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the .obj file to save to. a .mtl file with the same name is saved next to it
+        out: PathBuf,
+        /// give every vertex its brick's color
+        #[arg(long)]
+        vertex_colors: bool,
+        /// how much to scale positions by. brickadia units are centimeters, so the default gives meters
+        #[arg(long, default_value_t = 0.01)]
+        scale: f32,
+    },
+    /// save the bricks on the main grid as a binary gltf (.glb) file, which most 3d viewers and engines open
+    Gltf {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the .glb file to save to
+        out: PathBuf,
+        /// what to split the bricks into nodes by
+        #[arg(long, value_enum, default_value_t = GltfGroup::Owner)]
+        group_by: GltfGroup,
+        /// how much to scale positions by. brickadia units are centimeters, so the default gives meters
+        #[arg(long, default_value_t = 0.01)]
+        scale: f32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ImportCommand {
+    /// add the bricks in a .brs file to the main grid, as a new revision
+    Brs {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the .brs file to import
+        #[arg(value_parser = existing_file)]
+        input: PathBuf,
+        /// move the imported bricks by this much
+        #[arg(long, value_name = "X,Y,Z", value_parser = offset, default_value = "0,0,0", allow_hyphen_values = true)]
+        offset: Position,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RenderCommand {
+    /// draw the main grid from above as a png, like a map
+    Map {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the png file to save to
+        out: PathBuf,
+        /// how many world units each pixel covers. a 1x1 brick is 10 units wide
+        #[arg(long, default_value_t = 10)]
+        scale: u32,
+        /// what to color the map by
+        #[arg(long, value_enum, default_value_t = MapColor::Brick)]
+        color_by: MapColor,
+        /// the top left corner of the map, in world units. defaults to fitting every brick
+        #[arg(long, value_name = "X,Y", value_parser = point, allow_hyphen_values = true)]
+        min: Option<[i32; 2]>,
+        /// the bottom right corner of the map
+        #[arg(long, value_name = "X,Y", value_parser = point, allow_hyphen_values = true)]
+        max: Option<[i32; 2]>,
+    },
+    /// draw where the bricks on the main grid are as a png, from blue for few to red for many
+    Heatmap {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the png file to save to
+        out: PathBuf,
+        /// count bricks in cells this many world units wide instead of in chunks. slower, since every brick is read
+        #[arg(long)]
+        cell: Option<u32>,
+        /// how many pixels wide each cell is
+        #[arg(long, default_value_t = 16)]
+        pixels: u32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TransformCommand {
+    /// move every brick on the main grid and every entity by the same amount. bricks are put in the chunk
+    /// theyve moved into, along with their components and wires
+    Translate {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// how far to move everything, in world units
+        #[arg(value_name = "X,Y,Z", value_parser = offset, allow_hyphen_values = true)]
+        offset: Position,
+    },
+    /// turn every brick on the main grid and every entity around the z axis through 0,0, the same way a
+    /// brick's own rotation turns it. bricks are put in the chunk theyve turned into
+    Rotate {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// how far to turn, 90, 180 or 270 degrees
+        #[arg(value_name = "DEGREES", value_parser = quarter_turns)]
+        turns: u8,
+    },
+    /// flip every brick on the main grid and every entity to the other side of x = 0 or y = 0, for making one
+    /// half of a symmetric build from the other. bricks are turned so they still look right
+    Mirror {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the axis whose coordinates get flipped
+        #[arg(value_enum)]
+        axis: MirrorAxis,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RegionCommand {
+    /// save the bricks inside a box to a new world, or to json if the file ends in .json
+    Export {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// one corner of the box, in world units
+        #[arg(long, value_name = "X,Y,Z", value_parser = offset, allow_hyphen_values = true)]
+        min: Position,
+        /// the opposite corner of the box. bricks on its edges are included
+        #[arg(long, value_name = "X,Y,Z", value_parser = offset, allow_hyphen_values = true)]
+        max: Position,
+        /// the world or json file to save them to
+        out: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WiresCommand {
+    /// write out every wire in the world as a graph, with components as nodes and wires as edges
+    Export {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// what kind of graph to write
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// save the graph to this file instead of printing it
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ThumbnailCommand {
+    /// replace the preview image with a png file
+    Set {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the png image to use
+        #[arg(value_parser = existing_file)]
+        image: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EnvCommand {
+    /// show the environment settings
+    Show {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// change one setting and save it as a new revision
+    Set {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// json pointer to the setting, like /environment
+        pointer: String,
+        /// the new value, read as json if it can be and as a string if not
+        value: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MinigamesCommand {
+    /// list the minigames saved in the world
+    List {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// save a minigame to a file, to import it into another world
+    Export {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// index of the minigame, from minigames list
+        index: usize,
+        /// file to save it to
+        file: PathBuf,
+    },
+    /// put a minigame exported from another world into this one
+    Import {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the exported minigame file
+        #[arg(value_parser = existing_file)]
+        file: PathBuf,
+        /// index to save it at. a minigame already there gets replaced
+        #[arg(long, default_value_t = 0)]
+        index: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EntitiesCommand {
+    /// list every entity with its type, location, rotation and owner
+    List {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// show everything saved about one entity
+    Show {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// id of the entity, from entities list
+        id: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum WorldsCommand {
+    /// list every world in brickadia's saved worlds folder with its size and when it was last saved
+    List {
+        /// look in this folder instead of the one brickadia saves to
+        #[arg(long)]
+        dir: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StatsCommand {
+    /// how many bricks and components each player has placed, most first
+    Owners {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// how many bricks use each color, most first
+    Colors {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// also save every color as a square in a png, most used first
+        #[arg(long, value_name = "PNG")]
+        palette: Option<PathBuf>,
+    },
+    /// how many bricks use each material, most first
+    Materials {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// count each material and color together
+        #[arg(long)]
+        by_color: bool,
+        /// print csv instead of a table, for spreadsheets
+        #[arg(long)]
+        csv: bool,
+    },
+    /// how many bricks use each brick asset, most first, and whether the game ships with it
+    Assets {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// how much space sharing blobs between files and revisions saves, and which contents are stored twice anyway
+    Dedup {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// how big the files in each folder and of each extension are with and without compression
+    Compression {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SchemaCommand {
+    /// list every .schema file with how many enums, variants and structs it has
+    List {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+    },
+    /// show the enums, variants and structs in one schema, with every value and field
+    Show {
+        /// path to the world file
+        #[arg(value_parser = existing_file)]
+        world: PathBuf,
+        /// the schema's path, or the end of it like Owners or Bricks/ChunksShared
+        name: String,
+    },
+    /// compare the schemas in two worlds, like from before and after a game update
+    Diff {
+        /// path to the world file to compare from. --revision applies to this one
+        #[arg(value_name = WORLD, value_parser = existing_file)]
+        a: PathBuf,
+        /// path to the world file to compare to
+        #[arg(value_parser = existing_file)]
+        b: PathBuf,
+    },
+}
+
+impl Command {
+    /// whether the command saves a new revision into the world it opens.
+    /// every other command gets its worlds opened read-only, so it cant change them by accident
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            Command::Edit { .. }
+                | Command::Write { .. }
+                | Command::Cp { .. }
+                | Command::Mv { .. }
+                | Command::Rm { .. }
+                | Command::Pack { .. }
+                | Command::Rollback { .. }
+                | Command::Prune { dry_run: false, .. }
+                | Command::Compact { .. }
+                | Command::Bricks { command: Some(BricksCommand::Remove { dry_run: false, .. }), .. }
+                | Command::Bricks { command: Some(BricksCommand::Recolor { .. } | BricksCommand::Rematerial { .. }), .. }
+                | Command::Owners { command: Some(OwnersCommand::Reassign { .. }), .. }
+                | Command::Transform { .. }
+                | Command::Thumbnail { command: Some(ThumbnailCommand::Set { .. }), .. }
+                | Command::Env { command: EnvCommand::Set { .. } }
+                | Command::Import { .. }
+                | Command::Minigames { command: MinigamesCommand::Import { .. } }
+        )
+    }
+
+    /// whether the output can get long enough to be worth showing in a pager
+    pub fn pages(&self) -> bool {
+        matches!(
+            self,
+            Command::Ls { recursive: true, .. }
+                | Command::Tree { .. }
+                | Command::Read { .. }
+                | Command::Revisions { .. }
+                | Command::Sql { .. }
+                | Command::Bricks { .. }
+                | Command::Check { .. }
+                | Command::Hash { .. }
+                | Command::Validate { .. }
+                | Command::Stats { command: StatsCommand::Dedup { .. } }
+                | Command::Schema { .. }
+                | Command::Chunks { .. }
+        )
+    }
+
+    /// the file to save the output to instead of printing it, for commands that have -o
+    pub fn output_file(&self) -> Option<PathBuf> {
+        match self {
+            Command::Read { output, .. }
+            | Command::Cat { output, .. }
+            | Command::Diff { output, .. }
+            | Command::DiffRev { output, .. }
+            | Command::Chunks { command: ChunksCommand::Get { output, .. } }
+            | Command::Wires { command: WiresCommand::Export { output, .. } } => output.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// a world given on the command line, where `@name` is one from the config and `@` alone is the default world
+fn world_path(path: &str) -> Result<PathBuf, String> {
+    match path.strip_prefix('@') {
+        Some(alias) => crate::config::world(alias),
+        None => Ok(PathBuf::from(path)),
+    }
+}
+
+/// make sure a path given on the command line points to an existing file
+fn existing_file(path: &str) -> Result<PathBuf, String> {
+    let path = world_path(path)?;
+    if path.is_file() {
+        Ok(path)
+    } else {
+        Err(format!("no such file: {}", path.display()))
+    }
+}
+
+/// parse a position given as x,y,z
+fn offset(value: &str) -> Result<Position, String> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        [x, y, z] => match (x.parse(), y.parse(), z.parse()) {
+            (Ok(x), Ok(y), Ok(z)) => Ok(Position::new(x, y, z)),
+            _ => Err(format!("not a whole number position: {value}")),
+        },
+        _ => Err(format!("expected x,y,z: {value}")),
+    }
+}
+
+/// parse a turn of 90, 180 or 270 degrees into how many quarter turns it is
+fn quarter_turns(value: &str) -> Result<u8, String> {
+    match value.trim_end_matches('°') {
+        "90" => Ok(1),
+        "180" => Ok(2),
+        "270" => Ok(3),
+        _ => Err(format!("expected 90, 180 or 270 degrees: {value}")),
+    }
+}
+
+/// parse a color given as #rrggbb, with or without the #
+fn hex_color(value: &str) -> Result<[u8; 3], String> {
+    let hex = value.trim_start_matches('#');
+    match (hex.len(), u32::from_str_radix(hex, 16)) {
+        (6, Ok(rgb)) => Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]),
+        _ => Err(format!("expected a color like #ff8800: {value}")),
+    }
+}
+
+/// parse a 2d position given as x,y
+fn point(value: &str) -> Result<[i32; 2], String> {
+    let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        [x, y] => match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => Ok([x, y]),
+            _ => Err(format!("not a whole number position: {value}")),
+        },
+        _ => Err(format!("expected x,y: {value}")),
+    }
+}
+
+/// parse a chunk coordinate given as x,y,z or the way chunk files are named, like x_y_z
+fn chunk(value: &str) -> Result<ChunkIndex, String> {
+    let separator = if value.contains(',') { ',' } else { '_' };
+    let parts: Vec<&str> = value.split(separator).map(str::trim).collect();
+    match parts.as_slice() {
+        [x, y, z] => match (x.parse(), y.parse(), z.parse()) {
+            (Ok(x), Ok(y), Ok(z)) => Ok(ChunkIndex::new(x, y, z)),
+            _ => Err(format!("not a chunk coordinate: {value}")),
+        },
+        _ => Err(format!("expected x,y,z: {value}")),
+    }
+}
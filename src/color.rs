@@ -0,0 +1,132 @@
+use std::{env, io, io::IsTerminal};
+
+use crate::cli::ColorChoice;
+
+const RESET: &str = "\x1b[0m";
+const KEY: &str = "\x1b[1;34m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const KEYWORD: &str = "\x1b[35m";
+const NAME: &str = "\x1b[1;33m";
+const FOLDER: &str = "\x1b[1;34m";
+
+/// whether to color the output, `auto` only does it on a terminal and when $NO_COLOR isn't set
+pub fn enabled(choice: ColorChoice, to_file: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => !to_file && io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+    }
+}
+
+fn paint(buf: &mut String, color: &str, text: &str) {
+    buf.push_str(color);
+    buf.push_str(text);
+    buf.push_str(RESET);
+}
+
+/// color a folder name in a listing, the same blue ls uses
+pub fn folder(name: &str) -> String {
+    let mut buf = String::new();
+    paint(&mut buf, FOLDER, name);
+    buf
+}
+
+/// a little block of a true color, for showing brick colors in a terminal
+pub fn swatch(r: u8, g: u8, b: u8) -> String {
+    format!("\x1b[48;2;{r};{g};{b}m    {RESET}")
+}
+
+/// color the keys, strings, numbers and literals of pretty printed json
+pub fn json(text: &str) -> String {
+    let mut buf = String::with_capacity(text.len() * 2);
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '"' => {
+                let mut end = start + 1;
+                let mut escaped = false;
+                for (i, c) in chars.by_ref() {
+                    end = i + c.len_utf8();
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => (),
+                    }
+                }
+                // a string is a key if a colon comes right after it
+                let is_key = text[end..].trim_start().starts_with(':');
+                paint(&mut buf, if is_key { KEY } else { STRING }, &text[start..end]);
+            }
+            '-' | '0'..='9' => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')) {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                paint(&mut buf, NUMBER, &text[start..end]);
+            }
+            't' | 'f' | 'n' => {
+                let mut end = start + 1;
+                while let Some(&(i, c)) = chars.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    end = i + 1;
+                    chars.next();
+                }
+                paint(&mut buf, KEYWORD, &text[start..end]);
+            }
+            c => buf.push(c),
+        }
+    }
+    buf
+}
+
+/// color the keywords, names, fields and types of a schema
+pub fn schema(text: &str) -> String {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        let mut buf = String::from(indent);
+
+        if let Some((keyword, rest)) = trimmed.split_once(' ')
+            && matches!(keyword, "struct" | "enum" | "variant")
+        {
+            // `struct Name {`
+            paint(&mut buf, KEYWORD, keyword);
+            buf.push(' ');
+            let (name, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            paint(&mut buf, NAME, name);
+            if !rest.is_empty() {
+                buf.push(' ');
+                buf.push_str(rest);
+            }
+        } else if let Some((field, ty)) = trimmed.split_once(':') {
+            // `Field: type,`
+            paint(&mut buf, KEY, field);
+            buf.push_str(": ");
+            let ty = ty.trim_start();
+            let name = ty.trim_end_matches(',');
+            paint(&mut buf, STRING, name);
+            buf.push_str(&ty[name.len()..]);
+        } else if let Some((variant, value)) = trimmed.split_once(" = ") {
+            // `Variant = 0,`
+            paint(&mut buf, KEY, variant);
+            buf.push_str(" = ");
+            let number = value.trim_end_matches(',');
+            paint(&mut buf, NUMBER, number);
+            buf.push_str(&value[number.len()..]);
+        } else {
+            buf.push_str(trimmed);
+        }
+        lines.push(buf);
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,81 @@
+use std::{fs, path::Path};
+use brdb::Brdb;
+use serde_json::json;
+
+use crate::{confirm, error::Error, format_size, live, output::Output, revisions::all_revisions};
+
+/// forget every revision before the newest `keep`, along with the files and folders that were already deleted
+/// by the oldest one left, since no revision can see them anymore. returns how many revisions and entries went
+fn drop_old_revisions(db: &Brdb, keep: usize, yes: bool) -> Result<(usize, usize), Error> {
+    let revisions = all_revisions(db)?;
+    if revisions.len() <= keep {
+        return Ok((0, 0));
+    }
+    let oldest = &revisions[revisions.len() - keep];
+    let question = format!("forget the {} revisions before revision {}? they cant be brought back", revisions.len() - keep, oldest.id);
+    if !yes && !confirm(&question, "--yes")? {
+        return Ok((0, 0));
+    }
+
+    let tx = db.conn.unchecked_transaction()?;
+    let mut entries = tx.execute("DELETE FROM files WHERE deleted_at <= ?1", [oldest.created_at])?;
+    // folders can only go once nothing is left inside them, so deleted folders in deleted folders take a few rounds
+    loop {
+        let removed = tx.execute(
+            "DELETE FROM folders WHERE deleted_at <= ?1
+                AND folder_id NOT IN (SELECT parent_id FROM files WHERE parent_id IS NOT NULL)
+                AND folder_id NOT IN (SELECT parent_id FROM folders WHERE parent_id IS NOT NULL)",
+            [oldest.created_at],
+        )?;
+        if removed == 0 {
+            break;
+        }
+        entries += removed;
+    }
+    let revisions = tx.execute("DELETE FROM revisions WHERE revision_id < ?1", [oldest.id])?;
+    tx.commit()?;
+    Ok((revisions, entries))
+}
+
+/// make a world file smaller: forget old revisions if `keep` says how many to keep, drop the blobs no file
+/// points at anymore, then have sqlite rebuild the file so the free space actually goes away
+pub fn compact(path: &Path, keep: Option<usize>, yes: bool) -> Result<Output, Error> {
+    if keep == Some(0) {
+        return Err(Error::Other(String::from("--keep-revisions has to be at least 1")));
+    }
+    live::check_writable(path)?;
+    let before = fs::metadata(path)?.len();
+    let db = Brdb::open(path).map_err(|error| Error::BadWorld(path.to_path_buf(), error.to_string()))?;
+
+    let (revisions, entries) = match keep {
+        Some(keep) => drop_old_revisions(&db, keep, yes)?,
+        None => (0, 0),
+    };
+    // blobs can also be the base another blob is stored as a change from, so those stay as well
+    let blobs = db.conn.execute(
+        "WITH RECURSIVE used(blob_id) AS (
+            SELECT content_id FROM files WHERE content_id IS NOT NULL
+            UNION SELECT blobs.delta_base_id FROM blobs JOIN used USING (blob_id) WHERE blobs.delta_base_id IS NOT NULL
+        )
+        DELETE FROM blobs WHERE blob_id NOT IN (SELECT blob_id FROM used)",
+        [],
+    )?;
+    db.conn.execute_batch("VACUUM")?;
+    drop(db);
+    let after = fs::metadata(path)?.len();
+
+    let text = format!(
+        "forgot {revisions} revisions and {entries} deleted files and folders, dropped {blobs} unused blobs\n{} -> {} ({} smaller)",
+        format_size(before),
+        format_size(after),
+        format_size(before.saturating_sub(after))
+    );
+    let json = json!({
+        "revisions": revisions,
+        "entries": entries,
+        "blobs": blobs,
+        "size_before": before,
+        "size_after": after,
+    });
+    Ok(Output::new(text, json))
+}
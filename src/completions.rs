@@ -0,0 +1,128 @@
+use brdb::fs::BrFs;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use serde_json::{Value, json};
+
+use crate::{cli::Cli, output::Output, strings_to_lines, traverse};
+
+/// bash: paths starting with / after a .brdb file are looked up inside that world, anything else goes to the usual completion
+const BASH_PATHS: &str = r#"
+_brdb_cmd_paths() {
+    local cur="${COMP_WORDS[COMP_CWORD]}" world="" word
+    for word in "${COMP_WORDS[@]:1:COMP_CWORD-1}"; do
+        [[ "$word" == *.brdb && -f "$word" ]] && world="$word"
+    done
+    if [[ -n "$world" && "$cur" == /* ]]; then
+        mapfile -t COMPREPLY < <("${COMP_WORDS[0]}" __complete-path "$world" "$cur" 2>/dev/null)
+        # a folder ends in / so you can keep typing into it
+        [[ ${#COMPREPLY[@]} -eq 1 && "${COMPREPLY[0]}" == */ ]] && compopt -o nospace 2>/dev/null
+        return 0
+    fi
+    _brdb_cmd "$@"
+}
+
+complete -F _brdb_cmd_paths -o bashdefault -o default brdb_cmd
+"#;
+
+const ZSH_PATHS: &str = r#"
+_brdb_cmd_paths() {
+    local world word
+    for word in "${(@)words[2,CURRENT-1]}"; do
+        [[ "$word" == *.brdb && -f "$word" ]] && world="$word"
+    done
+    if [[ -n "$world" && "$PREFIX" == /* ]]; then
+        local -a paths
+        paths=("${(@f)$("${words[1]}" __complete-path "$world" "$PREFIX" 2>/dev/null)}")
+        compadd -U -S '' -- "${paths[@]}"
+        return
+    fi
+    _brdb_cmd "$@"
+}
+
+compdef _brdb_cmd_paths brdb_cmd
+"#;
+
+const FISH_PATHS: &str = r#"
+function __brdb_cmd_paths
+    set -l tokens (commandline -opc)
+    set -l world
+    for token in $tokens[2..-1]
+        if string match -q '*.brdb' -- $token; and test -f $token
+            set world $token
+        end
+    end
+    test -n "$world"; and $tokens[1] __complete-path $world (commandline -ct) 2>/dev/null
+end
+
+complete -c brdb_cmd -n 'string match -q "/*" -- (commandline -ct)' -f -a '(__brdb_cmd_paths)'
+"#;
+
+const POWERSHELL_PATHS: &str = r#"
+Register-ArgumentCompleter -Native -CommandName 'brdb_cmd' -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+
+    $world = $commandAst.CommandElements |
+        Select-Object -Skip 1 |
+        ForEach-Object { $_.ToString() } |
+        Where-Object { $_ -ne $wordToComplete -and $_ -like '*.brdb' -and (Test-Path $_ -PathType Leaf) } |
+        Select-Object -Last 1
+    if ($world -and $wordToComplete -like '/*') {
+        & $commandAst.CommandElements[0].ToString() __complete-path $world $wordToComplete 2>$null |
+            ForEach-Object { [CompletionResult]::new($_, $_, [CompletionResultType]::ParameterValue, $_) }
+        return
+    }
+    & $__brdb_cmd_completer $wordToComplete $commandAst $cursorPosition
+}
+"#;
+
+/// the completion script for a shell, to be sourced from its startup file
+pub fn completions(shell: Shell) -> Output {
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "brdb_cmd", &mut script);
+
+    // the generated scripts only know about commands and options, so paths inside worlds get hooked in after them
+    let mut script = String::from_utf8_lossy(&script).into_owned();
+    match shell {
+        Shell::Bash => script.push_str(BASH_PATHS),
+        Shell::Zsh => script.push_str(ZSH_PATHS),
+        Shell::Fish => script.push_str(FISH_PATHS),
+        Shell::PowerShell => {
+            // keep the generated completer around so the path one can fall back to it
+            script = script.replacen(
+                "Register-ArgumentCompleter -Native -CommandName 'brdb_cmd' -ScriptBlock {",
+                "$__brdb_cmd_completer = {",
+                1,
+            );
+            script.push_str(POWERSHELL_PATHS);
+        }
+        _ => (),
+    }
+
+    let json = json!({ "shell": shell.to_string(), "script": script });
+    Output::raw(script.into_bytes(), json)
+}
+
+/// the files and folders in a world that start with what's been typed so far, for the completion scripts
+pub fn complete_path(fs: &BrFs, partial: &str) -> Output {
+    let (dir, prefix) = partial.rsplit_once('/').unwrap_or(("", partial));
+    let folder = match dir.trim_matches('/') {
+        "" => Ok(fs),
+        dir => traverse(fs, dir),
+    };
+
+    let mut paths = Vec::new();
+    if let Ok(BrFs::Root(map) | BrFs::Folder(_, map)) = folder {
+        for (name, child) in map {
+            if !name.starts_with(prefix) {
+                continue;
+            }
+            match child {
+                BrFs::File(_) => paths.push(format!("{dir}/{name}")),
+                _ => paths.push(format!("{dir}/{name}/")),
+            }
+        }
+    }
+
+    let json = Value::Array(paths.iter().map(|path| json!(path)).collect());
+    Output::new(strings_to_lines(paths.iter()), json)
+}
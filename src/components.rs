@@ -0,0 +1,110 @@
+use brdb::{BrFsReader, BrReader};
+use serde_json::{Value, json};
+
+use crate::{error::Error, mps, output::Output};
+
+/// a component as it's saved in a components chunk, along with the brick it's on
+struct SavedComponent {
+    brick: String,
+    kind: String,
+    properties: Value,
+}
+
+/// every component in a decoded components chunk. the type counters say how many of each type there are,
+/// the brick indices which brick each one is on, and the records after them hold their properties,
+/// except for types without any
+fn chunk_components<T: BrFsReader>(db: &BrReader<T>, grid: usize, chunk: &str) -> Result<Vec<SavedComponent>, Error> {
+    let global_data = db.global_data()?;
+    let decoded = mps::decode(db, &format!("World/0/Bricks/Grids/{grid}/Components/{chunk}.mps"))?;
+    let soa = decoded.get("soa").unwrap_or(&decoded);
+    let bricks = soa["ComponentBrickIndices"].as_array().cloned().unwrap_or_default();
+    let mut instances = decoded["instances"].as_array().into_iter().flatten();
+
+    let mut components = Vec::new();
+    for counter in soa["ComponentTypeCounters"].as_array().into_iter().flatten() {
+        let type_index = counter["TypeIndex"].as_u64().unwrap_or(0) as usize;
+        let kind = global_data
+            .component_type_names
+            .get_index(type_index)
+            .cloned()
+            .unwrap_or_else(|| format!("unknown type {type_index}"));
+        let has_data = global_data
+            .component_data_struct_names
+            .get(type_index)
+            .is_some_and(|name| name != "None");
+
+        for _ in 0..counter["NumInstances"].as_u64().unwrap_or(0) {
+            let brick = bricks.get(components.len()).and_then(Value::as_u64).unwrap_or(0);
+            let properties = match has_data {
+                true => instances.next().map_or(Value::Null, |instance| instance["value"].clone()),
+                false => json!({}),
+            };
+            components.push(SavedComponent { brick: format!("{chunk}:{brick}"), kind: kind.clone(), properties });
+        }
+    }
+    Ok(components)
+}
+
+/// the chunks of a grid that have components
+fn component_chunks<T: BrFsReader>(db: &BrReader<T>, grid: usize) -> Result<Vec<String>, Error> {
+    let chunks = db.brick_chunk_index(grid)?;
+    Ok(chunks
+        .iter()
+        .filter(|chunk| chunk.num_components > 0)
+        .map(|chunk| chunk.index.to_string())
+        .collect())
+}
+
+/// show the components on a brick (by its id, like 0_0_0:12), in a components chunk file, or in a whole grid
+pub fn components<T: BrFsReader>(db: &BrReader<T>, grid: usize, target: Option<&str>) -> Result<Output, Error> {
+    let (chunks, brick) = match target {
+        None => (component_chunks(db, grid)?, None),
+        // a path to a chunk file, like /World/0/Bricks/Grids/1/Components/0_0_0.mps
+        Some(path) if path.contains('/') => {
+            let path = path.trim_start_matches('/');
+            let Some((grid_path, chunk)) = path.strip_suffix(".mps").and_then(|path| path.rsplit_once("/Components/"))
+            else {
+                return Err(Error::Other(format!("/{path} isnt a components chunk")));
+            };
+            let grid = grid_path.rsplit('/').next().and_then(|grid| grid.parse().ok()).unwrap_or(grid);
+            return components_in(db, grid, &[chunk.to_string()], None);
+        }
+        Some(id) => match id.split_once(':') {
+            Some((chunk, _)) => (vec![chunk.to_string()], Some(id)),
+            None => return Err(Error::Other(format!("not a brick id or path: {id}"))),
+        },
+    };
+    components_in(db, grid, &chunks, brick)
+}
+
+/// list the components in some chunks of a grid, optionally only the ones on one brick
+fn components_in<T: BrFsReader>(
+    db: &BrReader<T>,
+    grid: usize,
+    chunks: &[String],
+    brick: Option<&str>,
+) -> Result<Output, Error> {
+    let mut lines = Vec::new();
+    let mut json = Vec::new();
+    for chunk in chunks {
+        // a chunk without a components file just has no components
+        let path = format!("World/0/Bricks/Grids/{grid}/Components/{chunk}.mps");
+        if db.find_file_by_path(&path)?.is_none() {
+            continue;
+        }
+        for component in chunk_components(db, grid, chunk)? {
+            if brick.is_some_and(|brick| brick != component.brick) {
+                continue;
+            }
+            lines.push(format!("{}  {}", component.brick, component.kind));
+            for (name, value) in component.properties.as_object().into_iter().flatten() {
+                lines.push(format!("    {name}: {value}"));
+            }
+            json.push(json!({ "brick": component.brick, "type": component.kind, "properties": component.properties }));
+        }
+    }
+    if let (Some(brick), true) = (brick, json.is_empty()) {
+        lines.push(format!("no components on {brick}"));
+    }
+    Ok(Output::new(lines.join("\n"), Value::Array(json)))
+}
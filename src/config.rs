@@ -0,0 +1,179 @@
+use std::{env, fs, path::{Path, PathBuf}, sync::OnceLock};
+use indexmap::IndexMap;
+use serde_json::json;
+use toml_edit::{Document, Item};
+
+use crate::{cli::ColorChoice, error::Error, output::{Output, table}};
+
+/// settings from the config file, each one only set if the file has it
+#[derive(Default)]
+pub struct Config {
+    /// where the settings were read from, if there was a file
+    pub path: Option<PathBuf>,
+    /// worlds by a short name, in the `[worlds]` table
+    pub worlds: IndexMap<String, PathBuf>,
+    /// show output as json by default, from `format = "json"`
+    pub json: Option<bool>,
+    pub color: Option<ColorChoice>,
+    /// the editor `edit` opens, which wins over $VISUAL and $EDITOR
+    pub editor: Option<String>,
+    /// where `backup` puts backups when --dir isnt given
+    pub backup_dir: Option<PathBuf>,
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// the settings loaded when brdb_cmd started, or none at all before that
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// keep the loaded settings around for the rest of the run
+pub fn set(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// the home folder, for finding the config file and expanding ~ in paths
+pub fn home() -> Option<PathBuf> {
+    env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")).filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+/// where the config file is looked for without --config: $XDG_CONFIG_HOME/brdb_cmd/config.toml, which is
+/// ~/.config/brdb_cmd/config.toml when that isnt set, or %APPDATA%\brdb_cmd\config.toml on windows
+pub fn default_path() -> Option<PathBuf> {
+    let dir = match cfg!(windows) {
+        true => env::var_os("APPDATA").map(PathBuf::from),
+        false => env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from).or_else(|| Some(home()?.join(".config"))),
+    };
+    Some(dir?.join("brdb_cmd").join("config.toml"))
+}
+
+/// paths in the config can start with ~ for the home folder
+fn expand(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/").or_else(|| path.strip_prefix("~\\")), home()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => home().unwrap_or_default(),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// the world an `@name` stands for, from the `[worlds]` table. a bare `@` is the default world, which is
+/// $BRDB_WORLD when thats set, or the one called `default` in the config
+pub fn world(alias: &str) -> Result<PathBuf, String> {
+    let config = get();
+    let name = match alias {
+        "" => match env::var("BRDB_WORLD") {
+            Ok(world) if !world.is_empty() => match world.strip_prefix('@') {
+                Some(name) if !name.is_empty() => name.to_string(),
+                Some(_) => return Err(String::from("BRDB_WORLD cant be @ itself")),
+                None => return Ok(expand(&world)),
+            },
+            _ if config.worlds.contains_key("default") => String::from("default"),
+            _ => return Err(String::from("theres no default world, set BRDB_WORLD or add a world called default to the config")),
+        },
+        name => name.to_string(),
+    };
+    match config.worlds.get(&name) {
+        Some(world) => Ok(world.clone()),
+        None if config.worlds.is_empty() => Err(format!("theres no world called @{name}, the config doesnt have any worlds")),
+        None => {
+            let names: Vec<String> = config.worlds.keys().map(|name| format!("@{name}")).collect();
+            Err(format!("theres no world called @{name}, the config has {}", names.join(", ")))
+        }
+    }
+}
+
+/// read the config file. a missing file at the default place just means there are no settings,
+/// but one given with --config has to be there
+pub fn load(path: Option<&Path>) -> Result<Config, Error> {
+    let (path, given) = match path {
+        Some(path) => (path.to_path_buf(), true),
+        None => match default_path() {
+            Some(path) => (path, false),
+            None => return Ok(Config::default()),
+        },
+    };
+    if !given && !path.exists() {
+        return Ok(Config::default());
+    }
+    let text = fs::read_to_string(&path).map_err(|error| Error::Other(format!("couldnt read {}: {error}", path.display())))?;
+    let bad = |problem: String| Error::Other(format!("{}: {problem}", path.display()));
+    let document: Document = text.parse().map_err(|error: toml_edit::TomlError| bad(error.to_string().trim_end().to_string()))?;
+
+    let string = |key: &str, item: &Item| item.as_str().map(String::from).ok_or_else(|| bad(format!("{key} has to be a string")));
+    let mut config = Config { path: Some(path.clone()), ..Config::default() };
+    for (key, item) in document.iter() {
+        match key {
+            "format" => {
+                config.json = Some(match string(key, item)?.as_str() {
+                    "json" => true,
+                    "text" => false,
+                    other => return Err(bad(format!("format has to be text or json, not {other}"))),
+                })
+            }
+            "color" => {
+                config.color = Some(match string(key, item)?.as_str() {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => return Err(bad(format!("color has to be auto, always or never, not {other}"))),
+                })
+            }
+            "editor" => config.editor = Some(string(key, item)?),
+            "backup_dir" => config.backup_dir = Some(expand(&string(key, item)?)),
+            "worlds" => {
+                let worlds = item.as_table_like().ok_or_else(|| bad(String::from("worlds has to be a table of names and paths")))?;
+                for (name, world) in worlds.iter() {
+                    config.worlds.insert(name.to_string(), expand(&string(&format!("worlds.{name}"), world)?));
+                }
+            }
+            // a typo would otherwise just quietly do nothing
+            other => return Err(bad(format!("unknown setting {other}"))),
+        }
+    }
+    Ok(config)
+}
+
+/// show where the config file is and what's set in it
+pub fn show() -> Result<Output, Error> {
+    let config = get();
+    let path = config.path.clone().or_else(default_path);
+    let mut lines = vec![match (&config.path, &path) {
+        (Some(path), _) => format!("config file: {}", path.display()),
+        (None, Some(path)) => format!("config file: {} (doesnt exist, so nothing is set)", path.display()),
+        (None, None) => String::from("config file: none, theres no home folder to look in"),
+    }];
+
+    let mut rows = Vec::new();
+    let format = config.json.map(|json| if json { "json" } else { "text" });
+    let color = config.color.map(|color| match color {
+        ColorChoice::Auto => "auto",
+        ColorChoice::Always => "always",
+        ColorChoice::Never => "never",
+    });
+    for (key, value) in [("format", format.map(String::from)), ("color", color.map(String::from)), ("editor", config.editor.clone())] {
+        if let Some(value) = value {
+            rows.push(vec![key.to_string(), value]);
+        }
+    }
+    if let Some(dir) = &config.backup_dir {
+        rows.push(vec![String::from("backup_dir"), dir.display().to_string()]);
+    }
+    for (name, world) in &config.worlds {
+        rows.push(vec![format!("worlds.{name}"), world.display().to_string()]);
+    }
+    if !rows.is_empty() {
+        lines.push(table(&rows));
+    }
+
+    let json = json!({
+        "path": path,
+        "exists": config.path.is_some(),
+        "format": format,
+        "color": color,
+        "editor": config.editor,
+        "backup_dir": config.backup_dir,
+        "worlds": config.worlds,
+    });
+    Ok(Output::new(lines.join("\n"), json))
+}
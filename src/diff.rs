@@ -0,0 +1,156 @@
+use std::path::Path;
+use brdb::{BrFsReader, BrReader, Brdb, IntoReader};
+use indexmap::IndexMap;
+use serde_json::{Value, json};
+
+use crate::{error::Error, files_in, mps, output::Output, revisions::open_at_revision};
+
+/// the blake3 hash of a blob, which is the same for the same content in any world
+pub fn blob_hash(db: &Brdb, blob_id: Option<i64>) -> Result<Option<Vec<u8>>, Error> {
+    let Some(blob_id) = blob_id else {
+        return Ok(None);
+    };
+    let hash = db
+        .conn
+        .query_row("SELECT hash FROM blobs WHERE blob_id = ?1", [blob_id], |row| row.get(0))?;
+    Ok(Some(hash))
+}
+
+/// every file in a world with the hash of its content
+pub fn file_hashes(db: &Brdb) -> Result<IndexMap<String, Option<Vec<u8>>>, Error> {
+    let root = db.get_fs()?;
+    files_in(&root, "")
+        .into_iter()
+        .map(|(path, file)| Ok((path, blob_hash(db, file.content_id)?)))
+        .collect()
+}
+
+/// decode a file to json if it's something that can be compared structurally
+fn decode(db: &BrReader<Brdb>, path: &str) -> Option<Value> {
+    match path.rsplit_once(".").map(|(_, ext)| ext) {
+        Some("mps") => mps::decode(db, path).ok(),
+        Some("json") => serde_json::from_slice(&db.read_file(path).ok()?).ok(),
+        _ => None,
+    }
+}
+
+/// short single line version of a json value for diff output
+fn summary(value: &Value) -> String {
+    let text = value.to_string();
+    match text.chars().count() > 60 {
+        true => format!("{}...", text.chars().take(57).collect::<String>()),
+        false => text,
+    }
+}
+
+/// a value that was added, removed or changed inside a file
+struct Change {
+    path: String,
+    old: Option<Value>,
+    new: Option<Value>,
+}
+
+impl Change {
+    fn new(path: String, old: Option<&Value>, new: Option<&Value>) -> Self {
+        Self { path, old: old.cloned(), new: new.cloned() }
+    }
+
+    /// the change as a line like `~ .a.b[0]: 1 -> 2`
+    fn line(&self) -> String {
+        let path = &self.path;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => format!("~ {path}: {} -> {}", summary(old), summary(new)),
+            (Some(old), None) => format!("- {path}: {}", summary(old)),
+            (None, Some(new)) => format!("+ {path}: {}", summary(new)),
+            (None, None) => format!("~ {path}"),
+        }
+    }
+
+    /// the change as json, leaving out `old` or `new` if the value was added or removed
+    fn json(&self) -> Value {
+        let mut json = json!({ "path": self.path });
+        if let Some(old) = &self.old {
+            json["old"] = old.clone();
+        }
+        if let Some(new) = &self.new {
+            json["new"] = new.clone();
+        }
+        json
+    }
+}
+
+/// every difference between two json values
+fn json_diff(path: &str, a: &Value, b: &Value, changes: &mut Vec<Change>) {
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                match b.get(key) {
+                    Some(other) => json_diff(&format!("{path}.{key}"), value, other, changes),
+                    None => changes.push(Change::new(format!("{path}.{key}"), Some(value), None)),
+                }
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                changes.push(Change::new(format!("{path}.{key}"), None, Some(value)));
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for (i, value) in a.iter().enumerate() {
+                match b.get(i) {
+                    Some(other) => json_diff(&format!("{path}[{i}]"), value, other, changes),
+                    None => changes.push(Change::new(format!("{path}[{i}]"), Some(value), None)),
+                }
+            }
+            for (i, value) in b.iter().enumerate().skip(a.len()) {
+                changes.push(Change::new(format!("{path}[{i}]"), None, Some(value)));
+            }
+        }
+        (a, b) if a != b => changes.push(Change::new(path.to_string(), Some(a), Some(b))),
+        _ => (),
+    }
+}
+
+/// report which files were added, removed and changed going from world `a` to world `b`
+pub fn diff_worlds(a: &BrReader<Brdb>, b: &BrReader<Brdb>, content: bool) -> Result<Output, Error> {
+    let old = file_hashes(a)?;
+    let new = file_hashes(b)?;
+    let (mut added, mut removed, mut modified) = (Vec::new(), Vec::new(), Vec::new());
+    let mut lines = Vec::new();
+
+    for (path, hash) in &old {
+        match new.get(path) {
+            None => {
+                removed.push(format!("/{path}"));
+                lines.push(format!("D /{path}"));
+            }
+            Some(other) if other != hash => {
+                lines.push(format!("M /{path}"));
+                let mut changes = Vec::new();
+                if content && let (Some(old_value), Some(new_value)) = (decode(a, path), decode(b, path)) {
+                    json_diff("", &old_value, &new_value, &mut changes);
+                    lines.extend(changes.iter().map(|change| format!("    {}", change.line())));
+                }
+                let mut file = json!({ "path": format!("/{path}") });
+                if content {
+                    file["changes"] = changes.iter().map(Change::json).collect();
+                }
+                modified.push(file);
+            }
+            Some(_) => (),
+        }
+    }
+    for path in new.keys().filter(|path| !old.contains_key(*path)) {
+        added.push(format!("/{path}"));
+        lines.push(format!("A /{path}"));
+    }
+
+    lines.push(format!("{} added, {} removed, {} modified", added.len(), removed.len(), modified.len()));
+    let json = json!({ "added": added, "removed": removed, "modified": modified });
+    Ok(Output::new(lines.join("\n"), json))
+}
+
+/// compare two revisions of the same world
+pub fn diff_revisions(world: &Path, a: i64, b: i64, content: bool) -> Result<Output, Error> {
+    let a = open_at_revision(world, a)?.into_reader();
+    let b = open_at_revision(world, b)?.into_reader();
+    diff_worlds(&a, &b, content)
+}
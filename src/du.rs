@@ -0,0 +1,55 @@
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use serde_json::json;
+
+use crate::{blob_sizes, error::Error, files_in, format_size, output::Output, traverse};
+
+/// total compressed and uncompressed size of every file below an entry
+fn total_size(db: &Brdb, fs: &BrFs) -> Result<(u64, u64), Error> {
+    let mut total = (0, 0);
+    for (_, file) in files_in(fs, "") {
+        if let Some(blob_id) = file.content_id {
+            let (compressed, size) = blob_sizes(db, blob_id)?;
+            total.0 += compressed;
+            total.1 += size;
+        }
+    }
+    Ok(total)
+}
+
+/// how much space each entry in a folder takes up, biggest first
+pub fn du(db: &BrReader<Brdb>, path: &str) -> Result<Output, Error> {
+    let path = path.trim_end_matches('/');
+    let root = db.get_fs()?;
+    let fs = match path {
+        "" => &root,
+        _ => traverse(&root, path)?,
+    };
+
+    let mut rows = Vec::new();
+    if let BrFs::Root(map) | BrFs::Folder(_, map) = fs {
+        for (name, child) in map {
+            let (compressed, size) = total_size(db, child)?;
+            let name = match child {
+                BrFs::File(_) => name.clone(),
+                _ => format!("{name}/"),
+            };
+            rows.push((size, compressed, name));
+        }
+    }
+    rows.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.2.cmp(&b.2)));
+
+    let (compressed, size) = total_size(db, fs)?;
+    let mut lines = vec![format!("{:>10}  {:>10}  {}", "SIZE", "COMPRESSED", "PATH")];
+    let mut entries = Vec::new();
+    for (size, compressed, name) in rows {
+        let name = match path {
+            "" => format!("/{name}"),
+            _ => format!("/{path}/{name}"),
+        };
+        lines.push(format!("{:>10}  {:>10}  {name}", format_size(size), format_size(compressed)));
+        entries.push(json!({ "path": name, "size": size, "compressed": compressed }));
+    }
+    lines.push(format!("{:>10}  {:>10}  total", format_size(size), format_size(compressed)));
+    let json = json!({ "entries": entries, "total": { "size": size, "compressed": compressed } });
+    Ok(Output::new(lines.join("\n"), json))
+}
@@ -0,0 +1,99 @@
+use std::{env, fs, io::Write, path::Path, process};
+use brdb::{BrFsReader, BrReader, Brdb, schema::{BrdbSchema, ReadBrdbSchema}};
+use serde_json::json;
+
+use crate::{config, error::Error, mps, output::Output, write::save_file};
+
+/// the editor to use, from the config file, $VISUAL or $EDITOR
+fn editor() -> String {
+    config::get()
+        .editor
+        .clone()
+        .or_else(|| env::var("VISUAL").ok())
+        .or_else(|| env::var("EDITOR").ok())
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from(if cfg!(windows) { "notepad" } else { "vi" }))
+}
+
+/// open a file in the editor and wait for it to close
+fn run_editor(path: &Path) -> Result<(), Error> {
+    let editor = editor();
+    // editors like "code --wait" come with their own arguments
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|error| Error::Other(format!("couldnt start {program}: {error}")))?;
+
+    match status.success() {
+        true => Ok(()),
+        false => Err(Error::Other(format!("{program} exited with {status}"))),
+    }
+}
+
+/// turn the file into something a human can edit
+pub fn to_text(db: &BrReader<Brdb>, path: &str, ext: &str) -> Result<Vec<u8>, Error> {
+    Ok(match ext {
+        "mps" => serde_json::to_vec_pretty(&mps::decode(db, path)?)?,
+        "json" => {
+            let value: serde_json::Value = serde_json::from_slice(&db.read_file(path)?)?;
+            serde_json::to_vec_pretty(&value)?
+        }
+        "schema" => {
+            let schema = db.read_file(path)?.as_slice().read_brdb_schema()?;
+            format!("{schema}").into_bytes()
+        }
+        _ => db.read_file(path)?,
+    })
+}
+
+/// turn the edited text back into what the brdb stores
+fn from_text(db: &BrReader<Brdb>, path: &str, ext: &str, text: &[u8]) -> Result<Vec<u8>, Error> {
+    Ok(match ext {
+        "mps" => mps::encode(db, path, &serde_json::from_slice(text)?)?,
+        "json" => serde_json::to_vec(&serde_json::from_slice::<serde_json::Value>(text)?)?,
+        "schema" => {
+            let text = String::from_utf8_lossy(text);
+            BrdbSchema::new_parsed(&text)?.to_bytes()?
+        }
+        _ => text.to_vec(),
+    })
+}
+
+/// open a file in your favorite editor and save it into the brdb once finished
+pub fn edit_file(db: &BrReader<Brdb>, path: &str) -> Result<Output, Error> {
+    let ext = path.rsplit_once(".").map(|(_, ext)| ext).unwrap_or("");
+    let name = path.rsplit('/').next().unwrap_or(path);
+
+    // decoded files get a .json extension so the editor highlights them properly. the name is random and the
+    // file is made fresh, so nothing already sitting in the temp folder gets written through
+    let suffix = match ext {
+        "mps" => format!("-{name}.json"),
+        _ => format!("-{name}"),
+    };
+    let mut temp = tempfile::Builder::new().prefix("brdb_cmd-").suffix(&suffix).tempfile()?;
+
+    let before = to_text(db, path, ext)?;
+    temp.write_all(&before)?;
+    temp.flush()?;
+    run_editor(temp.path())?;
+    let after = fs::read(temp.path())?;
+
+    if after == before {
+        return Ok(Output::new("no changes", json!({ "path": format!("/{path}"), "saved": false })));
+    }
+
+    // keep the edited file around if it can't be saved, so the changes aren't lost
+    let data = match from_text(db, path, ext, &after) {
+        Ok(data) => data,
+        Err(error) => {
+            let kept = temp.into_temp_path().keep().map_err(|error| Error::Other(error.to_string()))?;
+            return Err(Error::Other(format!("{error}\nyour changes are still in {}", kept.display())));
+        }
+    };
+
+    save_file(db, path, data, &format!("Edit {path}"))?;
+    Ok(Output::new(format!("saved {path}"), json!({ "path": format!("/{path}"), "saved": true })))
+}
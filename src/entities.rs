@@ -0,0 +1,132 @@
+use brdb::{BrFsReader, BrReader, ChunkIndex, Entity, OwnerTableSoA};
+use serde_json::{Map, Value, json};
+
+use crate::{
+    error::Error,
+    mps,
+    output::{Output, table},
+    owners::owner_table,
+};
+
+/// every entity in the world along with the chunk it's saved in
+fn all_entities<T: BrFsReader>(db: &BrReader<T>) -> Result<Vec<(ChunkIndex, Entity)>, Error> {
+    // worlds without any entities dont have an entity index at all
+    if db.find_file_by_path("World/0/Entities/ChunkIndex.mps")?.is_none() {
+        return Ok(Vec::new());
+    }
+    let mut entities = Vec::new();
+    for chunk in db.entity_chunk_index()? {
+        entities.extend(db.entity_chunk(chunk)?.into_iter().map(|entity| (chunk, entity)));
+    }
+    Ok(entities)
+}
+
+/// the owner of an entity as its index and name, like `1 (alice)`
+fn owner_name(owners: &OwnerTableSoA, index: Option<u32>) -> (Option<u32>, Option<String>) {
+    let name = index.and_then(|index| owners.user_names.get(index as usize).cloned());
+    (index, name)
+}
+
+/// list every entity in the world with its type, where it is and who owns it
+pub fn entities<T: BrFsReader>(db: &BrReader<T>) -> Result<Output, Error> {
+    let owners = owner_table(db)?;
+    let mut rows = vec![["ID", "TYPE", "LOCATION", "ROTATION", "OWNER"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for (chunk, entity) in all_entities(db)? {
+        let (location, rotation) = (entity.location, entity.rotation);
+        let (owner, name) = owner_name(&owners, entity.owner_index);
+        rows.push(vec![
+            entity.id.map_or_else(|| String::from("-"), |id| id.to_string()),
+            entity.asset.to_string(),
+            format!("{},{},{}", location.x, location.y, location.z),
+            format!("{:.3},{:.3},{:.3},{:.3}", rotation.x, rotation.y, rotation.z, rotation.w),
+            match (owner, &name) {
+                (Some(owner), Some(name)) => format!("{owner} ({name})"),
+                (Some(owner), None) => owner.to_string(),
+                (None, _) => String::from("-"),
+            },
+        ]);
+        json.push(json!({
+            "id": entity.id,
+            "type": entity.asset.to_string(),
+            "chunk": chunk.to_string(),
+            "location": [location.x, location.y, location.z],
+            "rotation": [rotation.x, rotation.y, rotation.z, rotation.w],
+            "owner": owner,
+            "owner_name": name,
+            "frozen": entity.frozen,
+            "sleeping": entity.sleeping,
+        }));
+    }
+    Ok(Output::new(table(&rows), Value::Array(json)))
+}
+
+/// the properties of the entity at `index` in a decoded entity chunk: its value from every array
+/// in the chunk, and the data record stored after them for its type
+fn entity_detail<T: BrFsReader>(db: &BrReader<T>, chunk: &Value, index: usize) -> Result<Value, Error> {
+    let global_data = db.global_data()?;
+    let (soa, instances) = match chunk.get("soa") {
+        Some(soa) => (soa, chunk["instances"].as_array().cloned().unwrap_or_default()),
+        None => (chunk, Vec::new()),
+    };
+
+    // the data records only exist for types that have data, in the same order as the entities
+    let mut count = 0;
+    let mut instance = 0;
+    let mut kind = None;
+    let mut data = Value::Null;
+    for counter in soa["TypeCounters"].as_array().into_iter().flatten() {
+        let type_index = counter["TypeIndex"].as_u64().unwrap_or(0) as usize;
+        let num = counter["NumEntities"].as_u64().unwrap_or(0) as usize;
+        let has_data = global_data
+            .entity_data_class_names
+            .get_index(type_index)
+            .is_some_and(|name| name != "None");
+        if (count..count + num).contains(&index) {
+            kind = global_data.entity_type_names.get_index(type_index).cloned();
+            if has_data {
+                data = instances.get(instance + index - count).map_or(Value::Null, |value| value["value"].clone());
+            }
+        }
+        count += num;
+        if has_data {
+            instance += num;
+        }
+    }
+
+    let mut detail = Map::new();
+    detail.insert(String::from("Type"), json!(kind));
+    for (key, value) in soa.as_object().into_iter().flatten() {
+        match value {
+            Value::Array(values) if key != "TypeCounters" && values.len() == count => {
+                detail.insert(key.clone(), values[index].clone());
+            }
+            // flags are packed eight to a byte
+            Value::Object(flags) if flags.contains_key("Flags") => {
+                let byte = flags["Flags"].get(index / 8).and_then(Value::as_u64).unwrap_or(0);
+                detail.insert(key.clone(), json!(byte & (1 << (index % 8)) != 0));
+            }
+            _ => (),
+        }
+    }
+    detail.insert(String::from("Data"), data);
+    Ok(Value::Object(detail))
+}
+
+/// everything saved about one entity
+pub fn show<T: BrFsReader>(db: &BrReader<T>, id: usize) -> Result<Output, Error> {
+    let entities = all_entities(db)?;
+    let Some((chunk, entity)) = entities.iter().find(|(_, entity)| entity.id == Some(id)) else {
+        return Err(Error::NotFound(format!("entity {id}")));
+    };
+    let index = entities
+        .iter()
+        .filter(|(other, _)| other == chunk)
+        .position(|(_, other)| other.id == entity.id)
+        .unwrap_or(0);
+
+    let path = format!("World/0/Entities/Chunks/{chunk}.mps");
+    let detail = entity_detail(db, &mps::decode(db, &path)?, index)?;
+    let text = serde_json::to_string_pretty(&detail)?;
+    Ok(Output::new(text, json!({ "id": id, "chunk": chunk.to_string(), "entity": detail })))
+}
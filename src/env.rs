@@ -0,0 +1,54 @@
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use serde_json::{Value, json};
+
+use crate::{error::Error, format_size, output::Output, traverse, write::save_file};
+
+const WORLD_JSON: &str = "Meta/World.json";
+
+/// the environment files saved in each world folder, with their size. brdb cant decode them yet,
+/// so the sun, fog and weather settings in them cant be shown
+fn environment_files(db: &BrReader<Brdb>) -> Result<Vec<(String, usize)>, Error> {
+    let root = db.get_fs()?;
+    let mut files = Vec::new();
+    if let Ok(BrFs::Folder(_, map)) = traverse(&root, "World") {
+        for name in map.keys() {
+            let path = format!("World/{name}/Environment.bp");
+            if let Ok(BrFs::File(file)) = traverse(&root, &path) {
+                files.push((path, file.read(&**db)?.len()));
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// show the environment settings of the world
+pub fn show(db: &BrReader<Brdb>) -> Result<Output, Error> {
+    let settings: Value = serde_json::from_slice(&db.read_file(WORLD_JSON)?)?;
+    let files = environment_files(db)?;
+
+    let mut lines = vec![serde_json::to_string_pretty(&settings)?];
+    for (path, size) in &files {
+        lines.push(format!("/{path}: {} (cant be decoded yet)", format_size(*size as u64)));
+    }
+    let files: Vec<Value> = files
+        .iter()
+        .map(|(path, size)| json!({ "path": format!("/{path}"), "bytes": size }))
+        .collect();
+    Ok(Output::new(lines.join("\n"), json!({ "settings": settings, "files": files })))
+}
+
+/// change one environment setting, found by its json pointer like /environment, and save it as a new revision.
+/// the value is read as json if it can be, so numbers and true/false keep their type, and as a string if not
+pub fn set(db: &BrReader<Brdb>, pointer: &str, value: &str) -> Result<Output, Error> {
+    let mut settings: Value = serde_json::from_slice(&db.read_file(WORLD_JSON)?)?;
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+
+    let Some(setting) = settings.pointer_mut(pointer) else {
+        return Err(Error::NotFound(format!("{pointer} in /{WORLD_JSON}")));
+    };
+    let old = std::mem::replace(setting, value.clone());
+    save_file(db, WORLD_JSON, serde_json::to_vec_pretty(&settings)?, &format!("Set {pointer}"))?;
+
+    let text = format!("{pointer}: {old} -> {value}");
+    Ok(Output::new(text, json!({ "pointer": pointer, "old": old, "new": value })))
+}
@@ -0,0 +1,125 @@
+use std::{fmt, io, path::PathBuf};
+use brdb::{BrError, BrFsError, BrdbError, BrdbSchemaError, BrdbWorldError};
+
+use crate::TraverseError;
+
+/// everything that can go wrong while running a command
+#[derive(Debug)]
+pub enum Error {
+    /// a path or something else that isnt in the world
+    NotFound(String),
+    /// a file on disk that couldnt be opened as a world
+    BadWorld(PathBuf, String),
+    Traverse(TraverseError),
+    Fs(BrFsError),
+    Brdb(BrError),
+    Io(io::Error),
+    Json(serde_json::Error),
+    Decode(String),
+    Other(String),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotFound(what) => write!(f, "{what} doesnt exist"),
+            Error::BadWorld(path, error) => write!(f, "{} isnt a world file brdb_cmd can read: {error}", path.display()),
+            Error::Traverse(error) => write!(f, "{error}"),
+            _ if self.is_read_only() => write!(f, "the world was opened read-only, so nothing was changed"),
+            Error::Fs(error) => write!(f, "couldnt read file: {error}"),
+            Error::Brdb(error) => write!(f, "couldnt decode file: {error}"),
+            Error::Io(error) => write!(f, "{error}"),
+            Error::Json(error) => write!(f, "invalid json: {error}"),
+            Error::Decode(error) => write!(f, "couldnt encode file: {error}"),
+            Error::Other(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl Error {
+    /// whether sqlite refused to write because the world was opened read-only
+    fn is_read_only(&self) -> bool {
+        fn sqlite(error: &BrError) -> Option<&rusqlite::Error> {
+            match error {
+                BrError::Wrapped(_, error) => sqlite(error),
+                BrError::Fs(BrFsError::Sqlite(error)) => Some(error),
+                BrError::Brdb(error) => brdb_sqlite(error),
+                _ => None,
+            }
+        }
+        fn brdb_sqlite(error: &BrdbError) -> Option<&rusqlite::Error> {
+            match error {
+                BrdbError::Wrapped(_, error) => brdb_sqlite(error),
+                BrdbError::Sqlite(error) | BrdbError::Fs(BrFsError::Sqlite(error)) => Some(error),
+                _ => None,
+            }
+        }
+        let error = match self {
+            Error::Fs(BrFsError::Sqlite(error)) => Some(error),
+            Error::Brdb(error) => sqlite(error),
+            _ => None,
+        };
+        error.and_then(rusqlite::Error::sqlite_error_code) == Some(rusqlite::ErrorCode::ReadOnly)
+    }
+
+    /// the exit code for this kind of error, so scripts can tell them apart.
+    /// 2 is left for bad arguments, which clap exits with
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            _ if self.is_read_only() => 1,
+            Error::NotFound(_) | Error::Traverse(_) => 3,
+            Error::BadWorld(..) | Error::Fs(_) => 4,
+            Error::Brdb(_) | Error::Json(_) | Error::Decode(_) => 5,
+            Error::Io(_) => 6,
+            Error::Other(_) => 1,
+        }
+    }
+}
+
+impl From<TraverseError> for Error {
+    fn from(error: TraverseError) -> Self {
+        match error {
+            TraverseError::NotFound(path) => Error::NotFound(path),
+            error => Error::Traverse(error),
+        }
+    }
+}
+impl From<BrFsError> for Error {
+    fn from(error: BrFsError) -> Self {
+        Error::Fs(error)
+    }
+}
+impl From<BrError> for Error {
+    fn from(error: BrError) -> Self {
+        Error::Brdb(error)
+    }
+}
+impl From<BrdbWorldError> for Error {
+    fn from(error: BrdbWorldError) -> Self {
+        Error::Brdb(error.into())
+    }
+}
+impl From<BrdbError> for Error {
+    fn from(error: BrdbError) -> Self {
+        Error::Brdb(error.into())
+    }
+}
+impl From<BrdbSchemaError> for Error {
+    fn from(error: BrdbSchemaError) -> Self {
+        Error::Brdb(error.into())
+    }
+}
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Self {
+        Error::Fs(BrFsError::Sqlite(error))
+    }
+}
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+impl From<serde_json::Error> for Error {
+    fn from(error: serde_json::Error) -> Self {
+        Error::Json(error)
+    }
+}
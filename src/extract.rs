@@ -0,0 +1,39 @@
+use std::{fs, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb};
+use serde_json::json;
+
+use crate::{error::Error, files_in, mps, output::Output, progress::Progress};
+
+/// write every file in the world to a folder on disk
+pub fn extract(db: &BrReader<Brdb>, output: &Path, decode: bool) -> Result<Output, Error> {
+    let root = db.get_fs()?;
+    let files = files_in(&root, "");
+
+    let progress = Progress::new("extracting", files.len(), "files");
+    for (path, file) in &files {
+        progress.tick();
+        log::debug!("extracting /{path}");
+        let target = output.join(path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        if path.ends_with(".mps") && decode {
+            // decoded files are written while theyre decoded, so big ones dont have to fit in memory as json
+            let decoded = target.with_extension("mps.json");
+            match mps::decode_to(db, path, fs::File::create(&decoded)?) {
+                Ok(()) => continue,
+                // a raw copy is still better than nothing
+                Err(error) => {
+                    fs::remove_file(&decoded)?;
+                    log::warn!("couldnt decode {path}, extracting it as is: {error}");
+                }
+            }
+        }
+        fs::write(&target, file.read(&**db)?)?;
+    }
+
+    let text = format!("extracted {} files to {}", files.len(), output.display());
+    let paths: Vec<String> = files.iter().map(|(path, _)| format!("/{path}")).collect();
+    Ok(Output::new(text, json!({ "output": output, "files": paths })))
+}
@@ -0,0 +1,36 @@
+use brdb::fs::BrFs;
+use glob::Pattern;
+use regex::Regex;
+use serde_json::json;
+
+use crate::{entries_in, error::Error, output::Output, strings_to_lines, traverse};
+
+/// find every entry below a folder whose name matches a glob, or a regex if `regex` is set
+pub fn find(fs: &BrFs, root: &str, pattern: &str, regex: bool) -> Result<Output, Error> {
+    let root = root.trim_start_matches('/').trim_end_matches('/');
+    let sub_fs = match root {
+        "" => fs,
+        _ => traverse(fs, root)?,
+    };
+
+    let matches: Box<dyn Fn(&str) -> bool> = match regex {
+        true => {
+            let regex = Regex::new(pattern).map_err(|error| Error::Other(format!("invalid regex: {error}")))?;
+            Box::new(move |name| regex.is_match(name))
+        }
+        false => {
+            let glob = Pattern::new(pattern).map_err(|error| Error::Other(format!("invalid pattern: {error}")))?;
+            Box::new(move |name| glob.matches(name))
+        }
+    };
+
+    let found: Vec<String> = entries_in(sub_fs, root)
+        .into_iter()
+        .filter_map(|(path, _)| {
+            let path = path.trim_end_matches('/');
+            let name = path.rsplit('/').next().unwrap_or(path);
+            matches(name).then(|| format!("/{path}"))
+        })
+        .collect();
+    Ok(Output::new(strings_to_lines(found.iter()), json!(found)))
+}
@@ -0,0 +1,188 @@
+use std::{fs, path::Path};
+use brdb::{BrReader, Brdb};
+use indexmap::IndexMap;
+use serde_json::{Value, json};
+
+use crate::{bricks::grid_bricks, cli::GltfGroup, error::Error, mesh::brick_mesh, output::Output, owners::owner_table};
+
+/// magic number and version at the start of every .glb file
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+
+// accessor component types and buffer view targets from the gltf spec
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// a pbr material that looks roughly like a brickadia material. the color comes from the vertices
+fn material(name: &str) -> Value {
+    let (metallic, roughness) = match name {
+        name if name.contains("Metallic") => (1.0, 0.2),
+        name if name.contains("Glass") => (0.0, 0.05),
+        _ => (0.0, 0.6),
+    };
+    let mut material = json!({
+        "name": name,
+        "pbrMetallicRoughness": { "baseColorFactor": [1.0, 1.0, 1.0, 1.0], "metallicFactor": metallic, "roughnessFactor": roughness },
+    });
+    if name.contains("Glass") {
+        material["pbrMetallicRoughness"]["baseColorFactor"] = json!([1.0, 1.0, 1.0, 0.5]);
+        material["alphaMode"] = json!("BLEND");
+    }
+    if name.contains("Glow") {
+        material["emissiveFactor"] = json!([1.0, 1.0, 1.0]);
+    }
+    material
+}
+
+/// brick colors are srgb, but gltf vertex colors are linear
+fn linear(channel: u8) -> f32 {
+    let channel = channel as f32 / 255.0;
+    match channel <= 0.04045 {
+        true => channel / 12.92,
+        false => ((channel + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/// the buffer everything is packed into, and the views and accessors that point into it
+#[derive(Default)]
+struct Buffer {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl Buffer {
+    /// add a list of vec3s and return its accessor
+    fn vec3(&mut self, values: &[[f32; 3]], with_bounds: bool) -> usize {
+        let offset = self.data.len();
+        for value in values {
+            for component in value {
+                self.data.extend(component.to_le_bytes());
+            }
+        }
+        let mut accessor = json!({ "bufferView": self.view(offset, ARRAY_BUFFER), "componentType": FLOAT, "count": values.len(), "type": "VEC3" });
+        // positions have to say how far they go
+        if with_bounds {
+            let min = values.iter().fold([f32::MAX; 3], |min, v| [min[0].min(v[0]), min[1].min(v[1]), min[2].min(v[2])]);
+            let max = values.iter().fold([f32::MIN; 3], |max, v| [max[0].max(v[0]), max[1].max(v[1]), max[2].max(v[2])]);
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    /// add a list of triangle indices and return its accessor
+    fn indices(&mut self, indices: &[u32]) -> usize {
+        let offset = self.data.len();
+        for index in indices {
+            self.data.extend(index.to_le_bytes());
+        }
+        let view = self.view(offset, ELEMENT_ARRAY_BUFFER);
+        self.accessors.push(json!({ "bufferView": view, "componentType": UNSIGNED_INT, "count": indices.len(), "type": "SCALAR" }));
+        self.accessors.len() - 1
+    }
+
+    fn view(&mut self, offset: usize, target: u32) -> usize {
+        self.views.push(json!({ "buffer": 0, "byteOffset": offset, "byteLength": self.data.len() - offset, "target": target }));
+        self.views.len() - 1
+    }
+}
+
+/// the triangles of some bricks that share a material
+#[derive(Default)]
+struct Primitive {
+    positions: Vec<[f32; 3]>,
+    colors: Vec<[f32; 3]>,
+    indices: Vec<u32>,
+}
+
+/// save the bricks on the main grid as a binary gltf (.glb) file. there's a node for each owner or chunk, each
+/// with one mesh that has a primitive per brickadia material, and the brick colors are kept as vertex colors
+pub fn export(db: &BrReader<Brdb>, out: &Path, group: GltfGroup, scale: f32) -> Result<Output, Error> {
+    let owners = owner_table(db)?;
+    let bricks = grid_bricks(db, 1, None)?;
+
+    let mut groups: IndexMap<String, IndexMap<String, Primitive>> = IndexMap::new();
+    let mut count = 0;
+    for saved in bricks.iter().filter(|saved| saved.brick.visible) {
+        let brick = &saved.brick;
+        let name = match group {
+            GltfGroup::Owner => owners.user_names.get(brick.owner_index.unwrap_or(0)).cloned().unwrap_or_default(),
+            GltfGroup::Chunk => saved.chunk.to_string(),
+        };
+        let primitive = groups.entry(name).or_default().entry(brick.material.to_string()).or_default();
+        let mesh = brick_mesh(brick, scale);
+        let start = primitive.positions.len() as u32;
+        let color = [brick.color.r, brick.color.g, brick.color.b].map(linear);
+        primitive.colors.extend(mesh.vertices.iter().map(|_| color));
+        primitive.positions.extend(mesh.vertices);
+        primitive.indices.extend(mesh.triangles.iter().flatten().map(|index| start + *index as u32));
+        count += 1;
+    }
+
+    let mut buffer = Buffer::default();
+    let mut materials: IndexMap<String, Value> = IndexMap::new();
+    let (mut nodes, mut meshes) = (vec![json!({ "name": "world", "children": [] })], Vec::new());
+    let (mut vertices, mut triangles) = (0, 0);
+    for (name, primitives) in &groups {
+        let mut mesh_primitives = Vec::new();
+        for (material_name, primitive) in primitives {
+            let entry = materials.entry(material_name.clone());
+            let material_index = entry.index();
+            entry.or_insert_with(|| material(material_name));
+            let position = buffer.vec3(&primitive.positions, true);
+            let color = buffer.vec3(&primitive.colors, false);
+            let indices = buffer.indices(&primitive.indices);
+            mesh_primitives.push(json!({
+                "attributes": { "POSITION": position, "COLOR_0": color },
+                "indices": indices,
+                "material": material_index,
+            }));
+            vertices += primitive.positions.len();
+            triangles += primitive.indices.len() / 3;
+        }
+        meshes.push(json!({ "name": name, "primitives": mesh_primitives }));
+        nodes.push(json!({ "name": name, "mesh": meshes.len() - 1 }));
+    }
+    nodes[0]["children"] = json!((1..nodes.len()).collect::<Vec<_>>());
+
+    let gltf = json!({
+        "asset": { "version": "2.0", "generator": "brdb_cmd" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials.into_values().collect::<Vec<_>>(),
+        "buffers": [{ "byteLength": buffer.data.len() }],
+        "bufferViews": buffer.views,
+        "accessors": buffer.accessors,
+    });
+
+    // both chunks of a glb have to be padded to 4 bytes, json with spaces and binary with zeros
+    let mut json = serde_json::to_vec(&gltf)?;
+    json.resize(json.len().next_multiple_of(4), b' ');
+    let mut bin = buffer.data;
+    bin.resize(bin.len().next_multiple_of(4), 0);
+    let mut file = Vec::new();
+    file.extend(GLB_MAGIC);
+    file.extend(GLB_VERSION.to_le_bytes());
+    file.extend((12 + 8 + json.len() as u32 + 8 + bin.len() as u32).to_le_bytes());
+    file.extend((json.len() as u32).to_le_bytes());
+    file.extend(b"JSON");
+    file.extend(json);
+    file.extend((bin.len() as u32).to_le_bytes());
+    file.extend(b"BIN\0");
+    file.extend(bin);
+    fs::write(out, &file)?;
+
+    let text = format!(
+        "exported {count} bricks in {} nodes ({vertices} vertices, {triangles} triangles) to {}",
+        groups.len(),
+        out.display()
+    );
+    let json = json!({ "out": out, "bricks": count, "nodes": groups.len(), "vertices": vertices, "triangles": triangles, "bytes": file.len() });
+    Ok(Output::new(text, json))
+}
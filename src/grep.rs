@@ -0,0 +1,65 @@
+use brdb::{BrReader, Brdb, fs::BrFs};
+use regex::RegexBuilder;
+use serde_json::{Value, json};
+
+use crate::{edit::to_text, error::Error, files_in, output::Output, pattern, traverse};
+
+/// search the decoded contents of files for a regex, printing the matching lines
+pub fn grep(
+    db: &BrReader<Brdb>,
+    fs: &BrFs,
+    pattern: &str,
+    path: &str,
+    ignore_case: bool,
+    files_only: bool,
+) -> Result<Output, Error> {
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|error| Error::Other(format!("invalid regex: {error}")))?;
+
+    // folders are searched all the way down, like grep -r
+    let mut files = Vec::new();
+    for path in pattern::expand(fs, path)? {
+        let sub_fs = match path.as_str() {
+            "" => fs,
+            _ => traverse(fs, &path)?,
+        };
+        files.extend(files_in(sub_fs, &path).into_iter().map(|(path, _)| path));
+    }
+
+    let mut buf = String::new();
+    let mut json = Vec::new();
+    for path in files {
+        let ext = path.rsplit_once(".").map(|(_, ext)| ext).unwrap_or("");
+        let text = match to_text(db, &path, ext) {
+            Ok(text) => String::from_utf8_lossy(&text).into_owned(),
+            Err(error) => {
+                log::warn!("couldnt decode /{path}: {error}");
+                continue;
+            }
+        };
+
+        if text.contains('\0') {
+            if regex.is_match(&text) {
+                buf.push_str(&format!("binary file /{path} matches\n"));
+                json.push(json!({ "path": format!("/{path}"), "binary": true }));
+            }
+            continue;
+        }
+
+        for (number, line) in text.lines().enumerate() {
+            if !regex.is_match(line) {
+                continue;
+            }
+            if files_only {
+                buf.push_str(&format!("/{path}\n"));
+                json.push(json!(format!("/{path}")));
+                break;
+            }
+            buf.push_str(&format!("/{path}:{}: {}\n", number + 1, line.trim()));
+            json.push(json!({ "path": format!("/{path}"), "line": number + 1, "text": line.trim() }));
+        }
+    }
+    Ok(Output::new(buf, Value::Array(json)))
+}
@@ -0,0 +1,99 @@
+use std::{fs, path::Path};
+use brdb::{BrReader, Brdb, BrFsReader, fs::BrFs};
+use clap::ValueEnum;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+
+use crate::{cli::HashAlgo, error::Error, files_in, output::Output, output::hex, traverse};
+
+fn checksum(algo: HashAlgo, data: &[u8]) -> String {
+    match algo {
+        HashAlgo::Sha256 => hex(&Sha256::digest(data)),
+        HashAlgo::Blake3 => blake3::hash(data).to_hex().to_string(),
+    }
+}
+
+/// the checksum of every file below a folder in the world, of its contents after unpacking, one per line
+/// like `sha256sum --tag` prints them, so the manifest says which hash made it. that makes a manifest
+/// `check` can compare a world against later
+pub fn hash(db: &BrReader<Brdb>, path: &str, algo: HashAlgo) -> Result<Output, Error> {
+    let root = db.get_fs()?;
+    let fs = match path {
+        "" => &root,
+        _ => traverse(&root, path)?,
+    };
+    let mut files = match fs {
+        BrFs::File(file) => vec![(path.to_string(), file)],
+        _ => files_in(fs, path),
+    };
+    // sorted so two manifests of the same world can be diffed line by line
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut lines = Vec::new();
+    let mut sums = Vec::new();
+    for (path, file) in files {
+        let sum = checksum(algo, &file.read(&**db)?);
+        lines.push(format!("{} (/{path}) = {sum}", algo.tag()));
+        sums.push(json!({ "path": format!("/{path}"), "hash": sum }));
+    }
+    Ok(Output::new(lines.join("\n"), json!({ "algo": algo.name(), "files": sums })))
+}
+
+/// split a manifest line into the hash that made it, if it says, the path and the expected sum. lines are
+/// either tagged like `SHA256 (/path) = sum`, or `sum  /path` like sha256sum prints without --tag
+fn parse_line(line: &str) -> Result<(Option<HashAlgo>, &str, &str), Error> {
+    let tagged = line.split_once(" (").and_then(|(tag, rest)| {
+        let algo = HashAlgo::value_variants().iter().find(|algo| algo.tag() == tag)?;
+        let (path, sum) = rest.rsplit_once(") = ")?;
+        Some((Some(*algo), path, sum))
+    });
+    if let Some(tagged) = tagged {
+        return Ok(tagged);
+    }
+    // sha256sum puts a * before the name for binary files
+    let Some((sum, path)) = line.split_once(' ') else {
+        return Err(Error::Other(format!("not a manifest line: {line}")));
+    };
+    Ok((None, path.trim_start_matches([' ', '*']), sum))
+}
+
+/// compare a world against a manifest made by `hash`, saying for every file in it whether it still matches.
+/// the hash is the one the manifest was made with, and giving a different one is an error rather than
+/// every file failing. untagged manifests dont say, so they use `algo`, or sha256 without it
+pub fn check(db: &BrReader<Brdb>, manifest: &Path, algo: Option<HashAlgo>) -> Result<Output, Error> {
+    let manifest = fs::read_to_string(manifest)?;
+    let (mut lines, mut results) = (Vec::new(), Vec::new());
+    let mut bad = 0;
+    let mut used = algo.unwrap_or(HashAlgo::Sha256);
+    for line in manifest.lines().filter(|line| !line.trim().is_empty()) {
+        let (tag, path, expected) = parse_line(line)?;
+        used = match (tag, algo) {
+            (Some(tag), Some(algo)) if tag != algo => {
+                return Err(Error::Other(format!(
+                    "the manifest was made with {}, not {}. leave out --algo to use what it says",
+                    tag.name(),
+                    algo.name()
+                )));
+            }
+            (Some(tag), _) => tag,
+            (None, algo) => algo.unwrap_or(HashAlgo::Sha256),
+        };
+        let status = match db.find_file_by_path(path.trim_start_matches('/'))? {
+            Some(file) => match checksum(used, &db.find_blob(file.blob_id)?.read()?) == expected.to_lowercase() {
+                true => "OK",
+                false => "FAILED",
+            },
+            None => "MISSING",
+        };
+        bad += usize::from(status != "OK");
+        lines.push(format!("{path}: {status}"));
+        results.push(json!({ "path": path, "status": status }));
+    }
+
+    lines.push(match bad {
+        0 => format!("all {} files match", results.len()),
+        _ => format!("{bad} of {} files dont match", results.len()),
+    });
+    let output = Output::new(lines.join("\n"), json!({ "algo": used.name(), "files": results, "bad": bad }));
+    Ok(if bad > 0 { output.failed() } else { output })
+}
@@ -0,0 +1,62 @@
+use std::{fs, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb};
+use serde_json::json;
+
+use crate::{bricks::grid_ids, error::Error, format_size, format_time, output::Output, revisions::all_revisions};
+
+/// a time from Bundle.json, like 2025.06.01-12.30.00, the way the rest of brdb_cmd shows times.
+/// worlds that never set it have 0001.01.01-00.00.00 there, which is no time at all
+fn bundle_time(time: &str) -> Option<String> {
+    let (date, clock) = time.split_once('-')?;
+    match date {
+        "0001.01.01" => None,
+        _ => Some(format!("{} {}", date.replace('.', "-"), clock.replace('.', ":"))),
+    }
+}
+
+/// a summary of the world: what it's called, when it was made and changed, and how big it is
+pub fn info(db: &BrReader<Brdb>, path: &Path) -> Result<Output, Error> {
+    let bundle = db.bundle_json()?;
+    let revisions = all_revisions(db)?;
+    let size = fs::metadata(path)?.len();
+
+    let mut bricks = 0;
+    let mut grids = 0;
+    for grid in grid_ids(db)? {
+        if db.find_file_by_path(format!("World/0/Bricks/Grids/{grid}/ChunkIndex.mps"))?.is_none() {
+            continue;
+        }
+        grids += 1;
+        bricks += db.brick_chunk_index(grid)?.iter().map(|chunk| chunk.num_bricks as u64).sum::<u64>();
+    }
+
+    // fall back to when the first and last revisions were saved if the bundle doesnt say
+    let created = bundle_time(&bundle.created_at)
+        .or_else(|| revisions.first().map(|revision| format_time(revision.created_at)));
+    let updated = bundle_time(&bundle.updated_at)
+        .or_else(|| revisions.last().map(|revision| format_time(revision.created_at)));
+    let unknown = || String::from("unknown");
+
+    let lines = [
+        format!("name:         {}", bundle.name),
+        format!("description:  {}", bundle.description),
+        format!("created:      {}", created.clone().unwrap_or_else(unknown)),
+        format!("modified:     {}", updated.clone().unwrap_or_else(unknown)),
+        format!("game version: {}", bundle.game_version),
+        format!("revisions:    {}", revisions.len()),
+        format!("size:         {}", format_size(size)),
+        format!("bricks:       {bricks} in {grids} grids"),
+    ];
+    let json = json!({
+        "name": bundle.name,
+        "description": bundle.description,
+        "created": created,
+        "modified": updated,
+        "game_version": bundle.game_version,
+        "revisions": revisions.len(),
+        "bytes": size,
+        "bricks": bricks,
+        "grids": grids,
+    });
+    Ok(Output::new(lines.join("\n"), json))
+}
@@ -0,0 +1,1059 @@
+//! everything brdb_cmd does, for other rust tools that want to look inside brickadia worlds the same way
+//! without running the binary. [`open_world`] opens a world, [`lookup`] and [`traverse`] find files in it,
+//! [`mps`] decodes .mps files into json and back, [`diff`] compares worlds, [`bricks`] and [`stats`] read
+//! the bricks, and [`obj`], [`gltf`], [`brs`] and [`render`] export them. commands give back an
+//! [`output::Output`], which has the text brdb_cmd would print along with the same thing as json
+
+use std::{fs::File, io, io::IsTerminal, io::Write, fmt, ops::Deref, path::Path};
+use brdb::{Brdb, tables::BrFile, fs::BrFs, schema::ReadBrdbSchema, BrReader, BrFsReader, IntoReader};
+use serde_json::{Value, json};
+
+mod backup;
+pub mod bricks;
+pub mod brs;
+mod cat;
+mod check;
+mod chunks;
+pub mod cli;
+mod color;
+mod compact;
+pub mod config;
+mod completions;
+mod components;
+pub mod diff;
+mod du;
+mod edit;
+mod entities;
+mod env;
+pub mod error;
+mod extract;
+mod find;
+pub mod gltf;
+mod grep;
+mod hash;
+mod info;
+pub mod live;
+pub mod logging;
+pub mod lookup;
+mod merge;
+mod mesh;
+mod minigames;
+#[cfg(all(feature = "mount", unix))]
+mod mount;
+pub mod mps;
+pub mod obj;
+pub mod output;
+mod owners;
+mod pack;
+pub mod pattern;
+mod progress;
+mod prune;
+mod region;
+pub mod render;
+pub mod revisions;
+mod rewrite;
+pub mod schema;
+pub mod script;
+mod serve;
+mod shell;
+mod sql;
+mod stat;
+pub mod stats;
+#[cfg(test)]
+mod testing;
+mod thumbnail;
+mod transform;
+mod tree;
+mod tui;
+pub mod validate;
+mod watch;
+mod wires;
+mod worlds;
+mod write;
+
+use cli::{
+    BricksCommand, ChunksCommand, Cli, Command, EntitiesCommand, EntryType, EnvCommand, ExportCommand, Format, HashAlgo, ImportCommand, MinigamesCommand, OwnersCommand,
+    RegionCommand, RenderCommand, SchemaCommand, StatsCommand, ThumbnailCommand, TransformCommand, WiresCommand, WorldsCommand,
+};
+use error::Error;
+use output::Output;
+
+/// convert a vector array of strings to a multiline string
+fn strings_to_lines<I, T>(iter: I) -> String
+where
+    I: Iterator<Item = T>,
+    T: AsRef<str>,
+{
+    let mut buf = String::new();
+    for name in iter {
+        buf.push_str(name.as_ref());
+        buf.push('\n');
+    }
+    buf
+}
+
+/// get brfs object based on path
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum TraverseError {
+  NoParentOfRoot,
+  NotFound(String),
+  TraverseIntoFile,
+}
+impl fmt::Display for TraverseError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TraverseError::NoParentOfRoot => write!(f, "You tried to go above the root directory."),
+      TraverseError::NotFound(path) => write!(f, "cannot access '{}': No such file or directory.", path),
+      TraverseError::TraverseIntoFile => write!(f, "Tried to traverse into a file, not a folder."),
+    }
+  }
+}
+
+/// get a BrFs object for the given path
+pub fn traverse<'a>(root: &'a BrFs, path: &str) -> Result<&'a BrFs, TraverseError> {
+  // thanks to voximity for this function
+  let mut traversal = vec![root];
+  for part in path.split('/') {
+    match part {
+      "." => (),
+      ".." => {
+        traversal.pop().ok_or(TraverseError::NoParentOfRoot)?;
+      }
+      part => match traversal.last().ok_or(TraverseError::NoParentOfRoot)? {
+        BrFs::Root(map) | BrFs::Folder(_, map) => match map.get(part) {
+          Some(v) => traversal.push(v),
+          None => return Err(TraverseError::NotFound(format!("/{}", path.trim_start_matches('/')))),
+        },
+        BrFs::File(_) => return Err(TraverseError::TraverseIntoFile),
+      },
+    }
+  }
+  traversal.pop().ok_or(TraverseError::NoParentOfRoot)
+}
+
+/// every file below a folder, along with its path from that folder
+pub fn files_in<'a>(fs: &'a BrFs, path: &str) -> Vec<(String, &'a BrFile)> {
+    let mut files = Vec::new();
+    match fs {
+        BrFs::Root(map) | BrFs::Folder(_, map) => {
+            for (name, child) in map {
+                let child_path = match path {
+                    "" => name.clone(),
+                    _ => format!("{path}/{name}"),
+                };
+                files.extend(files_in(child, &child_path));
+            }
+        }
+        BrFs::File(file) => files.push((path.to_string(), file)),
+    }
+    files
+}
+
+/// compressed and uncompressed size of a blob, without reading its contents
+fn blob_sizes(db: &Brdb, blob_id: i64) -> Result<(u64, u64), Error> {
+    let (compressed, uncompressed): (i64, i64) = db
+        .conn
+        .query_row(
+            "SELECT size_compressed, size_uncompressed FROM blobs WHERE blob_id = ?1",
+            [blob_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+    Ok((compressed as u64, uncompressed as u64))
+}
+
+/// turn a unix timestamp into a utc date like 2025-01-31 12:00:00
+pub fn format_time(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86400);
+    let secs = timestamp.rem_euclid(86400);
+
+    // days since 1970 to a calendar date, from howard hinnant's civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/// turn a number of bytes into something like 1.5 KiB
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// every folder and file below a folder, with folders ending in a slash
+pub fn entries_in<'a>(fs: &'a BrFs, path: &str) -> Vec<(String, &'a BrFs)> {
+    let mut entries = Vec::new();
+    if let BrFs::Root(map) | BrFs::Folder(_, map) = fs {
+        for (name, child) in map {
+            let child_path = match path {
+                "" => name.clone(),
+                _ => format!("{path}/{name}"),
+            };
+            match child {
+                BrFs::File(_) => entries.push((child_path, child)),
+                _ => {
+                    entries.push((format!("{child_path}/"), child));
+                    entries.extend(entries_in(child, &child_path));
+                }
+            }
+        }
+    }
+    entries
+}
+
+/// how ls should show the entries it finds
+#[derive(Default, Clone, Copy)]
+struct ListOptions<'a> {
+    /// include everything in the subfolders too
+    recursive: bool,
+    /// show the type, sizes and blob id of every entry
+    long: bool,
+    /// put a slash after folder names
+    classify: bool,
+    /// color folders so they stand out from files
+    color: bool,
+    /// only show files or only folders
+    kind: Option<EntryType>,
+    /// only show files with one of these extensions. folders dont have one, so theyre left out too
+    ext: &'a [String],
+}
+
+impl ListOptions<'_> {
+    /// whether an entry gets past --type and --ext
+    fn shows(&self, name: &str, fs: &BrFs) -> bool {
+        let kind = match fs {
+            BrFs::File(_) => EntryType::File,
+            _ => EntryType::Folder,
+        };
+        if self.kind.is_some_and(|wanted| wanted != kind) {
+            return false;
+        }
+        if self.ext.is_empty() {
+            return true;
+        }
+        let ext = name.rsplit_once('.').map_or("", |(_, ext)| ext);
+        kind == EntryType::File && self.ext.iter().any(|wanted| wanted.trim_start_matches('.').eq_ignore_ascii_case(ext))
+    }
+}
+
+/// an entry found by ls as json, with its sizes and blob id in long form
+fn entry_json(db: &Brdb, base: &str, name: &str, fs: &BrFs, long: bool) -> Result<Value, Error> {
+    let path = match base {
+        "" => name.trim_end_matches('/').to_string(),
+        _ => format!("{base}/{}", name.trim_end_matches('/')),
+    };
+    let mut entry = json!({
+        "name": name,
+        "path": format!("/{path}"),
+        "type": match fs {
+            BrFs::File(_) => "file",
+            _ => "folder",
+        },
+    });
+    if let (true, BrFs::File(file)) = (long, fs) {
+        let (compressed, size) = match file.content_id {
+            Some(blob_id) => {
+                let (compressed, size) = blob_sizes(db, blob_id)?;
+                (json!(compressed), json!(size))
+            }
+            None => (Value::Null, Value::Null),
+        };
+        entry["compressed"] = compressed;
+        entry["size"] = size;
+        entry["blob"] = json!(file.content_id);
+    }
+    Ok(entry)
+}
+
+/// how read should show the files it reads
+#[derive(Default, Clone, Copy)]
+struct ReadOptions {
+    /// how to show json and .mps files
+    format: Format,
+    /// leave json and .mps files as they're stored
+    raw: bool,
+    /// show a hex dump instead of the contents
+    hex: bool,
+    /// highlight json and schemas
+    color: bool,
+}
+
+/// one line per entry with its type, compressed size, uncompressed size and blob id
+fn long_listing(entries: &[Value], names: &[String]) -> String {
+    let field = |entry: &Value, key: &str| match &entry[key] {
+        Value::Null => String::from("-"),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    };
+    let mut rows = vec![[
+        String::from("TYPE"),
+        String::from("COMPRESSED"),
+        String::from("SIZE"),
+        String::from("BLOB"),
+        String::from("NAME"),
+    ]];
+    for (entry, name) in entries.iter().zip(names) {
+        rows.push([
+            field(entry, "type"),
+            field(entry, "compressed"),
+            field(entry, "size"),
+            field(entry, "blob"),
+            name.clone(),
+        ]);
+    }
+
+    // every column is as wide as its widest value, numbers line up on the right
+    let mut widths = [0; 4];
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.len());
+        }
+    }
+    let lines = rows.iter().map(|[kind, compressed, size, blob, name]| {
+        format!(
+            "{kind:<w0$}  {compressed:>w1$}  {size:>w2$}  {blob:>w3$}  {name}",
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+        )
+    });
+    strings_to_lines(lines)
+}
+
+/// show the entries found by ls, either just their names or in long form, along with them as json
+fn render_entries(
+    db: &Brdb,
+    base: &str,
+    entries: &[(String, &BrFs)],
+    options: ListOptions,
+) -> Result<(String, Vec<Value>), Error> {
+    // folders come first, except in recursive listings where everything stays under its folder
+    let mut entries = entries.to_vec();
+    entries.retain(|(name, fs)| options.shows(name, fs));
+    if !options.recursive {
+        entries.sort_by_key(|(_, fs)| matches!(fs, BrFs::File(_)));
+    }
+
+    let json = entries
+        .iter()
+        .map(|(name, fs)| entry_json(db, base, name, fs, options.long))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let names: Vec<String> = entries
+        .iter()
+        .map(|(name, fs)| match fs {
+            BrFs::File(_) => name.clone(),
+            _ => {
+                let name = match options.classify && !name.ends_with('/') {
+                    true => format!("{name}/"),
+                    false => name.clone(),
+                };
+                match options.color {
+                    true => color::folder(&name),
+                    false => name,
+                }
+            }
+        })
+        .collect();
+    let text = match options.long {
+        true => long_listing(&json, &names),
+        false => strings_to_lines(names.iter()),
+    };
+    Ok((text, json))
+}
+
+/// show files in specified path
+fn list_dir(db: &Brdb, fs: &BrFs, path: &str, options: ListOptions) -> Result<Output, Error> {
+    let mut path_ = path;
+    path_ = path_.trim_start_matches("/");
+    path_ = path_.trim_end_matches("/");
+
+    let paths = pattern::expand(fs, path_)?;
+    let mut files = Vec::new();
+    let mut folders = Vec::new();
+
+    for path_ in &paths {
+        let sub_fs = match path_.as_str() {
+            "" => fs,
+            _  => traverse(fs, path_)?
+        };
+
+        match sub_fs {
+              // paths are relative to the folder being listed
+              BrFs::Root(_) | BrFs::Folder(..) if options.recursive => folders.push((path_, entries_in(sub_fs, ""))),
+              BrFs::Root(map) | BrFs::Folder(_, map) => {
+                  folders.push((path_, map.iter().map(|(name, child)| (name.clone(), child)).collect()))
+              }
+              _ => {
+                  /* 
+                   * lol just show the path to the file
+                   * like what linux `ls` does
+                   */
+                  files.push((path_.clone(), sub_fs))
+              }
+        }
+    }
+
+    // a single folder is listed as is, otherwise every folder gets a header like `ls a b` does
+    if files.is_empty() && folders.len() == 1 {
+        let (text, json) = render_entries(db, folders[0].0, &folders[0].1, options)?;
+        return Ok(Output::new(text, Value::Array(json)));
+    }
+    let mut sections = Vec::new();
+    let mut json = Vec::new();
+    if !files.is_empty() {
+        let (text, entries) = render_entries(db, "", &files, options)?;
+        sections.push(text);
+        json.extend(entries);
+    }
+    for (path_, entries) in &folders {
+        let (text, entries) = render_entries(db, path_, entries, options)?;
+        sections.push(format!("/{path_}:\n{text}"));
+        json.extend(entries);
+    }
+    Ok(Output::new(sections.join("\n"), Value::Array(json)))
+}
+
+/// decode a .mps file as json straight into a file, or to stdout
+fn stream_mps(db: &BrReader<Brdb>, path: &str, output: Option<&Path>) -> Result<Output, Error> {
+    let mut out: Box<dyn Write> = match output {
+        Some(file) => Box::new(File::create(file)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    match mps::decode_to(db, path, &mut out).and_then(|()| Ok(writeln!(out)?)) {
+        Err(Error::Io(error)) if error.kind() == io::ErrorKind::BrokenPipe => Ok(Output::none()),
+        written => written.map(|()| Output::none()),
+    }
+}
+
+/// read file in brdb based on file type
+fn read_file(db: &BrReader<Brdb>, path: &str, options: ReadOptions) -> Result<Output, Error> {
+    let file_ext = path.rsplit_once(".").map(|(_, ext)| ext).unwrap_or("");
+
+    match file_ext {
+        // any file can be dumped as hex, whatever it is
+        _ if options.hex => {
+            let file_bytes = db.read_file(path)?;
+            let json = json!({ "path": format!("/{path}"), "hex": output::hex(&file_bytes) });
+            Ok(Output::new(output::hex_dump(&file_bytes), json))
+        }
+        // structured files are decoded and pretty printed, unless the stored bytes are wanted
+        "json" | "mps" if !options.raw => {
+            let value = match file_ext {
+                "mps" => mps::decode(db, path)?,
+                _ => serde_json::from_slice(&db.read_file(path)?)?,
+            };
+            let text = match options.format {
+                Format::Json if options.color => color::json(&serde_json::to_string_pretty(&value)?),
+                Format::Json => serde_json::to_string_pretty(&value)?,
+                Format::Yaml => serde_yaml::to_string(&value)
+                    .map_err(|error| Error::Other(format!("couldnt write yaml: {error}")))?,
+            };
+            Ok(Output::new(text, json!({ "path": format!("/{path}"), "content": value })))
+        }
+        "schema" if !options.raw => {
+            // fetch the raw file data
+            let global_data = db.global_data()?;
+            let schema = db.read_file(path)?
+            .as_slice()
+            // convert it to a schema object
+            .read_brdb_schema_with_data(global_data)?;
+
+            // return a string representation of the schema
+            let text = format!("{schema}");
+            let json = json!({ "path": format!("/{path}"), "content": text });
+            match options.color {
+                true => Ok(Output::new(color::schema(&text), json)),
+                false => Ok(Output::new(text, json)),
+            }
+        }
+        _ => {
+            /*
+             * for any other filetype than schema,
+             * or .mps, .json and .schema with --raw,
+             * just get the raw file bytes
+             */
+            let file_bytes = db.read_file(path)?;
+            let mut json = json!({ "path": format!("/{path}") });
+            match (file_ext, std::str::from_utf8(&file_bytes)) {
+                ("json", Ok(text)) => json["content"] = serde_json::from_str(text).unwrap_or_else(|_| json!(text)),
+                (_, Ok(text)) => json["content"] = json!(text),
+                // binary files can't go in a json string as they are
+                (_, Err(_)) => json["hex"] = json!(output::hex(&file_bytes)),
+            }
+            Ok(Output::raw(file_bytes, json))
+        }
+    }
+}
+
+/// read every file matching a path, which can be a glob pattern
+fn read_paths(db: &BrReader<Brdb>, fs: &BrFs, path: &str, options: ReadOptions) -> Result<Output, Error> {
+    let paths = pattern::expand(fs, path)?;
+    if let [path] = paths.as_slice() {
+        return match path.as_str() {
+            "" => Err(Error::Other(String::from("/ is a folder"))),
+            _ => match traverse(fs, path)? {
+                BrFs::File(_) => read_file(db, path, options),
+                _ => Err(Error::Other(format!("/{path} is a folder"))),
+            },
+        };
+    }
+
+    let mut text = Vec::new();
+    let mut json = Vec::new();
+    let mut raw = true;
+    for path in paths.iter().filter(|path| matches!(traverse(fs, path), Ok(BrFs::File(_)))) {
+        let output = read_file(db, path, options)?;
+        // yaml documents need a separator to be read back as several
+        if options.format == Format::Yaml && !text.is_empty() {
+            text.extend(b"---\n");
+        }
+        text.extend(&output.text);
+        // raw files are put together as they are, like cat does
+        if !output.raw && !output.text.ends_with(b"\n") {
+            text.push(b'\n');
+        }
+        raw &= output.raw;
+        json.push(output.json);
+    }
+    Ok(Output { text, json: Value::Array(json), raw, failed: false })
+}
+
+/// ask a yes or no question on the terminal, anything but yes counts as no. the question goes to stderr so
+/// it doesnt end up in --json output, and without a terminal to answer on it refuses, naming the `flag`
+/// that skips the question
+fn confirm(question: &str, flag: &str) -> Result<bool, Error> {
+    if !io::stdin().is_terminal() {
+        return Err(Error::Other(format!("theres no terminal to ask \"{question}\" on, add {flag} to go ahead anyway")));
+    }
+    eprint!("{question} [y/N] ");
+    let _ = io::stderr().flush();
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return Ok(false);
+    }
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// how to open the worlds a command works on
+#[derive(Default, Clone, Copy)]
+pub struct OpenOptions {
+    /// look at the world as it was at this revision
+    pub revision: Option<i64>,
+    /// read a copy of the world instead of the file itself
+    pub snapshot: bool,
+    /// open the world for writing, only for commands that save a new revision into it
+    pub write: bool,
+    /// refuse to open the world for writing at all
+    pub read_only: bool,
+}
+
+/// a world opened for one command. in a --script every command borrows the world the script opened,
+/// which goes back to the script once the command is done with it
+pub struct World {
+    db: Option<BrReader<Brdb>>,
+    lent: bool,
+}
+
+impl World {
+    fn new(db: BrReader<Brdb>) -> Self {
+        Self { db: Some(db), lent: false }
+    }
+}
+
+impl Deref for World {
+    type Target = BrReader<Brdb>;
+
+    fn deref(&self) -> &Self::Target {
+        // only ever taken out when it's dropped
+        self.db.as_ref().expect("world was already given back")
+    }
+}
+
+impl Drop for World {
+    fn drop(&mut self) {
+        if self.lent
+            && let Some(db) = self.db.take()
+        {
+            script::give_back(db);
+        }
+    }
+}
+
+/// open a world file and get a reader for it, optionally as it was at an older revision
+pub fn open_world(path: &Path, options: OpenOptions) -> Result<World, Error> {
+    if options.write && options.read_only {
+        return Err(Error::Other(String::from("this command changes the world, which --read-only doesnt allow")));
+    }
+    if let Some(db) = script::lend(path, options) {
+        return Ok(World { db: Some(db), lent: true });
+    }
+    let db = match options.snapshot {
+        true => live::snapshot(path)?,
+        false => {
+            if options.write {
+                live::check_writable(path)?;
+            } else if live::is_live(path) {
+                log::warn!("{} is open in brickadia right now, add --snapshot to read a consistent copy of it", path.display());
+            }
+            log::info!("opening {} for {}", path.display(), if options.write { "writing" } else { "reading" });
+            match options.write {
+                true => Brdb::open(path),
+                false => Brdb::open_readonly(path),
+            }
+            .map_err(|error| Error::BadWorld(path.to_path_buf(), error.to_string()))?
+        }
+    };
+    Ok(World::new(
+        match options.revision {
+            None => db,
+            Some(id) => {
+                log::info!("looking at revision {id}");
+                revisions::at_revision(db, id)?
+            }
+        }
+        .into_reader(),
+    ))
+}
+
+/// run a command and get back what it has to show
+fn run(command: Command, open: OpenOptions, color: bool, json: bool) -> Result<Output, Error> {
+    Ok(match command {
+        Command::Ls { world, path, recursive, long, classify, kind, ext } => {
+            let db = open_world(&world, open)?;
+            let fs = lookup::tree_to(&db, &path, recursive)?;
+            let options = ListOptions { recursive, long, classify, color, kind, ext: &ext };
+            list_dir(&db, &fs, path.trim_start_matches("/"), options)?
+        }
+        Command::Read { world, path, format, raw, hex, output } => {
+            let db = open_world(&world, open)?;
+            let fs = lookup::tree_to(&db, &path, false)?;
+            let path = path.trim_start_matches("/");
+            // a .mps file going into a file or another program is written out while it's decoded,
+            // since some are big enough that holding all of it as text takes a lot of memory
+            let streams = !json
+                && !color
+                && format == Format::Json
+                && !raw
+                && !hex
+                && path.ends_with(".mps")
+                && (output.is_some() || !io::stdout().is_terminal())
+                && matches!(traverse(&fs, path), Ok(BrFs::File(_)));
+            match streams {
+                true => stream_mps(&db, path, output.as_deref())?,
+                false => read_paths(&db, &fs, path, ReadOptions { format, raw, hex, color })?,
+            }
+        }
+        Command::Edit { world, path } => {
+            let db = open_world(&world, open)?;
+            edit::edit_file(&db, path.trim_start_matches("/"))?
+        }
+        Command::Write { world, local, path } => {
+            let db = open_world(&world, open)?;
+            write::write_file(&db, &local, path.trim_start_matches("/"))?
+        }
+        Command::Extract { world, output, decode } => {
+            let db = open_world(&world, open)?;
+            extract::extract(&db, &output, decode)?
+        }
+        Command::Pack { input, world } => pack::pack(&input, &world)?,
+        Command::Tree { world, path, sizes } => {
+            let db = open_world(&world, open)?;
+            tree::tree(&db, path.trim_start_matches("/"), sizes)?
+        }
+        Command::Find { world, root, pattern, regex } => {
+            let db = open_world(&world, open)?;
+            let fs: BrFs = db.get_fs()?;
+            find::find(&fs, &root, &pattern, regex)?
+        }
+        Command::Grep { world, pattern, path, ignore_case, files_with_matches } => {
+            let db = open_world(&world, open)?;
+            let fs: BrFs = db.get_fs()?;
+            grep::grep(&db, &fs, &pattern, &path, ignore_case, files_with_matches)?
+        }
+        Command::Cat { world, paths, header, .. } => {
+            let db = open_world(&world, open)?;
+            let fs: BrFs = db.get_fs()?;
+            cat::cat(&db, &fs, &paths, header)?
+        }
+        Command::Info { world } => {
+            let db = open_world(&world, open)?;
+            info::info(&db, &world)?
+        }
+        Command::Thumbnail { command, world, output } => match (command, world, output) {
+            (Some(ThumbnailCommand::Set { world, image }), ..) => {
+                let db = open_world(&world, open)?;
+                thumbnail::set(&db, &image)?
+            }
+            (None, Some(world), Some(output)) => {
+                let db = open_world(&world, open)?;
+                thumbnail::get(&db, &output)?
+            }
+            // clap requires both when there's no subcommand
+            (None, ..) => unreachable!(),
+        },
+        Command::Stat { world, path } => {
+            let db = open_world(&world, open)?;
+            stat::stat(&db, path.trim_start_matches("/"))?
+        }
+        Command::Du { world, path } => {
+            let db = open_world(&world, open)?;
+            du::du(&db, path.trim_start_matches("/"))?
+        }
+        Command::Hash { world, path, algo, check } => {
+            let db = open_world(&world, open)?;
+            match check {
+                Some(manifest) => hash::check(&db, &manifest, algo)?,
+                None => hash::hash(&db, path.trim_start_matches("/"), algo.unwrap_or(HashAlgo::Sha256))?,
+            }
+        }
+        Command::Validate { world, paths } => {
+            let db = open_world(&world, open)?;
+            validate::validate(&db, &paths)?
+        }
+        Command::Cp { world, src, dst, recursive } => {
+            let db = open_world(&world, open)?;
+            write::copy(&db, &src, &dst, recursive)?
+        }
+        Command::Mv { world, src, dst } => {
+            let db = open_world(&world, open)?;
+            write::move_entry(&db, &src, &dst)?
+        }
+        Command::Rm { world, path, recursive, force } => {
+            let db = open_world(&world, open)?;
+            write::remove(&db, &path, recursive, force)?
+        }
+        Command::CopyTo { from: from_path, src, to: to_path, dst, recursive } => {
+            let same_world = write::same_file(&from_path, &to_path)?;
+            let from = open_world(&from_path, OpenOptions { write: false, ..open })?;
+            // only the second world is written to, so --revision and --snapshot are about the first
+            let to = open_world(&to_path, OpenOptions { revision: None, snapshot: false, write: true, ..open })?;
+            write::copy_to(&from, &src, &to, &dst, recursive, same_world)?
+        }
+        Command::Restore { backup, path, into, recursive } => {
+            let from = open_world(&backup, OpenOptions { write: false, ..open })?;
+            // like copy-to, only the world being restored into is written to, so --revision is about the backup
+            let to = open_world(&into, OpenOptions { revision: None, snapshot: false, write: true, ..open })?;
+            write::restore(&from, &backup, &path, &to, recursive)?
+        }
+        Command::Revisions { world } => {
+            let db = open_world(&world, open)?;
+            revisions::revisions(&db)?
+        }
+        Command::Rollback { world, id } => revisions::rollback(&world, id)?,
+        Command::Prune { world, dry_run } => {
+            let db = open_world(&world, open)?;
+            prune::prune(&db, dry_run)?
+        }
+        Command::Check { world } => {
+            let db = open_world(&world, open)?;
+            check::check(&db)?
+        }
+        Command::Compact { world, keep_revisions, yes } => compact::compact(&world, keep_revisions, yes)?,
+        Command::Backup { world, dir, keep } => {
+            let dir = dir.or_else(|| config::get().backup_dir.clone());
+            backup::backup(&world, dir.as_deref(), keep)?
+        }
+        Command::Diff { a, b, content, .. } => {
+            let a = open_world(&a, open)?;
+            let b = open_world(&b, OpenOptions { revision: None, ..open })?;
+            diff::diff_worlds(&a, &b, content)?
+        }
+        Command::DiffRev { world, a, b, content, .. } => diff::diff_revisions(&world, a, b, content)?,
+        Command::Watch { world, interval } => watch::watch(&world, interval, json)?,
+        Command::Merge { base, other, out, prefer_other, offset } => {
+            let base = open_world(&base, open)?;
+            let other = open_world(&other, OpenOptions { revision: None, ..open })?;
+            merge::merge(&base, &other, &out, prefer_other, offset)?
+        }
+        Command::Bricks { command, world, grid, limit } => match (command, world) {
+            (Some(BricksCommand::Find { world, grid, filter, export }), _) => {
+                let db = open_world(&world, open)?;
+                bricks::find(&db, grid, &filter, export.as_deref())?
+            }
+            (Some(BricksCommand::Remove { world, filter, dry_run, yes }), _) => {
+                let db = open_world(&world, open)?;
+                bricks::remove(&db, &filter, dry_run, yes)?
+            }
+            (Some(BricksCommand::Recolor { world, from, to }), _) => {
+                let db = open_world(&world, open)?;
+                bricks::recolor(&db, &from, &to)?
+            }
+            (Some(BricksCommand::Rematerial { world, from, to, filter }), _) => {
+                let db = open_world(&world, open)?;
+                bricks::rematerial(&db, &from, &to, &filter)?
+            }
+            (None, Some(world)) => {
+                let db = open_world(&world, open)?;
+                bricks::bricks(&db, grid, limit)?
+            }
+            (None, None) => unreachable!(),
+        },
+        Command::Owners { command, world } => match (command, world) {
+            (Some(OwnersCommand::Reassign { world, from, to }), _) => {
+                let db = open_world(&world, open)?;
+                owners::reassign(&db, &from, &to)?
+            }
+            (None, Some(world)) => {
+                let db = open_world(&world, open)?;
+                owners::owners(&db)?
+            }
+            (None, None) => unreachable!(),
+        },
+        Command::Chunks { command } => match command {
+            ChunksCommand::List { world, grid, by_size } => {
+                let db = open_world(&world, open)?;
+                chunks::list(&db, grid, by_size)?
+            }
+            ChunksCommand::Get { world, chunk, grid, .. } => {
+                let db = open_world(&world, open)?;
+                chunks::get(&db, grid, chunk)?
+            }
+        },
+        Command::Export { command } => match command {
+            ExportCommand::Brs { world, out } => {
+                let db = open_world(&world, open)?;
+                brs::export(&db, &out)?
+            }
+            ExportCommand::Obj { world, out, vertex_colors, scale } => {
+                let db = open_world(&world, open)?;
+                obj::export(&db, &out, vertex_colors, scale)?
+            }
+            ExportCommand::Gltf { world, out, group_by, scale } => {
+                let db = open_world(&world, open)?;
+                gltf::export(&db, &out, group_by, scale)?
+            }
+        },
+        Command::Import { command } => match command {
+            ImportCommand::Brs { world, input, offset } => {
+                let db = open_world(&world, open)?;
+                brs::import(&db, &input, offset)?
+            }
+        },
+        Command::Render { command } => match command {
+            RenderCommand::Map { world, out, scale, color_by, min, max } => {
+                let db = open_world(&world, open)?;
+                render::map(&db, &out, scale, color_by, min, max)?
+            }
+            RenderCommand::Heatmap { world, out, cell, pixels } => {
+                let db = open_world(&world, open)?;
+                render::heatmap(&db, &out, cell, pixels)?
+            }
+        },
+        Command::Transform { command } => match command {
+            TransformCommand::Translate { world, offset } => {
+                let db = open_world(&world, open)?;
+                transform::translate(&db, offset)?
+            }
+            TransformCommand::Rotate { world, turns } => {
+                let db = open_world(&world, open)?;
+                transform::rotate(&db, turns)?
+            }
+            TransformCommand::Mirror { world, axis } => {
+                let db = open_world(&world, open)?;
+                transform::mirror(&db, axis)?
+            }
+        },
+        Command::Region { command } => match command {
+            RegionCommand::Export { world, min, max, out } => {
+                let db = open_world(&world, open)?;
+                region::export(&db, min, max, &out)?
+            }
+        },
+        Command::Components { world, target, grid } => {
+            let db = open_world(&world, open)?;
+            components::components(&db, grid, target.as_deref())?
+        }
+        Command::Wires { command } => match command {
+            WiresCommand::Export { world, format, .. } => {
+                let db = open_world(&world, open)?;
+                wires::export(&db, format)?
+            }
+        },
+        Command::Env { command } => match command {
+            EnvCommand::Show { world } => {
+                let db = open_world(&world, open)?;
+                env::show(&db)?
+            }
+            EnvCommand::Set { world, pointer, value } => {
+                let db = open_world(&world, open)?;
+                env::set(&db, &pointer, &value)?
+            }
+        },
+        Command::Minigames { command } => match command {
+            MinigamesCommand::List { world } => {
+                let db = open_world(&world, open)?;
+                minigames::list(&db)?
+            }
+            MinigamesCommand::Export { world, index, file } => {
+                let db = open_world(&world, open)?;
+                minigames::export(&db, index, &file)?
+            }
+            MinigamesCommand::Import { world, file, index } => {
+                let db = open_world(&world, open)?;
+                minigames::import(&db, &file, index)?
+            }
+        },
+        Command::Entities { command } => match command {
+            EntitiesCommand::List { world } => {
+                let db = open_world(&world, open)?;
+                entities::entities(&db)?
+            }
+            EntitiesCommand::Show { world, id } => {
+                let db = open_world(&world, open)?;
+                entities::show(&db, id)?
+            }
+        },
+        Command::Schema { command } => match command {
+            SchemaCommand::List { world } => {
+                let db = open_world(&world, open)?;
+                schema::list(&db)?
+            }
+            SchemaCommand::Show { world, name } => {
+                let db = open_world(&world, open)?;
+                schema::show(&db, &name)?
+            }
+            SchemaCommand::Diff { a, b } => {
+                let a = open_world(&a, open)?;
+                let b = open_world(&b, OpenOptions { revision: None, ..open })?;
+                schema::diff(&a, &b)?
+            }
+        },
+        Command::Stats { command } => match command {
+            StatsCommand::Owners { world } => {
+                let db = open_world(&world, open)?;
+                stats::owners(&db)?
+            }
+            StatsCommand::Colors { world, palette } => {
+                let db = open_world(&world, open)?;
+                stats::colors(&db, color, palette.as_deref())?
+            }
+            StatsCommand::Materials { world, by_color, csv } => {
+                let db = open_world(&world, open)?;
+                stats::materials(&db, by_color, csv)?
+            }
+            StatsCommand::Assets { world } => {
+                let db = open_world(&world, open)?;
+                stats::assets(&db)?
+            }
+            StatsCommand::Dedup { world } => {
+                let db = open_world(&world, open)?;
+                stats::dedup(&db)?
+            }
+            StatsCommand::Compression { world } => {
+                let db = open_world(&world, open)?;
+                stats::compression(&db)?
+            }
+        },
+        Command::Sql { world, query } => {
+            let db = open_world(&world, open)?;
+            sql::sql(&db, &query)?
+        }
+        Command::Shell { world } => {
+            let db = open_world(&world, open)?;
+            let fs: BrFs = db.get_fs()?;
+            shell::run(&db, &fs);
+            Output::none()
+        }
+        Command::Tui { world } => {
+            let db = open_world(&world, open)?;
+            let fs: BrFs = db.get_fs()?;
+            tui::run(&db, &fs)?;
+            Output::none()
+        }
+        #[cfg(all(feature = "mount", unix))]
+        Command::Mount { world, mountpoint, decode } => {
+            let db = open_world(&world, open)?;
+            let fs: BrFs = db.get_fs()?;
+            mount::mount_world(db, &fs, &mountpoint, decode)?
+        }
+        #[cfg(all(feature = "mount", not(unix)))]
+        Command::Mount { .. } => return Err(Error::Other(String::from("mount only works on linux and macos for now"))),
+        Command::Serve { world, port, host } => {
+            serve::serve(&world, open, &host, port)?;
+            Output::none()
+        }
+        Command::Worlds { command } => match command {
+            WorldsCommand::List { dir } => worlds::list(dir.as_deref())?,
+        },
+        Command::Config => config::show()?,
+        Command::Completions { shell } => completions::completions(shell),
+        Command::CompletePath { world, partial } => {
+            // this runs while typing, so a world that cant be read just has nothing to offer
+            match open_world(&world, open).and_then(|db| Ok(db.get_fs()?)) {
+                Ok(fs) => completions::complete_path(&fs, &partial),
+                Err(_) => Output::none(),
+            }
+        }
+    })
+}
+
+/// run a command and show its output, or its error, and get back the code to exit with
+pub fn execute(command: Command, cli: &Cli) -> i32 {
+    let output_file = command.output_file();
+    progress::set_quiet(cli.quiet);
+    let color = !cli.json && color::enabled(cli.color, output_file.is_some());
+    let page = !cli.no_pager && command.pages() && output_file.is_none() && io::stdout().is_terminal();
+
+    // old revisions and snapshots are only looked at, never written to
+    let open = OpenOptions {
+        revision: cli.revision,
+        snapshot: cli.snapshot,
+        write: command.writes(),
+        read_only: cli.read_only,
+    };
+    let result = match (cli.revision, cli.snapshot, open.write) {
+        _ if cli.read_only && open.write => Err(Error::Other(String::from("this command changes the world, which --read-only doesnt allow"))),
+        (Some(_), _, true) => Err(Error::Other(String::from("--revision only works with commands that dont change the world"))),
+        (_, true, true) => Err(Error::Other(String::from("--snapshot only works with commands that dont change the world"))),
+        _ => run(command, open, color, cli.json),
+    };
+
+    let mut exit_code = i32::from(matches!(&result, Ok(output) if output.failed));
+    let written = match (result, output_file) {
+        (Ok(output), Some(path)) => output.save(&path, cli.json),
+        // raw files can be binary, which pagers dont like
+        (Ok(output), None) if page && !output.raw => output.page(cli.json),
+        (Ok(output), None) => output.print(cli.json),
+        // errors go to stderr so they dont end up mixed into the output or hidden in the file,
+        // except with --json where scripts expect them as json on stdout
+        (Err(error), _) => {
+            exit_code = error.exit_code();
+            log::debug!("failed with {error:?}");
+            match cli.json {
+                true => Output::new("", json!({ "error": error.to_string(), "code": exit_code })).print(true),
+                false => writeln!(io::stderr(), "error: {error}"),
+            }
+        }
+    };
+    // a closed pipe just means whoever was reading has all they wanted, like `| head`
+    if let Err(error) = written
+        && error.kind() != io::ErrorKind::BrokenPipe
+    {
+        eprintln!("error: couldnt write output: {error}");
+        return Error::Io(error).exit_code();
+    }
+    exit_code
+}
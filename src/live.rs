@@ -0,0 +1,76 @@
+use std::{ffi::OsString, path::{Path, PathBuf}, time::Duration};
+use brdb::Brdb;
+use rusqlite::{Connection, ErrorCode, OpenFlags, backup::Backup};
+
+use crate::error::Error;
+
+/// a file sqlite keeps next to the world while it's open, like world.brdb-wal
+pub fn sidecar(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// whether something else, like a brickadia server, has the world open right now.
+/// the -wal and -journal files only stick around while a connection is writing to it,
+/// and a server that locks the database outright can't even be read from
+pub fn is_live(path: &Path) -> bool {
+    if sidecar(path, "-wal").exists() || sidecar(path, "-journal").exists() {
+        return true;
+    }
+    let Ok(conn) = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) else {
+        return false;
+    };
+    let busy = conn.query_row("SELECT count(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0));
+    matches!(
+        busy,
+        Err(rusqlite::Error::SqliteFailure(error, _))
+            if matches!(error.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+    )
+}
+
+/// refuse to write to a world that's open somewhere else, since the server would overwrite or clash with the change
+pub fn check_writable(path: &Path) -> Result<(), Error> {
+    match is_live(path) {
+        true => Err(Error::Other(format!(
+            "{} is open in brickadia right now, close it before changing it",
+            path.display()
+        ))),
+        false => Ok(()),
+    }
+}
+
+/// copy the world into memory with sqlite's backup api, which gets a consistent copy even while
+/// a server is writing to it, without ever opening the real file for writing
+pub fn snapshot(path: &Path) -> Result<Brdb, Error> {
+    let bad_world = |error: rusqlite::Error| Error::BadWorld(path.to_path_buf(), error.to_string());
+    log::info!("copying {} into memory", path.display());
+
+    let live = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(bad_world)?;
+    live.busy_timeout(Duration::from_secs(5)).map_err(bad_world)?;
+    let mut conn = Connection::open_in_memory()?;
+    Backup::new(&live, &mut conn)?
+        .run_to_completion(1024, Duration::from_millis(10), None)
+        .map_err(bad_world)?;
+    Ok(Brdb { conn })
+}
+
+/// copy the world into a new file with sqlite's backup api, the same way as `snapshot`, so the copy is
+/// consistent even while a server is saving. the copy is made next to `dest` and only moved there once
+/// it's complete, so a backup that got cut off never looks like a finished one
+pub fn copy_to(path: &Path, dest: &Path) -> Result<(), Error> {
+    let bad_world = |error: rusqlite::Error| Error::BadWorld(path.to_path_buf(), error.to_string());
+
+    let partial = sidecar(dest, ".partial");
+    let live = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(bad_world)?;
+    live.busy_timeout(Duration::from_secs(5)).map_err(bad_world)?;
+    let mut conn = Connection::open(&partial)?;
+    let copied = Backup::new(&live, &mut conn)?.run_to_completion(1024, Duration::from_millis(10), None);
+    drop(conn);
+    if let Err(error) = copied {
+        let _ = std::fs::remove_file(&partial);
+        return Err(bad_world(error));
+    }
+    std::fs::rename(&partial, dest)?;
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use std::{io, io::Write, time::Instant, sync::OnceLock};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+use crate::cli::LogFormat;
+
+/// writes log records to stderr, one per line, so they never get mixed into the output
+struct Logger {
+    format: LogFormat,
+    start: Instant,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            Level::Warn => "warning",
+            level => level.as_str(),
+        };
+        let line = match self.format {
+            LogFormat::Text => format!("{}: {}", level.to_lowercase(), record.args()),
+            LogFormat::Json => json!({
+                "level": level.to_lowercase(),
+                "message": record.args().to_string(),
+                "module": record.target(),
+                "ms": self.start.elapsed().as_millis() as u64,
+            })
+            .to_string(),
+        };
+        let _ = writeln!(io::stderr().lock(), "{line}");
+    }
+
+    fn flush(&self) {}
+}
+
+/// start logging. warnings always show, and every -v shows more: the worlds that are opened, then
+/// the files read and decoded in them, then everything
+pub fn init(verbose: u8, format: LogFormat) {
+    let logger = LOGGER.get_or_init(|| Logger { format, start: Instant::now() });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        });
+    }
+}
@@ -0,0 +1,90 @@
+use brdb::{BrFsReader, Brdb, fs::BrFs, tables::{BrFile, BrFolder}};
+use indexmap::IndexMap;
+use rusqlite::{OptionalExtension, params};
+
+use crate::{error::Error, pattern};
+
+const FOLDER: &str = "SELECT folder_id, parent_id, name, created_at, deleted_at FROM folders";
+const FILE: &str = "SELECT file_id, parent_id, name, content_id, created_at, deleted_at FROM files";
+
+fn folder(row: &rusqlite::Row) -> rusqlite::Result<BrFolder> {
+    Ok(BrFolder { folder_id: row.get(0)?, parent_id: row.get(1)?, name: row.get(2)?, created_at: row.get(3)?, deleted_at: row.get(4)? })
+}
+
+fn file(row: &rusqlite::Row) -> rusqlite::Result<BrFile> {
+    Ok(BrFile {
+        file_id: row.get(0)?,
+        parent_id: row.get(1)?,
+        name: row.get(2)?,
+        content_id: row.get(3)?,
+        created_at: row.get(4)?,
+        deleted_at: row.get(5)?,
+    })
+}
+
+/// what's directly in a folder, or in the root when `parent` is none. with `deep` the folders in it are
+/// filled in all the way down, otherwise they come back empty
+fn children(db: &Brdb, parent: Option<i64>, deep: bool) -> Result<IndexMap<String, BrFs>, Error> {
+    let folders: Vec<BrFolder> = db
+        .conn
+        .prepare(&format!("{FOLDER} WHERE parent_id IS ?1 AND deleted_at IS NULL ORDER BY name"))?
+        .query_map(params![parent], folder)?
+        .collect::<Result<_, _>>()?;
+    let mut map = IndexMap::new();
+    for folder in folders {
+        let inside = match deep {
+            true => children(db, Some(folder.folder_id), true)?,
+            false => IndexMap::new(),
+        };
+        map.insert(folder.name.clone(), BrFs::Folder(folder, inside));
+    }
+    let files: Vec<BrFile> = db
+        .conn
+        .prepare(&format!("{FILE} WHERE parent_id IS ?1 AND deleted_at IS NULL ORDER BY name"))?
+        .query_map(params![parent], file)?
+        .collect::<Result<_, _>>()?;
+    map.extend(files.into_iter().map(|file| (file.name.clone(), BrFs::File(file))));
+    Ok(map)
+}
+
+/// the entry called `name` in a folder, following the path in `rest` if it's a folder
+fn along(db: &Brdb, parent: Option<i64>, name: &str, rest: &[&str], deep: bool) -> Result<IndexMap<String, BrFs>, Error> {
+    let mut map = IndexMap::new();
+    let found = db
+        .conn
+        .query_row(&format!("{FOLDER} WHERE parent_id IS ?1 AND name = ?2 AND deleted_at IS NULL"), params![parent, name], folder)
+        .optional()?;
+    if let Some(found) = found {
+        let inside = match rest {
+            [] => children(db, Some(found.folder_id), deep)?,
+            [next, rest @ ..] => along(db, Some(found.folder_id), next, rest, deep)?,
+        };
+        map.insert(name.to_string(), BrFs::Folder(found, inside));
+        return Ok(map);
+    }
+    let found = db
+        .conn
+        .query_row(&format!("{FILE} WHERE parent_id IS ?1 AND name = ?2 AND deleted_at IS NULL"), params![parent, name], file)
+        .optional()?;
+    // anything past a file is left for traverse to complain about
+    if let Some(found) = found {
+        map.insert(name.to_string(), BrFs::File(found));
+    }
+    Ok(map)
+}
+
+/// the part of the world's tree needed to get to one path: the folders along the way and whatever is
+/// in the path itself, with `deep` for everything below it too. this is a lot quicker than loading
+/// the whole tree on big worlds, and the rest of the tree just looks empty. paths with patterns, `.` or `..`
+/// could go anywhere, so those get the whole tree
+pub fn tree_to(db: &Brdb, path: &str, deep: bool) -> Result<BrFs, Error> {
+    let parts: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    if pattern::is_pattern(path) || parts.iter().any(|part| matches!(*part, "." | "..")) {
+        log::debug!("loading every folder to find /{path}");
+        return Ok(db.get_fs()?);
+    }
+    Ok(BrFs::Root(match parts.as_slice() {
+        [] => children(db, None, deep)?,
+        [name, rest @ ..] => along(db, None, name, rest, deep)?,
+    }))
+}
@@ -0,0 +1,737 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+use brdb::{
+    BrFsReader, BrReader, Brdb, Brick, ChunkIndex, IntoReader, LocalWirePortSource, Owner, OwnerTableSoA, Position,
+    RemoteWirePortSource, UnsavedGrid, WireChunkSoA, WirePortTarget,
+    pending::BrPendingFs,
+    schema::{BrdbSchema, BrdbSchemaGlobalData, BrdbValue},
+    schemas::{self, GLOBAL_DATA_SOA, OWNER_TABLE_SOA},
+};
+use indexmap::IndexMap;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    bricks::{grid_bricks, grid_ids},
+    diff::file_hashes,
+    error::Error,
+    mps,
+    output::Output,
+    rewrite::{Components, array, components_file, number, read_components},
+    strings_to_lines, transform,
+    write::{entry_patch, file_patch},
+};
+
+/// files that make up the bricks, entities and owners, which get merged by hand instead of file by file
+const WORLD_DATA: [&str; 4] = ["World/0/Bricks/", "World/0/Entities/", "World/0/Owners.", "World/0/GlobalData."];
+
+/// the schemas of the bricks and entities, which the merged world gets with the structs of both worlds in them
+const SCHEMAS: [&str; 6] = [
+    "World/0/Bricks/ChunksShared.schema",
+    "World/0/Bricks/ChunkIndexShared.schema",
+    "World/0/Bricks/ComponentsShared.schema",
+    "World/0/Bricks/WiresShared.schema",
+    "World/0/Entities/ChunksShared.schema",
+    "World/0/Entities/ChunkIndex.schema",
+];
+
+const ENTITY_INDEX: &str = "World/0/Entities/ChunkIndex.mps";
+
+/// the lists in an entity chunk file that have an entry for every entity
+const ENTITY_LISTS: [&str; 9] = [
+    "PersistentIndices",
+    "OwnerIndices",
+    "OriginalOwnerIndices",
+    "Locations",
+    "Rotations",
+    "LinearVelocities",
+    "AngularVelocities",
+    "ColorsAndAlphas",
+    "RemainingLifeSpans",
+];
+
+/// the bit flags in an entity chunk file, one bit for every entity. the weld parent flag says which entities
+/// have an entry in WeldParentIndices
+const ENTITY_FLAGS: [&str; 3] = ["WeldParentFlags", "PhysicsLockedFlags", "PhysicsSleepingFlags"];
+
+/// every brick on the main grid, chunk by chunk in the order they're saved
+fn main_grid_bricks<T: BrFsReader>(db: &BrReader<T>) -> Result<Vec<Brick>, Error> {
+    Ok(grid_bricks(db, 1, None)?.into_iter().map(|saved| saved.brick).collect())
+}
+
+/// the index of a player in the owner table, adding them if they arent in it yet. players are matched up
+/// by user id, so the same one isnt listed twice
+pub fn owner_index(owners: &mut OwnerTableSoA, owner: &Owner) -> usize {
+    match owners.user_ids.iter().position(|id| *id == owner.user_id) {
+        Some(index) => index,
+        None => {
+            owners.add(owner);
+            owners.user_ids.len() - 1
+        }
+    }
+}
+
+/// rebuild the main grid of a world with more bricks in it, and patch the new chunks, chunk index,
+/// global data and owner table into `pending`. returns how many bricks the world already had
+pub fn add_main_grid_bricks(
+    base: &BrReader<Brdb>,
+    pending: &mut BrPendingFs,
+    added: Vec<Brick>,
+    owners: &OwnerTableSoA,
+) -> Result<usize, Error> {
+    // the base bricks go first in every chunk, so the components and wires pointing at them stay valid
+    let mut global_data = (*base.global_data()?).clone();
+    let base_chunks = base.brick_chunk_index(1)?;
+    let mut bricks = main_grid_bricks(base)?;
+    let base_count = bricks.len();
+    bricks.extend(added);
+    for brick in &bricks {
+        global_data.add_brick_meta(brick);
+    }
+
+    let mut grid = UnsavedGrid::default();
+    for brick in &bricks {
+        grid.add_brick(&global_data, brick)?;
+    }
+    let index = &mut grid.chunk_index;
+    for (i, chunk) in index.chunk_3d_indices.iter().enumerate() {
+        match base_chunks.iter().find(|base| base.index == *chunk) {
+            Some(base) => {
+                index.chunk_offsets[i] = base.chunk_offset;
+                index.chunk_sizes[i] = base.chunk_size;
+                index.num_components[i] = base.num_components;
+                index.num_wires[i] = base.num_wires;
+            }
+            // the game saves main grid chunks without an offset
+            None => {
+                index.chunk_offsets[i] = brdb::IntVector { x: 0, y: 0, z: 0 };
+                index.num_components[i] = 0;
+                index.num_wires[i] = 0;
+            }
+        }
+    }
+
+    patch_main_grid(base, pending, grid, &global_data, owners)?;
+    Ok(base_count)
+}
+
+/// patch a rebuilt main grid's chunks and chunk index into `pending`, along with the global data and owner table
+/// that go with them. the grid's components and wires arent written, the ones already saved are kept
+pub fn patch_main_grid(
+    base: &BrReader<Brdb>,
+    pending: &mut BrPendingFs,
+    grid: UnsavedGrid,
+    global_data: &BrdbSchemaGlobalData,
+    owners: &OwnerTableSoA,
+) -> Result<(), Error> {
+    let BrPendingFs::Folder(Some(grid_files)) =
+        grid.to_pending(global_data.proc_brick_starting_index(), base.components_schema()?.as_ref())?
+    else {
+        return Err(Error::Other(String::from("couldnt build the merged grid")));
+    };
+    for (name, entry) in grid_files {
+        if name == "Chunks" || name == "ChunkIndex.mps" {
+            pending.patch(entry_patch(&format!("World/0/Bricks/Grids/1/{name}"), entry))?;
+        }
+    }
+    let global_data_schema = schemas::global_data_schema();
+    pending.patch(entry_patch(
+        "World/0/GlobalData.schema",
+        BrPendingFs::File(Some(global_data_schema.to_bytes()?)),
+    ))?;
+    pending.patch(entry_patch(
+        "World/0/GlobalData.mps",
+        BrPendingFs::File(Some(global_data_schema.write_brdb(GLOBAL_DATA_SOA, global_data)?)),
+    ))?;
+    pending.patch(entry_patch(
+        "World/0/Owners.mps",
+        BrPendingFs::File(Some(base.owners_schema()?.write_brdb(OWNER_TABLE_SOA, owners)?)),
+    ))?;
+    Ok(())
+}
+
+/// where the things in one of the two worlds end up in the merged one
+#[derive(Default)]
+struct Remap {
+    owners: Vec<usize>,
+    /// persistent ids of entities. a brick grid has the id of the entity it belongs to
+    ids: HashMap<u32, u32>,
+    /// the chunk and index each brick on the main grid ended up at, by the chunk and index it was saved at
+    main_grid: HashMap<ChunkIndex, Vec<(ChunkIndex, u32)>>,
+    component_types: Vec<u32>,
+    ports: Vec<u32>,
+    entity_types: Vec<u32>,
+    basic_assets: Vec<u32>,
+    procedural_assets: Vec<u32>,
+    materials: Vec<u32>,
+}
+
+impl Remap {
+    fn id(&self, id: u32) -> u32 {
+        self.ids.get(&id).copied().unwrap_or(id)
+    }
+
+    /// a persistent id in a decoded file, if it is one
+    fn id_value(&self, value: &Value) -> Value {
+        value.as_u64().map_or_else(|| value.clone(), |id| json!(self.id(id as u32)))
+    }
+
+    fn owner(&self, index: u32) -> u32 {
+        self.owners.get(index as usize).map_or(0, |index| *index as u32)
+    }
+
+    /// where a brick ends up. only the main grid is rebuilt, so bricks on other grids keep their chunk and index
+    fn brick(&self, path: &str, grid: u32, chunk: ChunkIndex, index: u32) -> Result<(u32, ChunkIndex, u32), Error> {
+        if grid != 1 {
+            return Ok((self.id(grid), chunk, index));
+        }
+        match self.main_grid.get(&chunk).and_then(|slots| slots.get(index as usize)) {
+            Some(&(chunk, index)) => Ok((1, chunk, index)),
+            None => Err(Error::Other(format!("/{path} points at brick {chunk}:{index}, which isnt there"))),
+        }
+    }
+}
+
+/// an index into one of the lists in a world's global data, in the merged global data
+fn mapped(map: &[u32], index: u32) -> u32 {
+    map.get(index as usize).copied().unwrap_or(index)
+}
+
+/// add everything in a world's global data to `merged`, and say where each entry ended up
+fn merge_global_data(merged: &mut BrdbSchemaGlobalData, from: &BrdbSchemaGlobalData) -> Remap {
+    let component_types = (from.component_type_names.iter().enumerate())
+        .map(|(i, name)| {
+            let (index, added) = merged.component_type_names.insert_full(name.clone());
+            if added {
+                let struct_name = from.component_data_struct_names.get(i).cloned();
+                merged.component_data_struct_names.push(struct_name.unwrap_or_else(|| String::from("None")));
+            }
+            index as u32
+        })
+        .collect();
+    let entity_types: Vec<u32> = (from.entity_type_names.iter().enumerate())
+        .map(|(i, name)| {
+            let class = from.entity_data_class_names.get_index(i).unwrap_or(name);
+            merged.add_entity_type_with_class(name, class);
+            merged.entity_type_names.get_index_of(name).unwrap_or(0) as u32
+        })
+        .collect();
+    let global_grid = usize::try_from(from.global_grid_entity_type_index).ok().and_then(|ty| entity_types.get(ty));
+    if merged.global_grid_entity_type_index < 0
+        && let Some(ty) = global_grid
+    {
+        merged.global_grid_entity_type_index = *ty as i32;
+    }
+    merged.external_asset_types.extend(from.external_asset_types.iter().cloned());
+    merged.external_asset_references.extend(from.external_asset_references.iter().cloned());
+
+    Remap {
+        component_types,
+        ports: (from.component_wire_port_names.iter())
+            .map(|name| merged.component_wire_port_names.insert_full(name.clone()).0 as u32)
+            .collect(),
+        entity_types,
+        basic_assets: (from.basic_brick_asset_names.iter())
+            .map(|name| merged.basic_brick_asset_names.insert_full(name.clone()).0 as u32)
+            .collect(),
+        procedural_assets: (from.procedural_brick_asset_names.iter())
+            .map(|name| merged.procedural_brick_asset_names.insert_full(name.clone()).0 as u32)
+            .collect(),
+        materials: (from.material_asset_names.iter())
+            .map(|name| merged.material_asset_names.insert_full(name.clone()).0 as u32)
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// base's schema at `path` with the structs only the other world's has added to it, or the other world's
+/// if base doesnt have one. structs both have keep base's version
+fn merged_schema(base: &BrReader<Brdb>, other: &BrReader<Brdb>, path: &str) -> Result<Option<BrdbSchema>, Error> {
+    let read = |db: &BrReader<Brdb>| -> Result<Option<BrdbSchema>, Error> {
+        match db.find_file_by_path(path)? {
+            Some(_) => Ok(Some(BrdbSchema::read(db.read_file(path)?.as_slice())?)),
+            None => Ok(None),
+        }
+    };
+    let (mut schema, theirs) = match (read(base)?, read(other)?) {
+        (Some(schema), Some(theirs)) => (schema, theirs),
+        (schema, theirs) => return Ok(schema.or(theirs)),
+    };
+    let missing: Vec<String> = theirs.struct_names().into_iter().filter(|name| schema.get_struct(name).is_none()).collect();
+    let (enums, variants, structs) = theirs.extract_structs_transitive(missing.iter().map(String::as_str));
+    let enums: Vec<_> = enums.into_iter().filter(|(name, _)| schema.get_enum(name).is_none()).collect();
+    let variants: Vec<_> = variants.into_iter().filter(|(name, _)| schema.get_variant(name).is_none()).collect();
+    let structs: Vec<_> = structs.into_iter().filter(|(name, _)| schema.get_struct(name).is_none()).collect();
+    schema.add_meta(enums, structs);
+    schema.add_variants(variants);
+    Ok(Some(schema))
+}
+
+/// one entity in an entity chunk file, with its entry in each of the lists
+struct SavedEntity {
+    ty: u32,
+    /// lined up with `ENTITY_LISTS`
+    lists: Vec<Value>,
+    /// lined up with `ENTITY_FLAGS`
+    flags: [bool; 3],
+    weld_parent: Option<Value>,
+    instance: Value,
+}
+
+/// every entity in a decoded entity chunk file
+fn read_entities(file: &Value) -> Vec<SavedEntity> {
+    let soa = &file["soa"];
+    let mut types = Vec::new();
+    for counter in array(soa, "TypeCounters") {
+        types.extend((0..number(&counter["NumEntities"])).map(|_| number(&counter["TypeIndex"])));
+    }
+    let lists = ENTITY_LISTS.map(|name| array(soa, name));
+    let flags = ENTITY_FLAGS.map(|name| array(&soa[name], "Flags"));
+    let mut weld_parents = array(soa, "WeldParentIndices").into_iter();
+    let mut instances = array(file, "instances").into_iter();
+
+    let mut entities = Vec::new();
+    for (i, ty) in types.into_iter().enumerate() {
+        // bit i is in byte i / 8, and bytes past the end of the list are all 0
+        let flags = flags.each_ref().map(|bytes| bytes.get(i / 8).is_some_and(|byte| number(byte) & (1 << (i & 7)) != 0));
+        entities.push(SavedEntity {
+            ty,
+            // only the remaining life spans can be missing, in worlds saved before they were added
+            lists: lists.iter().map(|list| list.get(i).cloned().unwrap_or_else(|| json!(0.0))).collect(),
+            flags,
+            weld_parent: if flags[0] { weld_parents.next() } else { None },
+            instance: instances.next().unwrap_or_default(),
+        });
+    }
+    entities
+}
+
+/// the json of an entity chunk file holding these entities, sorted by type the way the game saves them
+fn entities_file(mut entities: Vec<SavedEntity>, linear: bool) -> Value {
+    entities.sort_by_key(|entity| entity.ty);
+    let mut counters: Vec<Value> = Vec::new();
+    for entity in &entities {
+        match counters.last_mut() {
+            Some(last) if number(&last["TypeIndex"]) == entity.ty => last["NumEntities"] = json!(number(&last["NumEntities"]) + 1),
+            _ => counters.push(json!({ "TypeIndex": entity.ty, "NumEntities": 1 })),
+        }
+    }
+    let mut soa = Map::new();
+    soa.insert(String::from("TypeCounters"), json!(counters));
+    for (i, name) in ENTITY_LISTS.iter().enumerate() {
+        soa.insert(name.to_string(), entities.iter().map(|entity| entity.lists[i].clone()).collect());
+    }
+    for (i, name) in ENTITY_FLAGS.iter().enumerate() {
+        let mut bytes = vec![0u8; entities.len().div_ceil(8)];
+        for (bit, _) in entities.iter().enumerate().filter(|(_, entity)| entity.flags[i]) {
+            bytes[bit / 8] |= 1 << (bit & 7);
+        }
+        soa.insert(name.to_string(), json!({ "Flags": bytes }));
+    }
+    soa.insert(String::from("WeldParentIndices"), entities.iter().filter_map(|entity| entity.weld_parent.clone()).collect());
+    soa.insert(String::from("bColorsAreLinear"), json!(linear));
+    json!({ "soa": soa, "instances": entities.iter().map(|entity| &entity.instance).collect::<Vec<_>>() })
+}
+
+/// every entity chunk in a world, decoded. worlds without entities dont have an entity index at all
+fn entity_chunks(db: &BrReader<Brdb>) -> Result<Vec<(ChunkIndex, Value)>, Error> {
+    if db.find_file_by_path(ENTITY_INDEX)?.is_none() {
+        return Ok(Vec::new());
+    }
+    let chunks = db.entity_chunk_index()?.into_iter();
+    chunks.map(|chunk| Ok((chunk, mps::decode(db, &format!("World/0/Entities/Chunks/{chunk}.mps"))?))).collect()
+}
+
+/// a brick chunk file of one of the other world's entity grids, with its asset, material and owner indices
+/// pointing into the merged global data and owner table instead
+fn remap_chunk(mut chunk: Value, remap: &Remap, procedural_start: u32) -> Value {
+    let old_start = number(&chunk["ProceduralBrickStartingIndex"]);
+    chunk["ProceduralBrickStartingIndex"] = json!(procedural_start);
+    let types = array(&chunk, "BrickTypeIndices").into_iter().map(|ty| number(&ty)).map(|ty| match ty < old_start {
+        true => mapped(&remap.basic_assets, ty),
+        false => procedural_start + ty - old_start,
+    });
+    chunk["BrickTypeIndices"] = json!(types.collect::<Vec<_>>());
+    if let Some(counters) = chunk["BrickSizeCounters"].as_array_mut() {
+        for counter in counters {
+            counter["AssetIndex"] = json!(mapped(&remap.procedural_assets, number(&counter["AssetIndex"])));
+        }
+    }
+    let materials = array(&chunk, "MaterialIndices").into_iter().map(|material| mapped(&remap.materials, number(&material)));
+    chunk["MaterialIndices"] = json!(materials.collect::<Vec<_>>());
+    for list in ["OwnerIndices", "OriginalOwnerIndices"] {
+        chunk[list] = json!(array(&chunk, list).iter().map(|owner| remap.owner(number(owner))).collect::<Vec<_>>());
+    }
+    chunk
+}
+
+/// combine two worlds into a new one. the other world's main grid bricks are added to the base's main grid,
+/// and its entities and their brick grids come along under new persistent ids, with the components and wires
+/// on all of them. both are moved by `offset` first. the rest of the files are merged file by file
+pub fn merge(
+    base: &BrReader<Brdb>,
+    other: &BrReader<Brdb>,
+    out: &Path,
+    prefer_other: bool,
+    offset: Position,
+) -> Result<Output, Error> {
+    if out.exists() {
+        return Err(Error::Other(format!("{} already exists", out.display())));
+    }
+
+    // the other world is moved in a copy, which is checked for positions going out of range before anything is written
+    let moved = Brdb::new_memory()?.into_reader();
+    moved.write_pending("Copy the other world", other.to_pending()?)?;
+    if offset != Position::new(0, 0, 0) {
+        transform::translate(&moved, offset)?;
+    }
+    let other = &moved;
+
+    let mut global_data = (*base.global_data()?).clone();
+    let mut base_remap = merge_global_data(&mut global_data, &*base.global_data()?);
+    let mut other_remap = merge_global_data(&mut global_data, &*other.global_data()?);
+
+    // where each owner of the other world ends up in the merged owner table, along with everything they own
+    let mut owners = OwnerTableSoA::try_from(&base.owners_soa()?.to_value())?;
+    base_remap.owners = (0..owners.user_ids.len()).collect();
+    let other_owners = OwnerTableSoA::try_from(&other.owners_soa()?.to_value())?;
+    for i in 0..other_owners.user_ids.len() {
+        let owner = Owner {
+            user_id: other_owners.user_ids[i],
+            user_name: other_owners.user_names[i].clone(),
+            display_name: other_owners.display_names[i].clone(),
+        };
+        let index = owner_index(&mut owners, &owner);
+        other_remap.owners.push(index);
+        let counts = [
+            (&mut owners.brick_counts, &other_owners.brick_counts),
+            (&mut owners.component_counts, &other_owners.component_counts),
+            (&mut owners.wire_counts, &other_owners.wire_counts),
+            (&mut owners.entity_counts, &other_owners.entity_counts),
+        ];
+        for (merged, theirs) in counts {
+            if let (Some(count), Some(theirs)) = (merged.get_mut(index), theirs.get(i)) {
+                *count += theirs;
+            }
+        }
+    }
+
+    // the other world's entities, and the grids that belong to them, get ids after every one the base uses
+    let base_entities = entity_chunks(base)?;
+    let other_entities = entity_chunks(other)?;
+    let base_ids = (base_entities.iter()).flat_map(|(_, file)| array(&file["soa"], "PersistentIndices")).map(|id| number(&id));
+    let base_grids = grid_ids(base)?.into_iter().map(|id| id as u32);
+    let mut next_id = base_ids.chain(base_grids).map(|id| id + 1).max().unwrap_or(2);
+    if base.find_file_by_path(ENTITY_INDEX)?.is_some() {
+        next_id = next_id.max(number(&mps::decode(base, ENTITY_INDEX)?["NextPersistentIndex"]));
+    }
+    let other_ids = (other_entities.iter()).flat_map(|(_, file)| array(&file["soa"], "PersistentIndices")).map(|id| number(&id));
+    let other_grids = grid_ids(other)?.into_iter().filter(|id| *id != 1).map(|id| id as u32);
+    for id in other_ids.chain(other_grids) {
+        other_remap.ids.entry(id).or_insert_with(|| {
+            next_id += 1;
+            next_id - 1
+        });
+    }
+
+    // the base bricks go first in every chunk of the main grid, then the other world's
+    let base_chunks = base.brick_chunk_index(1)?;
+    let mut grid = UnsavedGrid::default();
+    let mut counts = [0; 2];
+    for ((world, remap), count) in [(base, &mut base_remap), (other, &mut other_remap)].into_iter().zip(&mut counts) {
+        if !grid_ids(world)?.contains(&1) {
+            continue;
+        }
+        for saved in grid_bricks(world, 1, None)? {
+            let mut brick = saved.brick;
+            brick.owner_index = brick.owner_index.map(|index| remap.owner(index as u32) as usize);
+            brick.original_owner_index = brick.original_owner_index.map(|index| remap.owner(index as u32) as usize);
+            global_data.add_brick_meta(&brick);
+            let (chunk, index) = grid.add_brick(&global_data, &brick)?;
+            remap.main_grid.entry(saved.chunk).or_default().push((chunk, index as u32));
+            *count += 1;
+        }
+    }
+    let [base_count, other_count] = counts;
+
+    let mut pending = base.to_pending()?;
+    let shared = Arc::new(global_data.clone());
+    let mut schemas = HashMap::new();
+    for path in SCHEMAS {
+        if let Some(schema) = merged_schema(base, other, path)? {
+            pending.patch(file_patch(path, schema.to_bytes()?))?;
+            schemas.insert(path, Arc::new(schema.with_global_data(shared.clone())));
+        }
+    }
+    let schema = |path: &str| schemas.get(path).ok_or_else(|| Error::NotFound(format!("/{path}")));
+    let encode = |schema_path: &str, path: &str, json: &Value| -> Result<BrPendingFs, Error> {
+        Ok(file_patch(path, mps::encode_with(schema(schema_path)?, &global_data, path, json)?))
+    };
+
+    // every components and wires file of both worlds is gone through, since they point at main grid bricks
+    // that can be somewhere else now. the other grids keep their chunks, so only ids change there
+    let mut components: HashMap<(u32, ChunkIndex), Components> = HashMap::new();
+    let mut wires: HashMap<(u32, ChunkIndex), WireChunkSoA> = HashMap::new();
+    let (mut other_components, mut other_wires) = (0, 0);
+    for (world, remap) in [(base, &base_remap), (other, &other_remap)] {
+        let from_other = std::ptr::eq(world, other);
+        for grid_id in grid_ids(world)? {
+            for saved in world.brick_chunk_index(grid_id)? {
+                let chunk = saved.index;
+                if saved.num_components > 0 {
+                    let path = format!("World/0/Bricks/Grids/{grid_id}/Components/{chunk}.mps");
+                    let read = read_components(world, &path)?;
+                    for (ty, brick, instance) in read.entries {
+                        let (grid, chunk, index) = remap.brick(&path, grid_id as u32, chunk, brick)?;
+                        let ty = mapped(&remap.component_types, ty);
+                        components.entry((grid, chunk)).or_default().entries.push((ty, index, instance));
+                        other_components += usize::from(from_other);
+                    }
+                    for (brick, [entity, offset, rotation]) in read.joints {
+                        let (grid, chunk, index) = remap.brick(&path, grid_id as u32, chunk, brick)?;
+                        let joint = [remap.id_value(&entity), offset, rotation];
+                        components.entry((grid, chunk)).or_default().joints.push((index, joint));
+                    }
+                    for (brick, microchip_grid) in read.microchips {
+                        let (grid, chunk, index) = remap.brick(&path, grid_id as u32, chunk, brick)?;
+                        components.entry((grid, chunk)).or_default().microchips.push((index, remap.id_value(&microchip_grid)));
+                    }
+                }
+
+                if saved.num_wires > 0 {
+                    let path = format!("World/0/Bricks/Grids/{grid_id}/Wires/{chunk}.mps");
+                    let soa = WireChunkSoA::try_from(&BrdbValue::Struct(Box::new(world.wire_chunk_soa(grid_id, chunk)?)))?;
+                    // local sources are remote ones that happen to be in the same chunk
+                    let sources = soa.local_wire_sources.into_iter().map(|source| RemoteWirePortSource {
+                        grid_persistent_index: grid_id as u32,
+                        chunk_index: chunk,
+                        brick_index_in_chunk: source.brick_index_in_chunk,
+                        component_type_index: source.component_type_index,
+                        port_index: source.port_index,
+                    });
+                    let sources = sources.chain(soa.remote_wire_sources);
+                    let targets = soa.local_wire_targets.into_iter().chain(soa.remote_wire_targets);
+                    for (source, target) in sources.zip(targets) {
+                        let from = remap.brick(&path, source.grid_persistent_index, source.chunk_index, source.brick_index_in_chunk)?;
+                        let to = remap.brick(&path, grid_id as u32, chunk, target.brick_index_in_chunk)?;
+                        let component_type = |ty: u16| mapped(&remap.component_types, ty as u32) as u16;
+                        let port = |port: u16| mapped(&remap.ports, port as u32) as u16;
+                        let target = WirePortTarget {
+                            brick_index_in_chunk: to.2,
+                            component_type_index: component_type(target.component_type_index),
+                            port_index: port(target.port_index),
+                        };
+                        let soa = wires.entry((to.0, to.1)).or_default();
+                        match (from.0, from.1) == (to.0, to.1) {
+                            true => soa.add_local_wire(
+                                LocalWirePortSource {
+                                    brick_index_in_chunk: from.2,
+                                    component_type_index: component_type(source.component_type_index),
+                                    port_index: port(source.port_index),
+                                },
+                                target,
+                            ),
+                            false => soa.add_remote_wire(
+                                RemoteWirePortSource {
+                                    grid_persistent_index: from.0,
+                                    chunk_index: from.1,
+                                    brick_index_in_chunk: from.2,
+                                    component_type_index: component_type(source.component_type_index),
+                                    port_index: port(source.port_index),
+                                },
+                                target,
+                            ),
+                        }
+                        other_wires += usize::from(from_other);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut num_components = HashMap::new();
+    for ((grid, chunk), found) in components {
+        let path = format!("World/0/Bricks/Grids/{grid}/Components/{chunk}.mps");
+        num_components.insert((grid, chunk), found.entries.len() as u32);
+        pending.patch(encode("World/0/Bricks/ComponentsShared.schema", &path, &components_file(found))?)?;
+    }
+    let mut num_wires = HashMap::new();
+    for ((grid, chunk), soa) in wires {
+        let path = format!("World/0/Bricks/Grids/{grid}/Wires/{chunk}.mps");
+        num_wires.insert((grid, chunk), (soa.local_wire_sources.len() + soa.remote_wire_sources.len()) as u32);
+        pending.patch(file_patch(&path, soa.to_bytes(schema("World/0/Bricks/WiresShared.schema")?)?))?;
+    }
+
+    // the other world's grids keep their chunks, so their files only need the indices in them pointed at the
+    // merged global data and owner table
+    let other_grids: Vec<usize> = grid_ids(other)?.into_iter().filter(|id| *id != 1).collect();
+    for &grid_id in &other_grids {
+        let to = format!("World/0/Bricks/Grids/{}", other_remap.id(grid_id as u32));
+        for saved in other.brick_chunk_index(grid_id)? {
+            let path = format!("{to}/Chunks/{}.mps", saved.index);
+            let chunk = mps::decode(other, &format!("World/0/Bricks/Grids/{grid_id}/Chunks/{}.mps", saved.index))?;
+            let chunk = remap_chunk(chunk, &other_remap, global_data.proc_brick_starting_index());
+            pending.patch(encode("World/0/Bricks/ChunksShared.schema", &path, &chunk)?)?;
+        }
+        let path = format!("{to}/ChunkIndex.mps");
+        let index = mps::decode(other, &format!("World/0/Bricks/Grids/{grid_id}/ChunkIndex.mps"))?;
+        pending.patch(encode("World/0/Bricks/ChunkIndexShared.schema", &path, &index)?)?;
+    }
+
+    let index = &mut grid.chunk_index;
+    for (i, chunk) in index.chunk_3d_indices.iter().enumerate() {
+        let base = base_chunks.iter().find(|base| base.index == *chunk);
+        // the game saves main grid chunks without an offset
+        index.chunk_offsets[i] = base.map_or(brdb::IntVector { x: 0, y: 0, z: 0 }, |base| base.chunk_offset);
+        if let Some(base) = base {
+            index.chunk_sizes[i] = base.chunk_size;
+        }
+        index.num_components[i] = num_components.get(&(1, *chunk)).copied().unwrap_or(0);
+        index.num_wires[i] = num_wires.get(&(1, *chunk)).copied().unwrap_or(0);
+    }
+    patch_main_grid(base, &mut pending, grid, &global_data, &owners)?;
+
+    // entities stay in the chunk file they were saved in, with the other world's after the base's
+    let mut entities: IndexMap<ChunkIndex, (bool, Vec<SavedEntity>)> = IndexMap::new();
+    let mut other_entity_count = 0;
+    for (chunks, remap) in [(base_entities, &base_remap), (other_entities, &other_remap)] {
+        for (chunk, file) in chunks {
+            let linear = file["soa"]["bColorsAreLinear"].as_bool().unwrap_or(false);
+            let (was_linear, merged) = entities.entry(chunk).or_insert_with(|| (linear, Vec::new()));
+            if *was_linear != linear {
+                return Err(Error::Other(format!(
+                    "the entities in chunk {chunk} of the two worlds dont save their colors the same way"
+                )));
+            }
+            for mut entity in read_entities(&file) {
+                entity.ty = mapped(&remap.entity_types, entity.ty);
+                entity.lists[0] = remap.id_value(&entity.lists[0]);
+                for owner in &mut entity.lists[1..3] {
+                    *owner = json!(remap.owner(number(owner)));
+                }
+                entity.weld_parent = entity.weld_parent.map(|parent| remap.id_value(&parent));
+                if std::ptr::eq(remap, &other_remap) {
+                    other_entity_count += 1;
+                }
+                merged.push(entity);
+            }
+        }
+    }
+    if !entities.is_empty() {
+        let chunks = entities.keys().map(|chunk| json!({ "X": chunk.x, "Y": chunk.y, "Z": chunk.z }));
+        let index = json!({
+            "NextPersistentIndex": next_id,
+            "Chunk3DIndices": chunks.collect::<Vec<_>>(),
+            "NumEntities": entities.values().map(|(_, merged)| merged.len()).collect::<Vec<_>>(),
+        });
+        pending.patch(encode("World/0/Entities/ChunkIndex.schema", ENTITY_INDEX, &index)?)?;
+        for (chunk, (linear, merged)) in entities {
+            let path = format!("World/0/Entities/Chunks/{chunk}.mps");
+            pending.patch(encode("World/0/Entities/ChunksShared.schema", &path, &entities_file(merged, linear))?)?;
+        }
+    }
+
+    // everything else is merged file by file
+    let base_files = file_hashes(base)?;
+    let other_files = file_hashes(other)?;
+    let mut added = Vec::new();
+    let mut conflicts = Vec::new();
+    for (path, hash) in &other_files {
+        if WORLD_DATA.iter().any(|prefix| path.starts_with(prefix)) {
+            continue;
+        }
+        match base_files.get(path) {
+            Some(base_hash) if base_hash == hash => (),
+            Some(_) => {
+                if prefer_other {
+                    pending.patch(entry_patch(path, BrPendingFs::File(Some(other.read_file(path)?))))?;
+                }
+                conflicts.push(format!("/{path}"));
+            }
+            None => {
+                pending.patch(entry_patch(path, BrPendingFs::File(Some(other.read_file(path)?))))?;
+                added.push(format!("/{path}"));
+            }
+        }
+    }
+
+    Brdb::new(out)?.write_pending("Merge worlds", pending)?;
+
+    let kept = if prefer_other { "other" } else { "base" };
+    let mut report = vec![
+        format!("merged {base_count} bricks from base and {other_count} from other"),
+        format!(
+            "carried over {other_components} components, {other_wires} wires, {other_entity_count} entities and {} brick grids from other",
+            other_grids.len()
+        ),
+    ];
+    report.extend(added.iter().map(|path| format!("A {path}")));
+    report.extend(conflicts.iter().map(|path| format!("C {path} (kept {kept})")));
+    report.push(format!("{} conflicts, written to {}", conflicts.len(), out.display()));
+
+    let json = json!({
+        "out": out,
+        "bricks": { "base": base_count, "other": other_count },
+        "components": other_components,
+        "wires": other_wires,
+        "entities": other_entity_count,
+        "grids": other_grids.len(),
+        "added": added,
+        "conflicts": conflicts,
+        "kept": kept,
+    });
+    Ok(Output::new(strings_to_lines(report.iter()), json))
+}
+
+#[cfg(test)]
+mod tests {
+    use brdb::{BrFsReader, IntoReader};
+
+    use super::*;
+    use crate::{files_in, owners::owner_table, testing::world};
+
+    fn merged(offset: Position) -> (tempfile::TempDir, Result<Output, Error>) {
+        let dir = tempfile::tempdir().unwrap();
+        let result = merge(&world(), &world(), &dir.path().join("merged.brdb"), false, offset);
+        (dir, result)
+    }
+
+    #[test]
+    fn merge_carries_over_components_wires_and_entities() {
+        let (dir, result) = merged(Position::new(0, 0, 40));
+        result.unwrap();
+        let db = Brdb::open(dir.path().join("merged.brdb")).unwrap().into_reader();
+
+        // both worlds bricks land in the same chunk, the other worlds after the base's
+        let chunks = db.brick_chunk_index(1).unwrap();
+        let counts: Vec<_> = chunks.iter().map(|chunk| (chunk.num_bricks, chunk.num_components, chunk.num_wires)).collect();
+        assert_eq!(counts, [(4, 4, 2)]);
+        let wires = db.wire_chunk_soa(1, chunks[0].index).unwrap();
+        let wires = WireChunkSoA::try_from(&BrdbValue::Struct(Box::new(wires))).unwrap();
+        let ends: Vec<_> = (wires.local_wire_sources.iter().zip(&wires.local_wire_targets))
+            .map(|(source, target)| (source.brick_index_in_chunk, target.brick_index_in_chunk))
+            .collect();
+        assert_eq!(ends, [(1, 0), (3, 2)]);
+
+        // the other worlds entity gets a new id, and its grid goes along with it
+        assert_eq!(grid_ids(&db).unwrap(), [1, 2, 3]);
+        assert_eq!(grid_bricks(&db, 3, None).unwrap().len(), 1);
+        let entities = db.entity_chunk(ChunkIndex { x: 0, y: 0, z: 0 }).unwrap();
+        assert_eq!(entities.iter().map(|entity| entity.location.z).collect::<Vec<_>>(), [40.0, 80.0]);
+        assert_eq!(number(&mps::decode(&db, ENTITY_INDEX).unwrap()["NextPersistentIndex"]), 4);
+
+        let owners = owner_table(&db).unwrap();
+        assert_eq!(owners.user_names, ["PUBLIC", "builder"]);
+        assert_eq!((owners.brick_counts, owners.entity_counts), (vec![4, 2], vec![2, 0]));
+        for (path, _) in files_in(&db.get_fs().unwrap(), "") {
+            if mps::known(&path) {
+                mps::decode(&db, &path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn merge_past_the_edge_is_an_error_and_writes_nothing() {
+        let (dir, result) = merged(Position::new(i32::MAX, 0, 0));
+        assert!(matches!(result, Err(Error::Other(_))));
+        assert!(!dir.path().join("merged.brdb").exists());
+    }
+}
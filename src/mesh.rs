@@ -0,0 +1,219 @@
+use brdb::{Brick, BrickType, Direction, Rotation, assets::brick_sizes::brick_half_extent, byte_to_orientation};
+
+/// a brick as triangles, in the y up, right handed space most 3d programs use
+pub struct BrickMesh {
+    pub vertices: Vec<[f32; 3]>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+/// the rough shape of a brick. only boxes and wedges are modeled, everything else becomes the box around it
+enum Shape {
+    Box,
+    /// slopes down from the -x side to the +x side
+    Wedge,
+    /// slopes from the -x side to the +x side when seen from above
+    SideWedge,
+}
+
+fn shape(asset: &str) -> Shape {
+    match asset {
+        asset if asset.contains("SideWedge") => Shape::SideWedge,
+        asset if asset.contains("Wedge") || asset.contains("Ramp") => Shape::Wedge,
+        _ => Shape::Box,
+    }
+}
+
+/// corners and faces of a shape that fills the box from -1 to 1 on every axis
+fn unit_shape(shape: Shape) -> (Vec<[f32; 3]>, Vec<Vec<usize>>) {
+    let corners = |points: &[[f32; 3]]| points.to_vec();
+    match shape {
+        Shape::Box => (
+            corners(&[
+                [-1.0, -1.0, -1.0],
+                [1.0, -1.0, -1.0],
+                [1.0, 1.0, -1.0],
+                [-1.0, 1.0, -1.0],
+                [-1.0, -1.0, 1.0],
+                [1.0, -1.0, 1.0],
+                [1.0, 1.0, 1.0],
+                [-1.0, 1.0, 1.0],
+            ]),
+            vec![vec![0, 1, 2, 3], vec![4, 5, 6, 7], vec![0, 1, 5, 4], vec![1, 2, 6, 5], vec![2, 3, 7, 6], vec![3, 0, 4, 7]],
+        ),
+        Shape::Wedge => (
+            corners(&[
+                [-1.0, -1.0, -1.0],
+                [1.0, -1.0, -1.0],
+                [1.0, 1.0, -1.0],
+                [-1.0, 1.0, -1.0],
+                [-1.0, -1.0, 1.0],
+                [-1.0, 1.0, 1.0],
+            ]),
+            vec![vec![0, 1, 2, 3], vec![0, 3, 5, 4], vec![1, 2, 5, 4], vec![0, 1, 4], vec![3, 2, 5]],
+        ),
+        Shape::SideWedge => (
+            corners(&[
+                [-1.0, -1.0, -1.0],
+                [1.0, -1.0, -1.0],
+                [-1.0, 1.0, -1.0],
+                [-1.0, -1.0, 1.0],
+                [1.0, -1.0, 1.0],
+                [-1.0, 1.0, 1.0],
+            ]),
+            vec![vec![0, 1, 2], vec![3, 4, 5], vec![0, 1, 4, 3], vec![1, 2, 5, 4], vec![2, 0, 3, 5]],
+        ),
+    }
+}
+
+/// turn a point on the brick from its own space into the world's: first around its up axis by its rotation,
+/// then so its up axis points the way its direction says
+fn orient(brick: &Brick, point: [f32; 3]) -> [f32; 3] {
+    orient_by(brick.direction, brick.rotation, point)
+}
+
+fn orient_by(direction: Direction, rotation: Rotation, [mut x, mut y, z]: [f32; 3]) -> [f32; 3] {
+    for _ in 0..rotation as u8 {
+        (x, y) = (-y, x);
+    }
+    match direction {
+        Direction::XPositive => [z, y, -x],
+        Direction::XNegative => [-z, y, x],
+        Direction::YPositive => [x, z, -y],
+        Direction::YNegative => [x, -z, y],
+        Direction::ZNegative => [x, -y, -z],
+        _ => [x, y, z],
+    }
+}
+
+/// the direction and rotation that leave a brick facing the way it would after being turned by `turn`, which
+/// swaps and flips axes. a mirrored brick cant be saved, so when `turn` mirrors, the brick is also flipped
+/// along one of its own axes that its shape looks the same from both sides of
+pub fn turned(brick: &Brick, turn: impl Fn([f32; 3]) -> [f32; 3]) -> (Direction, Rotation) {
+    let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    let mut wanted = axes.map(|axis| turn(orient(brick, axis)));
+    let [a, b, c] = wanted;
+    if a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0]) + a[2] * (b[0] * c[1] - b[1] * c[0]) < 0.0 {
+        let axis = match shape(&asset_name(brick)) {
+            Shape::SideWedge => 2,
+            _ => 1,
+        };
+        wanted[axis] = wanted[axis].map(|v| -v);
+    }
+    // there are only 24 ways a brick can face, so just try them all
+    (0..24)
+        .map(byte_to_orientation)
+        .find(|(direction, rotation)| axes.map(|axis| orient_by(*direction, *rotation, axis)) == wanted)
+        .unwrap_or((brick.direction, brick.rotation))
+}
+
+fn asset_name(brick: &Brick) -> String {
+    match &brick.asset {
+        BrickType::Basic(asset) | BrickType::Procedural { asset, .. } => asset.to_string(),
+    }
+}
+
+/// the half size of a brick along its own axes, in brick units
+pub fn half_extent(brick: &Brick) -> [f32; 3] {
+    let [x, y, z] = match &brick.asset {
+        BrickType::Procedural { size, .. } => [size.x as i32, size.y as i32, size.z as i32],
+        BrickType::Basic(asset) => brick_half_extent(asset.as_ref()).unwrap_or([5, 5, 6]),
+    };
+    [x as f32, y as f32, z as f32]
+}
+
+/// the lowest and highest corners of the box around a brick once it's turned, in brickadia's own space
+pub fn brick_bounds(brick: &Brick) -> ([f32; 3], [f32; 3]) {
+    let half = orient(brick, half_extent(brick)).map(f32::abs);
+    let position = [brick.position.x as f32, brick.position.y as f32, brick.position.z as f32];
+    ([0, 1, 2].map(|axis| position[axis] - half[axis]), [0, 1, 2].map(|axis| position[axis] + half[axis]))
+}
+
+/// the triangles of a brick, scaled from brick units by `scale`. brickadia is z up and left handed,
+/// so y and z swap places, which makes it y up and right handed
+pub fn brick_mesh(brick: &Brick, scale: f32) -> BrickMesh {
+    let asset = asset_name(brick);
+    let (corners, faces) = unit_shape(shape(&asset));
+    let [hx, hy, hz] = half_extent(brick);
+    // inverted ramps are the same shape upside down
+    let flip = if asset.contains("Inverted") { -1.0 } else { 1.0 };
+
+    let position = [brick.position.x as f32, brick.position.y as f32, brick.position.z as f32];
+    let vertices: Vec<[f32; 3]> = corners
+        .iter()
+        .map(|[x, y, z]| {
+            let [x, y, z] = orient(brick, [x * hx, y * hy, z * hz * flip]);
+            [(position[0] + x) * scale, (position[2] + z) * scale, (position[1] + y) * scale]
+        })
+        .collect();
+
+    // faces are wound so they face away from the middle of the brick, which works since every shape is convex
+    let center = vertices.iter().fold([0.0; 3], |sum, v| [sum[0] + v[0], sum[1] + v[1], sum[2] + v[2]]);
+    let center = center.map(|sum| sum / vertices.len() as f32);
+    let mut triangles = Vec::new();
+    for face in faces {
+        for i in 1..face.len() - 1 {
+            let triangle = [face[0], face[i], face[i + 1]];
+            let [a, b, c] = triangle.map(|index| vertices[index]);
+            let (ab, ac) = (sub(b, a), sub(c, a));
+            let normal = [ab[1] * ac[2] - ab[2] * ac[1], ab[2] * ac[0] - ab[0] * ac[2], ab[0] * ac[1] - ab[1] * ac[0]];
+            let outward = sub(a, center);
+            match normal[0] * outward[0] + normal[1] * outward[1] + normal[2] * outward[2] < 0.0 {
+                true => triangles.push([triangle[0], triangle[2], triangle[1]]),
+                false => triangles.push(triangle),
+            }
+        }
+    }
+    BrickMesh { vertices, triangles }
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facing(orientation: u8) -> Brick {
+        let (direction, rotation) = byte_to_orientation(orientation);
+        Brick { direction, rotation, ..Default::default() }
+    }
+
+    /// where a brick's own x, y and z axes point in the world
+    fn axes_of(brick: &Brick) -> [[f32; 3]; 3] {
+        [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]].map(|axis| orient(brick, axis))
+    }
+
+    #[test]
+    fn turned_points_a_brick_the_way_the_turn_points_its_axes() {
+        let turn = |[x, y, z]: [f32; 3]| [-y, x, z];
+        for orientation in 0..24 {
+            let brick = facing(orientation);
+            let (direction, rotation) = turned(&brick, turn);
+            assert_eq!(axes_of(&Brick { direction, rotation, ..Default::default() }), axes_of(&brick).map(turn));
+        }
+    }
+
+    #[test]
+    fn four_quarter_turns_leave_a_brick_facing_the_way_it_was() {
+        for orientation in 0..24 {
+            let mut brick = facing(orientation);
+            for _ in 0..4 {
+                (brick.direction, brick.rotation) = turned(&brick, |[x, y, z]| [-y, x, z]);
+            }
+            assert_eq!(axes_of(&brick), axes_of(&facing(orientation)));
+        }
+    }
+
+    #[test]
+    fn a_mirrored_brick_is_flipped_along_one_of_its_own_axes() {
+        let flip = |[x, y, z]: [f32; 3]| [-x, y, z];
+        for orientation in 0..24 {
+            let brick = facing(orientation);
+            let (direction, rotation) = turned(&brick, flip);
+            let [a, b, c] = axes_of(&brick).map(flip);
+            // mirroring cant be saved, so a box has its own y axis flipped back
+            assert_eq!(axes_of(&Brick { direction, rotation, ..Default::default() }), [a, b.map(|v| -v), c]);
+        }
+    }
+}
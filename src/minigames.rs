@@ -0,0 +1,72 @@
+use std::{fs, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use serde_json::{Value, json};
+
+use crate::{error::Error, format_size, output::{Output, table}, traverse, write::save_file};
+
+/// where the minigame of world `index` is saved
+fn minigame_path(index: usize) -> String {
+    format!("World/{index}/Minigame.bp")
+}
+
+/// the index of every world folder that has a minigame saved in it.
+/// brdb cant decode minigame files yet, so they're only ever handled as a whole
+fn minigame_indices(root: &BrFs) -> Vec<usize> {
+    let mut indices = Vec::new();
+    if let Ok(BrFs::Folder(_, map)) = traverse(root, "World") {
+        indices.extend(
+            map.keys()
+                .filter_map(|name| name.parse::<usize>().ok())
+                .filter(|index| traverse(root, &minigame_path(*index)).is_ok()),
+        );
+    }
+    indices.sort();
+    indices
+}
+
+/// list the minigames saved in the world
+pub fn list(db: &BrReader<Brdb>) -> Result<Output, Error> {
+    let root = db.get_fs()?;
+    let mut rows = vec![["INDEX", "PATH", "SIZE"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for index in minigame_indices(&root) {
+        let path = minigame_path(index);
+        let BrFs::File(file) = traverse(&root, &path)? else {
+            continue;
+        };
+        let size = file.read(&**db)?.len();
+        rows.push(vec![index.to_string(), format!("/{path}"), format_size(size as u64)]);
+        json.push(json!({ "index": index, "path": format!("/{path}"), "bytes": size }));
+    }
+    let text = match json.is_empty() {
+        true => String::from("no minigames saved in this world"),
+        false => table(&rows),
+    };
+    Ok(Output::new(text, Value::Array(json)))
+}
+
+/// save a minigame to a file on disk, so it can be imported into another world
+pub fn export(db: &BrReader<Brdb>, index: usize, output: &Path) -> Result<Output, Error> {
+    let root = db.get_fs()?;
+    let path = minigame_path(index);
+    let BrFs::File(file) = traverse(&root, &path)? else {
+        return Err(Error::Other(format!("/{path} is a folder")));
+    };
+    let data = file.read(&**db)?;
+    fs::write(output, &data)?;
+    let text = format!("exported minigame {index} ({} bytes) to {}", data.len(), output.display());
+    Ok(Output::new(text, json!({ "index": index, "output": output, "bytes": data.len() })))
+}
+
+/// put a minigame exported from another world into this one, replacing the one at `index` if there is one
+pub fn import(db: &BrReader<Brdb>, input: &Path, index: usize) -> Result<Output, Error> {
+    let data = fs::read(input)?;
+    if data.is_empty() {
+        return Err(Error::Other(format!("{} is empty", input.display())));
+    }
+    let path = minigame_path(index);
+    let size = data.len();
+    save_file(db, &path, data, &format!("Import minigame {index}"))?;
+    let text = format!("imported {} as minigame {index} ({size} bytes)", input.display());
+    Ok(Output::new(text, json!({ "index": index, "path": format!("/{path}"), "bytes": size })))
+}
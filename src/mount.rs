@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    os::unix::{ffi::OsStrExt, fs::MetadataExt},
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+    time::{Duration, UNIX_EPOCH},
+};
+use brdb::{BrFsReader, fs::BrFs};
+use fuser::{
+    AccessFlags, Config, Errno, FileAttr, FileHandle, FileType, Filesystem, FopenFlags, Generation, INodeNo, LockOwner, MountOption,
+    OpenFlags, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyStatfs, ReplyXattr, Request,
+};
+
+use crate::{World, blob_sizes, error::Error, mps, output::Output};
+
+/// how long the kernel can trust what it was told about a file, since the world underneath never changes
+const VALID: Duration = Duration::from_secs(3600);
+
+/// set when ctrl-c is pressed, so the filesystem can be unmounted before stopping
+static STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn stop(_: libc::c_int) {
+    STOP.store(true, Ordering::Relaxed);
+}
+
+enum Kind {
+    /// the inodes of whats inside
+    Folder(Vec<u64>),
+    /// a file as its stored in the world
+    File { path: String, blob_id: Option<i64> },
+    /// an .mps file decoded into json, next to the file itself
+    Decoded { path: String },
+}
+
+struct Node {
+    name: String,
+    parent: u64,
+    created_at: i64,
+    kind: Kind,
+}
+
+/// what changes while the world is mounted, behind a lock since fuse can ask from more than one thread
+struct State {
+    db: World,
+    /// sizes of files, worked out the first time theyre asked for
+    sizes: HashMap<u64, u64>,
+    /// decoded files, kept since their size is only known once theyre decoded
+    decoded: HashMap<u64, Arc<[u8]>>,
+    /// the contents of files that are open, with how many times theyre open
+    open: HashMap<u64, (usize, Arc<[u8]>)>,
+}
+
+/// the world laid out as inodes, with inode 1 as the root
+struct Tree {
+    nodes: Vec<Node>,
+    /// everything shows up as belonging to whoever owns the mountpoint
+    uid: u32,
+    gid: u32,
+    state: Mutex<State>,
+}
+
+impl Tree {
+    fn new(db: World, fs: &BrFs, decode: bool, uid: u32, gid: u32) -> Self {
+        let root = Node { name: String::new(), parent: 1, created_at: 0, kind: Kind::Folder(Vec::new()) };
+        let state = State { db, sizes: HashMap::new(), decoded: HashMap::new(), open: HashMap::new() };
+        let mut tree = Self { nodes: vec![root], uid, gid, state: Mutex::new(state) };
+        if let BrFs::Root(map) = fs {
+            for (name, child) in map {
+                tree.add(1, name, name, child, decode);
+            }
+        }
+        tree
+    }
+
+    /// add an entry and everything below it to the folder at `parent`
+    fn add(&mut self, parent: u64, name: &str, path: &str, fs: &BrFs, decode: bool) {
+        let ino = self.nodes.len() as u64 + 1;
+        let (created_at, kind) = match fs {
+            BrFs::File(file) => (file.created_at, Kind::File { path: path.to_string(), blob_id: file.content_id }),
+            BrFs::Folder(folder, _) => (folder.created_at, Kind::Folder(Vec::new())),
+            BrFs::Root(_) => return,
+        };
+        self.nodes.push(Node { name: name.to_string(), parent, created_at, kind });
+        if let Kind::Folder(children) = &mut self.nodes[parent as usize - 1].kind {
+            children.push(ino);
+        }
+        match fs {
+            BrFs::Folder(_, map) => {
+                for (name, child) in map {
+                    self.add(ino, name, &format!("{path}/{name}"), child, decode);
+                }
+            }
+            BrFs::File(_) if decode && path.ends_with(".mps") => {
+                let kind = Kind::Decoded { path: path.to_string() };
+                self.nodes.push(Node { name: format!("{name}.json"), parent, created_at, kind });
+                let decoded = self.nodes.len() as u64;
+                if let Kind::Folder(children) = &mut self.nodes[parent as usize - 1].kind {
+                    children.push(decoded);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn node(&self, ino: u64) -> Result<&Node, Errno> {
+        ino.checked_sub(1).and_then(|index| self.nodes.get(index as usize)).ok_or(Errno::ENOENT)
+    }
+
+    /// decode an .mps file the way extract -d does, which only happens once for each file
+    fn decode(state: &mut State, ino: u64, path: &str) -> Result<Arc<[u8]>, Errno> {
+        if let Some(data) = state.decoded.get(&ino) {
+            return Ok(data.clone());
+        }
+        let mut data = Vec::new();
+        if let Err(error) = mps::decode_to(&state.db, path, &mut data) {
+            log::warn!("couldnt decode /{path}: {error}");
+            return Err(Errno::EIO);
+        }
+        let data: Arc<[u8]> = data.into();
+        state.decoded.insert(ino, data.clone());
+        Ok(data)
+    }
+
+    fn size(&self, ino: u64) -> Result<u64, Errno> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(size) = state.sizes.get(&ino) {
+            return Ok(*size);
+        }
+        let size = match &self.node(ino)?.kind {
+            Kind::Folder(_) | Kind::File { blob_id: None, .. } => 0,
+            Kind::File { blob_id: Some(blob_id), .. } => blob_sizes(&state.db, *blob_id).map_err(|_| Errno::EIO)?.1,
+            Kind::Decoded { path } => Self::decode(&mut state, ino, path)?.len() as u64,
+        };
+        state.sizes.insert(ino, size);
+        Ok(size)
+    }
+
+    fn attr(&self, ino: u64) -> Result<FileAttr, Errno> {
+        let size = self.size(ino)?;
+        let node = self.node(ino)?;
+        let (kind, perm, nlink) = match node.kind {
+            Kind::Folder(_) => (FileType::Directory, 0o555, 2),
+            _ => (FileType::RegularFile, 0o444, 1),
+        };
+        let time = UNIX_EPOCH + Duration::from_secs(node.created_at.max(0) as u64);
+        Ok(FileAttr {
+            ino: INodeNo(ino),
+            size,
+            blocks: size.div_ceil(512),
+            atime: time,
+            mtime: time,
+            ctime: time,
+            crtime: time,
+            kind,
+            perm,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        })
+    }
+}
+
+impl Filesystem for Tree {
+    fn lookup(&self, _: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        let found = match self.node(parent.0) {
+            Ok(Node { kind: Kind::Folder(children), .. }) => {
+                children.iter().find(|child| self.nodes[**child as usize - 1].name.as_bytes() == name.as_bytes()).ok_or(Errno::ENOENT)
+            }
+            Ok(_) => Err(Errno::ENOTDIR),
+            Err(errno) => Err(errno),
+        };
+        match found.and_then(|ino| self.attr(*ino)) {
+            Ok(attr) => reply.entry(&VALID, &attr, Generation(0)),
+            Err(errno) => reply.error(errno),
+        }
+    }
+
+    fn getattr(&self, _: &Request, ino: INodeNo, _: Option<FileHandle>, reply: ReplyAttr) {
+        match self.attr(ino.0) {
+            Ok(attr) => reply.attr(&VALID, &attr),
+            Err(errno) => reply.error(errno),
+        }
+    }
+
+    fn readdir(&self, _: &Request, ino: INodeNo, _: FileHandle, offset: u64, mut reply: ReplyDirectory) {
+        let node = match self.node(ino.0) {
+            Ok(node) => node,
+            Err(errno) => return reply.error(errno),
+        };
+        let Kind::Folder(children) = &node.kind else {
+            return reply.error(Errno::ENOTDIR);
+        };
+        let mut entries = vec![(ino.0, ".", FileType::Directory), (node.parent, "..", FileType::Directory)];
+        entries.extend(children.iter().map(|child| {
+            let node = &self.nodes[*child as usize - 1];
+            let kind = match node.kind {
+                Kind::Folder(_) => FileType::Directory,
+                _ => FileType::RegularFile,
+            };
+            (*child, node.name.as_str(), kind)
+        }));
+        // the offset of an entry is where to carry on from after it
+        for (index, (ino, name, kind)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(INodeNo(ino), index as u64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn open(&self, _: &Request, ino: INodeNo, _: OpenFlags, reply: ReplyOpen) {
+        let opened = (|| {
+            let mut state = self.state.lock().unwrap();
+            if let Some((count, _)) = state.open.get_mut(&ino.0) {
+                *count += 1;
+                return Ok(());
+            }
+            let data = match &self.node(ino.0)?.kind {
+                Kind::Folder(_) => return Err(Errno::EISDIR),
+                Kind::File { path, .. } => Arc::from(state.db.read_file(path.as_str()).map_err(|_| Errno::EIO)?),
+                Kind::Decoded { path } => Self::decode(&mut state, ino.0, path)?,
+            };
+            state.open.insert(ino.0, (1, data));
+            Ok(())
+        })();
+        // no file handle, since the inode is enough to find the contents
+        match opened {
+            Ok(()) => reply.opened(FileHandle(0), FopenFlags::FOPEN_KEEP_CACHE),
+            Err(errno) => reply.error(errno),
+        }
+    }
+
+    fn read(&self, _: &Request, ino: INodeNo, _: FileHandle, offset: u64, size: u32, _: OpenFlags, _: Option<LockOwner>, reply: ReplyData) {
+        let state = self.state.lock().unwrap();
+        let Some((_, data)) = state.open.get(&ino.0) else {
+            return reply.error(Errno::EBADF);
+        };
+        let start = (offset as usize).min(data.len());
+        reply.data(&data[start..(start + size as usize).min(data.len())]);
+    }
+
+    fn release(&self, _: &Request, ino: INodeNo, _: FileHandle, _: OpenFlags, _: Option<LockOwner>, _: bool, reply: ReplyEmpty) {
+        let mut state = self.state.lock().unwrap();
+        if let Some((count, _)) = state.open.get_mut(&ino.0) {
+            *count -= 1;
+            if *count == 0 {
+                state.open.remove(&ino.0);
+            }
+        }
+        reply.ok();
+    }
+
+    // nothing is ever written, so theres nothing to flush, and the read-only mount already refuses writes
+    fn flush(&self, _: &Request, _: INodeNo, _: FileHandle, _: LockOwner, reply: ReplyEmpty) {
+        reply.ok();
+    }
+
+    fn access(&self, _: &Request, _: INodeNo, _: AccessFlags, reply: ReplyEmpty) {
+        reply.ok();
+    }
+
+    // files in a world dont have extended attributes
+    fn getxattr(&self, _: &Request, _: INodeNo, _: &OsStr, _: u32, reply: ReplyXattr) {
+        reply.error(Errno::NO_XATTR);
+    }
+
+    fn listxattr(&self, _: &Request, _: INodeNo, size: u32, reply: ReplyXattr) {
+        match size {
+            0 => reply.size(0),
+            _ => reply.data(&[]),
+        }
+    }
+
+    fn statfs(&self, _: &Request, _: INodeNo, reply: ReplyStatfs) {
+        // no space to speak of, 4k blocks and long enough names
+        reply.statfs(0, 0, 0, self.nodes.len() as u64, 0, 4096, 255, 4096);
+    }
+}
+
+/// show the files in a world as a read-only folder at `target`, so any program can read them, until
+/// ctrl-c is pressed or it's unmounted. with `decode` every .mps file gets a decoded .mps.json next to it
+pub fn mount_world(db: World, fs: &BrFs, target: &Path, decode: bool) -> Result<Output, Error> {
+    if !target.is_dir() {
+        return Err(Error::Other(format!("{} isnt a folder", target.display())));
+    }
+    let owner = target.metadata()?;
+    let tree = Tree::new(db, fs, decode, owner.uid(), owner.gid());
+
+    let mut config = Config::default();
+    config.mount_options = vec![
+        MountOption::RO,
+        MountOption::NoSuid,
+        MountOption::NoDev,
+        MountOption::FSName(String::from("brdb_cmd")),
+        MountOption::Subtype(String::from("brdb_cmd")),
+    ];
+    let session = fuser::spawn_mount(tree, target, &config)
+        .map_err(|error| Error::Other(format!("couldnt mount {}: {error}", target.display())))?;
+
+    // safe since the handler only stores to an atomic
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = stop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        libc::sigaction(libc::SIGINT, &action, std::ptr::null_mut());
+        libc::sigaction(libc::SIGTERM, &action, std::ptr::null_mut());
+    }
+    eprintln!("mounted the world at {}, press ctrl-c to unmount it", target.display());
+
+    // the session ends by itself when someone else unmounts it
+    while !STOP.load(Ordering::Relaxed) && !session.guard.is_finished() {
+        thread::sleep(Duration::from_millis(100));
+    }
+    let result = match session.guard.is_finished() {
+        true => session.join(),
+        false => session.umount_and_join(),
+    };
+    if let Err(error) = result {
+        log::warn!("couldnt unmount {} cleanly: {error}, run `umount {}` if it's still there", target.display(), target.display());
+    }
+    Ok(Output::new(format!("unmounted {}", target.display()), serde_json::json!({ "unmounted": target })))
+}
@@ -0,0 +1,1082 @@
+use std::{cell::RefCell, collections::HashMap, io, io::BufWriter, io::Read, io::Write, sync::Arc};
+use brdb::{
+    AsBrdbValue, BrFsReader, BrReader, BrdbSchemaError, FoundFile, Vector3f, schemas,
+    schema::{
+        BrdbInterned, BrdbSchema, BrdbSchemaEnum, BrdbSchemaGlobalData,
+        BrdbSchemaStructProperty, BrdbValue, ReadBrdbSchema, WireVariant,
+        as_brdb::{BrdbArrayIter, BrdbMapIter},
+    },
+};
+use serde::{Serialize, Serializer, ser, ser::SerializeMap, ser::SerializeSeq};
+use serde_json::{Map, Number, Value};
+
+use crate::{error::Error, format_time};
+
+/// records that are stored one after another behind the main struct of a chunk file
+#[derive(Clone, Copy)]
+enum Trailing {
+    None,
+    Components,
+    Entities,
+}
+
+/// everything needed to decode a .mps file: which schema describes it and which struct it holds
+struct Layout {
+    schema_path: String,
+    struct_name: &'static str,
+    trailing: Trailing,
+    with_global_data: bool,
+}
+
+/// figure out how a .mps file is laid out based on where it lives in the world
+fn layout(path: &str) -> Option<Layout> {
+    let parts: Vec<&str> = path.split('/').collect();
+    let world = match parts.as_slice() {
+        ["World", id, ..] => format!("World/{id}"),
+        _ => return None,
+    };
+
+    let (schema_path, struct_name, trailing) = match &parts[2..] {
+        ["GlobalData.mps"] => {
+            return Some(Layout {
+                schema_path: format!("{world}/GlobalData.schema"),
+                struct_name: schemas::GLOBAL_DATA_SOA,
+                trailing: Trailing::None,
+                // global data is what the other schemas need, so it can't depend on itself
+                with_global_data: false,
+            });
+        }
+        ["Owners.mps"] => ("Owners.schema", schemas::OWNER_TABLE_SOA, Trailing::None),
+        ["Bricks", "Grids", _, "ChunkIndex.mps"] => (
+            "Bricks/ChunkIndexShared.schema",
+            schemas::BRICK_CHUNK_INDEX_SOA,
+            Trailing::None,
+        ),
+        ["Bricks", "Grids", _, "Chunks", _] => {
+            ("Bricks/ChunksShared.schema", schemas::BRICK_CHUNK_SOA, Trailing::None)
+        }
+        ["Bricks", "Grids", _, "Components", _] => (
+            "Bricks/ComponentsShared.schema",
+            schemas::BRICK_COMPONENT_SOA,
+            Trailing::Components,
+        ),
+        ["Bricks", "Grids", _, "Wires", _] => {
+            ("Bricks/WiresShared.schema", schemas::BRICK_WIRE_SOA, Trailing::None)
+        }
+        ["Entities", "ChunkIndex.mps"] => (
+            "Entities/ChunkIndex.schema",
+            schemas::ENTITY_CHUNK_INDEX_SOA,
+            Trailing::None,
+        ),
+        ["Entities", "Chunks", _] => (
+            "Entities/ChunksShared.schema",
+            schemas::ENTITY_CHUNK_SOA,
+            Trailing::Entities,
+        ),
+        _ => return None,
+    };
+
+    Some(Layout {
+        schema_path: format!("{world}/{schema_path}"),
+        struct_name,
+        trailing,
+        with_global_data: true,
+    })
+}
+
+/// read a schema file, attaching the global data when the file needs it
+fn parse_schema(
+    data: &[u8],
+    global_data: Option<Arc<BrdbSchemaGlobalData>>,
+) -> Result<Arc<BrdbSchema>, Error> {
+    let mut data = data;
+    Ok(match global_data {
+        Some(global_data) => data.read_brdb_schema_with_data(global_data)?,
+        None => data.read_brdb_schema()?,
+    })
+}
+
+/// whether brdb_cmd knows how to decode the .mps file at a path
+pub fn known(path: &str) -> bool {
+    layout(path).is_some()
+}
+
+/// get the layout for a path, or complain that we don't know how to handle it
+fn layout_or_err(path: &str) -> Result<Layout, Error> {
+    layout(path).ok_or_else(|| Error::Decode(format!("dont know which schema describes {path}")))
+}
+
+/// the struct names of the records behind the main struct, based on its type counters
+fn trailing_structs(
+    trailing: Trailing,
+    soa: &BrdbValue,
+    global_data: &BrdbSchemaGlobalData,
+) -> Result<Vec<String>, Error> {
+    let (counters, count_prop) = match trailing {
+        Trailing::None => return Ok(Vec::new()),
+        Trailing::Components => ("ComponentTypeCounters", "NumInstances"),
+        Trailing::Entities => ("TypeCounters", "NumEntities"),
+    };
+
+    let mut names = Vec::new();
+    for counter in soa.prop(counters)?.as_array()? {
+        let type_index = counter.prop("TypeIndex")?.as_brdb_u32()? as usize;
+        let count = counter.prop(count_prop)?.as_brdb_u32()?;
+        let name = match trailing {
+            Trailing::Components => global_data.component_data_struct_names.get(type_index),
+            _ => global_data.entity_data_class_names.get_index(type_index),
+        };
+        // types without any data don't store a record at all
+        let Some(name) = name.filter(|name| name.as_str() != "None") else {
+            continue;
+        };
+        names.extend((0..count).map(|_| name.clone()));
+    }
+    Ok(names)
+}
+
+/// a .mps file's data with the schema it was written with and the global data it needs
+struct Loaded {
+    schema: Arc<BrdbSchema>,
+    global_data: Option<Arc<BrdbSchemaGlobalData>>,
+    data: Vec<u8>,
+}
+
+/// a .mps file in the world with the schema it was written with and the global data it needs, before it's read
+struct Found {
+    schema: Arc<BrdbSchema>,
+    global_data: Option<Arc<BrdbSchemaGlobalData>>,
+    file: FoundFile,
+}
+
+fn find<T: BrFsReader>(db: &BrReader<T>, path: &str, layout: &Layout) -> Result<Found, Error> {
+    let found = db
+        .find_file_by_path(path)?
+        .ok_or_else(|| brdb::BrFsError::NotFound(path.to_string()))?;
+
+    // the file has to be decoded with the schema that was live when it was written
+    let schema_file = db
+        .find_file_by_path_at_revision(&layout.schema_path, found.created_at)?
+        .ok_or_else(|| brdb::BrFsError::NotFound(layout.schema_path.clone()))?;
+    let global_data = match layout.with_global_data {
+        true => Some(db.global_data()?),
+        false => None,
+    };
+    log::debug!("decoding /{path} as {} with /{} as it was at {}", layout.struct_name, layout.schema_path, format_time(found.created_at));
+    let schema = parse_schema(&db.find_blob(schema_file.blob_id)?.read()?, global_data.clone())?;
+    Ok(Found { schema, global_data, file: found })
+}
+
+fn load<T: BrFsReader>(db: &BrReader<T>, path: &str, layout: &Layout) -> Result<Loaded, Error> {
+    let Found { schema, global_data, file } = find(db, path, layout)?;
+    Ok(Loaded { schema, global_data, data: db.find_blob(file.blob_id)?.read()? })
+}
+
+/// decode a .mps file inside the world into json
+pub fn decode<T: BrFsReader>(db: &BrReader<T>, path: &str) -> Result<Value, Error> {
+    let layout = layout_or_err(path)?;
+    let Loaded { schema, global_data, data } = load(db, path, &layout)?;
+    decode_data(&schema, &global_data.unwrap_or_default(), &layout, &data)
+}
+
+/// decode the contents of a .mps file into json with a schema
+fn decode_data(schema: &Arc<BrdbSchema>, global_data: &BrdbSchemaGlobalData, layout: &Layout, data: &[u8]) -> Result<Value, Error> {
+    let buf = &mut &data[..];
+    let soa = buf.read_brdb(schema, layout.struct_name)?;
+
+    let structs = trailing_structs(layout.trailing, &soa, global_data)?;
+    if structs.is_empty() {
+        return Ok(value_to_json(schema, global_data, &soa)?);
+    }
+
+    let mut instances = Vec::new();
+    for name in structs {
+        let value = buf.read_brdb(schema, &name)?;
+        let mut instance = Map::new();
+        instance.insert(String::from("struct"), Value::String(name));
+        instance.insert(String::from("value"), value_to_json(schema, global_data, &value)?);
+        instances.push(Value::Object(instance));
+    }
+
+    let mut root = Map::new();
+    root.insert(String::from("soa"), value_to_json(schema, global_data, &soa)?);
+    root.insert(String::from("instances"), Value::Array(instances));
+    Ok(Value::Object(root))
+}
+
+/// a decoded value that serializes into the same json value_to_json makes, without making it first
+struct Json<'a> {
+    schema: &'a BrdbSchema,
+    global_data: &'a BrdbSchemaGlobalData,
+    value: &'a BrdbValue,
+}
+
+impl Serialize for Json<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (schema, global_data) = (self.schema, self.global_data);
+        let json = |value| Json { schema, global_data, value };
+        match self.value {
+            BrdbValue::Struct(value) => {
+                let mut object = serializer.serialize_map(None)?;
+                match schema.get_struct_interned(value.name) {
+                    Some(fields) => {
+                        for field in fields.keys() {
+                            if let (Some(name), Some(field)) = (schema.intern.lookup(*field), value.properties.get(field)) {
+                                object.serialize_entry(&name, &json(field))?;
+                            }
+                        }
+                    }
+                    None => {
+                        for (field, v) in &value.properties {
+                            object.serialize_entry(&schema.intern.lookup(*field).unwrap_or_default(), &json(v))?;
+                        }
+                    }
+                }
+                object.end()
+            }
+            BrdbValue::Array(values) | BrdbValue::FlatArray(values) => serializer.collect_seq(values.iter().map(json)),
+            BrdbValue::Map(map) => {
+                let mut object = serializer.serialize_map(Some(map.len()))?;
+                for (k, v) in map {
+                    object.serialize_entry(&key_to_string(schema, global_data, k).map_err(ser::Error::custom)?, &json(v))?;
+                }
+                object.end()
+            },
+            // everything else is small enough to just convert
+            value => value_to_json(schema, global_data, value).map_err(ser::Error::custom)?.serialize(serializer),
+        }
+    }
+}
+
+/// the records behind the main struct of a file, each one read only when it's its turn to be written
+struct Instances<'a, R> {
+    schema: &'a Arc<BrdbSchema>,
+    global_data: &'a BrdbSchemaGlobalData,
+    names: Vec<String>,
+    data: RefCell<R>,
+    /// serde can only carry a message, so the real error waits here
+    error: RefCell<Option<BrdbSchemaError>>,
+}
+
+impl<R: Read> Serialize for Instances<'_, R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut instances = serializer.serialize_seq(Some(self.names.len()))?;
+        for name in &self.names {
+            let value = self.data.borrow_mut().read_brdb(self.schema, name).map_err(|error| {
+                let message = error.to_string();
+                *self.error.borrow_mut() = Some(error);
+                ser::Error::custom(message)
+            })?;
+            instances.serialize_element(&Instance { name, value: Json { schema: self.schema, global_data: self.global_data, value: &value } })?;
+        }
+        instances.end()
+    }
+}
+
+/// one record, named after the struct it is
+struct Instance<'a> {
+    name: &'a str,
+    value: Json<'a>,
+}
+
+impl Serialize for Instance<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut instance = serializer.serialize_map(Some(2))?;
+        instance.serialize_entry("struct", self.name)?;
+        instance.serialize_entry("value", &self.value)?;
+        instance.end()
+    }
+}
+
+/// a file with records behind its main struct
+struct WithInstances<'a, R> {
+    soa: Json<'a>,
+    instances: Instances<'a, R>,
+}
+
+impl<R: Read> Serialize for WithInstances<'_, R> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut root = serializer.serialize_map(Some(2))?;
+        root.serialize_entry("soa", &self.soa)?;
+        root.serialize_entry("instances", &self.instances)?;
+        root.end()
+    }
+}
+
+/// decode a .mps file inside the world and write it out as pretty json as it goes, without ever holding the whole
+/// file decompressed, as json or as text. the records behind the main struct are read one at a time, which is
+/// where most of a big components or entities chunk is. the json is the same as decode gives
+pub fn decode_to<T: BrFsReader>(db: &BrReader<T>, path: &str, out: impl Write) -> Result<(), Error> {
+    let layout = layout_or_err(path)?;
+    let Found { schema, global_data, file } = find(db, path, &layout)?;
+    let blob = db.find_blob(file.blob_id)?;
+    let mut data: Box<dyn Read> = match blob.compression {
+        0 => Box::new(blob.content.as_slice()),
+        _ => Box::new(zstd::Decoder::new(blob.content.as_slice())?),
+    };
+    let soa = data.read_brdb(&schema, layout.struct_name)?;
+    let global_data = global_data.unwrap_or_default();
+    let names = trailing_structs(layout.trailing, &soa, &global_data)?;
+
+    let mut out = BufWriter::new(out);
+    let soa = Json { schema: &schema, global_data: &global_data, value: &soa };
+    let written = match names.is_empty() {
+        true => serde_json::to_writer_pretty(&mut out, &soa),
+        false => {
+            let instances = Instances { schema: &schema, global_data: &global_data, names, data: RefCell::new(data), error: RefCell::new(None) };
+            let file = WithInstances { soa, instances };
+            let written = serde_json::to_writer_pretty(&mut out, &file);
+            if let Some(error) = file.instances.error.take() {
+                return Err(error.into());
+            }
+            written
+        }
+    };
+    written.map_err(io::Error::from)?;
+    Ok(out.flush()?)
+}
+
+/// where and why a .mps file doesnt decode
+pub struct Invalid {
+    /// the struct and fields that were being read, like `BRSavedOwnerTableSoA.UserNames[2]`
+    pub at: String,
+    /// how far into the file decoding got, when it got to reading the file at all
+    pub offset: Option<usize>,
+    pub problem: String,
+}
+
+impl Invalid {
+    fn new(error: Error) -> Self {
+        Self { at: String::new(), offset: None, problem: error.to_string() }
+    }
+
+    /// brdb wraps an error in the name of every struct, field and array index it was inside of,
+    /// which are pulled back out here into one path
+    fn reading(prefix: String, error: BrdbSchemaError, offset: usize) -> Self {
+        let (mut at, mut error) = (prefix, error);
+        while let BrdbSchemaError::Wrapped(label, inner) = error {
+            match label.parse::<usize>() {
+                Ok(index) => at.push_str(&format!("[{index}]")),
+                Err(_) if at.is_empty() => at.push_str(&label),
+                Err(_) => at.push_str(&format!(".{label}")),
+            }
+            error = *inner;
+        }
+        Self { at, offset: Some(offset), problem: error.to_string() }
+    }
+}
+
+/// decode every record in a .mps file without keeping any of it, and make sure nothing is left over
+/// after the last one. returns how many records the file holds
+pub fn validate<T: BrFsReader>(db: &BrReader<T>, path: &str) -> Result<usize, Invalid> {
+    let layout = layout(path).ok_or_else(|| Invalid {
+        at: String::new(),
+        offset: None,
+        problem: String::from("dont know which schema describes it"),
+    })?;
+    let Loaded { schema, global_data, data } = load(db, path, &layout).map_err(Invalid::new)?;
+    let buf = &mut data.as_slice();
+    let offset = |buf: &[u8]| data.len() - buf.len();
+    let soa = buf
+        .read_brdb(&schema, layout.struct_name)
+        .map_err(|error| Invalid::reading(String::new(), error, offset(buf)))?;
+
+    let global_data = global_data.unwrap_or_default();
+    let structs = trailing_structs(layout.trailing, &soa, &global_data).map_err(|error| Invalid {
+        at: String::from(layout.struct_name),
+        offset: Some(offset(buf)),
+        problem: error.to_string(),
+    })?;
+    for (index, name) in structs.iter().enumerate() {
+        buf.read_brdb(&schema, name)
+            .map_err(|error| Invalid::reading(format!("instances[{index}]"), error, offset(buf)))?;
+    }
+
+    match buf.is_empty() {
+        true => Ok(1 + structs.len()),
+        false => Err(Invalid {
+            at: String::from("end of file"),
+            offset: Some(offset(buf)),
+            problem: format!("{} bytes are left over after the last record", buf.len()),
+        }),
+    }
+}
+
+/// encode json back into a .mps file with the schema the file was decoded with, or the latest schema for a
+/// file that isnt there yet. once saved the file is read with the latest schema, so when thats a newer one
+/// both have to read the new data the same way
+pub fn encode<T: BrFsReader>(db: &BrReader<T>, path: &str, json: &Value) -> Result<Vec<u8>, Error> {
+    let layout = layout_or_err(path)?;
+    let global_data = match layout.with_global_data {
+        true => Some(db.global_data()?),
+        false => None,
+    };
+    let latest = db.read_file(&layout.schema_path)?;
+    let decoded_with = match db.find_file_by_path(path)? {
+        Some(found) => db
+            .find_file_by_path_at_revision(&layout.schema_path, found.created_at)?
+            .map(|schema_file| db.find_blob(schema_file.blob_id)?.read())
+            .transpose()?,
+        None => None,
+    };
+    let schema = parse_schema(decoded_with.as_deref().unwrap_or(&latest), global_data.clone())?;
+    let data = encode_data(&schema, &global_data.clone().unwrap_or_default(), &layout, json)?;
+
+    if decoded_with.is_some_and(|decoded_with| decoded_with != latest) {
+        let now = parse_schema(&latest, global_data.clone())?;
+        let global_data = global_data.unwrap_or_default();
+        let read_now = decode_data(&now, &global_data, &layout, &data).ok();
+        if read_now.as_ref() != Some(&decode_data(&schema, &global_data, &layout, &data)?) {
+            return Err(Error::Decode(format!(
+                "/{path} was saved with an older /{} than the world has now, which reads it differently",
+                layout.schema_path
+            )));
+        }
+    }
+    Ok(data)
+}
+
+/// encode json into a .mps file for a path with a schema and global data that dont have to be the world's,
+/// like when a new world is being put together
+pub fn encode_with(schema: &Arc<BrdbSchema>, global_data: &BrdbSchemaGlobalData, path: &str, json: &Value) -> Result<Vec<u8>, Error> {
+    encode_data(schema, global_data, &layout_or_err(path)?, json)
+}
+
+fn encode_data(schema: &Arc<BrdbSchema>, global_data: &BrdbSchemaGlobalData, layout: &Layout, json: &Value) -> Result<Vec<u8>, Error> {
+    let (soa, instances) = match layout.trailing {
+        Trailing::None => (json, None),
+        _ => (
+            json.get("soa").ok_or_else(|| Error::Decode(String::from("missing \"soa\"")))?,
+            json.get("instances").and_then(Value::as_array),
+        ),
+    };
+
+    let soa = from_json(schema, global_data, layout.struct_name, soa).map_err(Error::Decode)?;
+    let mut buf = schema.write_brdb(layout.struct_name, &soa)?;
+
+    // the records have to line up with the type counters in the main struct
+    let expected = trailing_structs(layout.trailing, &soa_value(schema, layout, &buf)?, global_data)?;
+    let instances = instances.map(Vec::as_slice).unwrap_or_default();
+    if expected.len() != instances.len() {
+        return Err(Error::Decode(format!(
+            "the type counters expect {} instances but there are {}",
+            expected.len(),
+            instances.len()
+        )));
+    }
+
+    for (index, (name, instance)) in expected.iter().zip(instances).enumerate() {
+        let value = instance.get("value").unwrap_or(instance);
+        let value = from_json(schema, global_data, name, value)
+            .map_err(|error| Error::Decode(format!("instance {index} ({name}): {error}")))?;
+        buf.extend(schema.write_brdb(name, &value)?);
+    }
+
+    Ok(buf)
+}
+
+/// read the main struct back out of freshly encoded data, when there are records behind it to line up
+fn soa_value(schema: &Arc<BrdbSchema>, layout: &Layout, data: &[u8]) -> Result<BrdbValue, Error> {
+    let mut data = data;
+    match layout.trailing {
+        Trailing::None => Ok(BrdbValue::Nil),
+        _ => Ok(data.read_brdb(schema, layout.struct_name)?),
+    }
+}
+
+/// floats are printed the way rust would print them so 0.1f32 doesn't turn into 0.10000000149011612
+fn float_to_json(value: impl ToString) -> Value {
+    value
+        .to_string()
+        .parse::<f64>()
+        .ok()
+        .and_then(Number::from_f64)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+/// name of an asset like `BrickType/PB_DefaultBrick`
+fn asset_name(global_data: &BrdbSchemaGlobalData, index: usize) -> Value {
+    match global_data.external_asset_references.get_index(index) {
+        Some((asset_type, name)) => Value::String(format!("{asset_type}/{name}")),
+        None => Value::from(index),
+    }
+}
+
+fn wire_to_json(global_data: &BrdbSchemaGlobalData, wire: &WireVariant) -> Value {
+    let object = |fields: &[(&str, Value)]| {
+        Value::Object(fields.iter().map(|(k, v)| (k.to_string(), v.clone())).collect())
+    };
+    match wire {
+        WireVariant::Number(value) => float_to_json(value),
+        WireVariant::Int(value) => Value::from(*value),
+        WireVariant::Bool(value) => Value::Bool(*value),
+        WireVariant::Str(value) => Value::String(value.clone()),
+        WireVariant::Object(index) => object(&[(
+            "Object",
+            index.map(|index| asset_name(global_data, index)).unwrap_or(Value::Null),
+        )]),
+        WireVariant::Exec => object(&[("Exec", Value::Null)]),
+        WireVariant::Vector(v) => {
+            object(&[("X", float_to_json(v.x)), ("Y", float_to_json(v.y)), ("Z", float_to_json(v.z))])
+        }
+        WireVariant::Rotator { pitch, yaw, roll } => object(&[
+            ("Pitch", float_to_json(pitch)),
+            ("Yaw", float_to_json(yaw)),
+            ("Roll", float_to_json(roll)),
+        ]),
+        WireVariant::Quat { x, y, z, w } => object(&[
+            ("X", float_to_json(x)),
+            ("Y", float_to_json(y)),
+            ("Z", float_to_json(z)),
+            ("W", float_to_json(w)),
+        ]),
+        WireVariant::LinearColor { r, g, b, a } => object(&[
+            ("R", float_to_json(r)),
+            ("G", float_to_json(g)),
+            ("B", float_to_json(b)),
+            ("A", float_to_json(a)),
+        ]),
+    }
+}
+
+/// name of an enum value. a value the schema doesnt list is an error, since encoding it back would
+/// need a name that doesnt exist
+fn enum_name(schema: &BrdbSchema, name: BrdbInterned, value: u64) -> Result<Value, BrdbSchemaError> {
+    let enum_name = || schema.intern.lookup(name).unwrap_or_default();
+    let values = schema
+        .get_enum_interned(name)
+        .ok_or_else(|| BrdbSchemaError::UnknownType(enum_name()))?;
+    values
+        .iter()
+        .find(|(_, v)| **v as u64 == value)
+        .and_then(|(key, _)| schema.intern.lookup(*key))
+        .map(Value::String)
+        .ok_or_else(|| BrdbSchemaError::EnumIndexOutOfBounds { enum_name: enum_name(), index: value })
+}
+
+/// map keys have to be strings in json
+fn key_to_string(schema: &BrdbSchema, global_data: &BrdbSchemaGlobalData, key: &BrdbValue) -> Result<String, BrdbSchemaError> {
+    Ok(match value_to_json(schema, global_data, key)? {
+        Value::String(value) => value,
+        value => value.to_string(),
+    })
+}
+
+/// convert a decoded value into json, keeping struct fields in schema order
+pub fn value_to_json(schema: &BrdbSchema, global_data: &BrdbSchemaGlobalData, value: &BrdbValue) -> Result<Value, BrdbSchemaError> {
+    Ok(match value {
+        BrdbValue::Nil => Value::Null,
+        BrdbValue::Bool(value) => Value::Bool(*value),
+        BrdbValue::U8(value) => Value::from(*value),
+        BrdbValue::U16(value) => Value::from(*value),
+        BrdbValue::U32(value) => Value::from(*value),
+        BrdbValue::U64(value) => Value::from(*value),
+        BrdbValue::I8(value) => Value::from(*value),
+        BrdbValue::I16(value) => Value::from(*value),
+        BrdbValue::I32(value) => Value::from(*value),
+        BrdbValue::I64(value) => Value::from(*value),
+        BrdbValue::F32(value) => float_to_json(value),
+        BrdbValue::F64(value) => float_to_json(value),
+        BrdbValue::String(value) => Value::String(value.clone()),
+        BrdbValue::Asset(None) => Value::Null,
+        BrdbValue::Asset(Some(index)) => asset_name(global_data, *index),
+        BrdbValue::Enum(value) => enum_name(schema, value.name, value.get_value_raw())?,
+        BrdbValue::Struct(value) => {
+            let mut object = Map::new();
+            match schema.get_struct_interned(value.name) {
+                Some(fields) => {
+                    for field in fields.keys() {
+                        if let (Some(name), Some(field)) =
+                            (schema.intern.lookup(*field), value.properties.get(field))
+                        {
+                            object.insert(name, value_to_json(schema, global_data, field)?);
+                        }
+                    }
+                }
+                None => {
+                    for (field, v) in &value.properties {
+                        let name = schema.intern.lookup(*field).unwrap_or_default();
+                        object.insert(name, value_to_json(schema, global_data, v)?);
+                    }
+                }
+            }
+            Value::Object(object)
+        }
+        BrdbValue::Array(values) | BrdbValue::FlatArray(values) => Value::Array(
+            values.iter().map(|value| value_to_json(schema, global_data, value)).collect::<Result<_, _>>()?,
+        ),
+        BrdbValue::Map(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((key_to_string(schema, global_data, k)?, value_to_json(schema, global_data, v)?)))
+                .collect::<Result<_, BrdbSchemaError>>()?,
+        ),
+        BrdbValue::WireVar(wire) => wire_to_json(global_data, wire),
+    })
+}
+
+/// a value built from json that the brdb schema writer knows how to encode
+enum MpsValue {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    Str(String),
+    Asset(Option<usize>),
+    Enum(i32),
+    Wire(WireVariant),
+    Array(Vec<MpsValue>),
+    Map(Vec<(MpsValue, MpsValue)>),
+    Struct {
+        // set when this struct is one member of a variant type
+        member: Option<String>,
+        fields: HashMap<String, MpsValue>,
+    },
+}
+
+fn cast_err(expected: &str, value: &MpsValue) -> BrdbSchemaError {
+    let found = match value {
+        MpsValue::Nil => "null",
+        MpsValue::Bool(_) => "bool",
+        MpsValue::Int(_) | MpsValue::UInt(_) => "integer",
+        MpsValue::Float(_) => "float",
+        MpsValue::Str(_) => "string",
+        MpsValue::Asset(_) => "asset",
+        MpsValue::Enum(_) => "enum",
+        MpsValue::Wire(_) => "wire variant",
+        MpsValue::Array(_) => "array",
+        MpsValue::Map(_) => "map",
+        MpsValue::Struct { .. } => "struct",
+    };
+    BrdbSchemaError::ExpectedType(expected.to_string(), found.to_string())
+}
+
+impl MpsValue {
+    fn int<T: TryFrom<i64> + TryFrom<u64>>(&self, expected: &str) -> Result<T, BrdbSchemaError> {
+        let value = match self {
+            MpsValue::Int(value) => T::try_from(*value).ok(),
+            MpsValue::UInt(value) => T::try_from(*value).ok(),
+            MpsValue::Bool(value) => T::try_from(*value as u64).ok(),
+            _ => return Err(cast_err(expected, self)),
+        };
+        value.ok_or_else(|| BrdbSchemaError::ExpectedType(expected.to_string(), String::from("out of range integer")))
+    }
+
+    fn float(&self) -> Result<f64, BrdbSchemaError> {
+        match self {
+            MpsValue::Float(value) => Ok(*value),
+            MpsValue::Int(value) => Ok(*value as f64),
+            MpsValue::UInt(value) => Ok(*value as f64),
+            // json can't hold NaN, so it comes back as null
+            MpsValue::Nil => Ok(f64::NAN),
+            _ => Err(cast_err("float", self)),
+        }
+    }
+
+    fn field(&self, schema: &BrdbSchema, prop_name: BrdbInterned) -> Option<&MpsValue> {
+        let MpsValue::Struct { fields, .. } = self else {
+            return None;
+        };
+        fields.get(schema.intern.lookup_ref(prop_name)?)
+    }
+
+    fn field_or_err(&self, schema: &BrdbSchema, struct_name: BrdbInterned, prop_name: BrdbInterned) -> Result<&MpsValue, BrdbSchemaError> {
+        self.field(schema, prop_name).ok_or_else(|| {
+            BrdbSchemaError::MissingStructField(
+                schema.intern.lookup(struct_name).unwrap_or_default(),
+                schema.intern.lookup(prop_name).unwrap_or_default(),
+            )
+        })
+    }
+}
+
+impl AsBrdbValue for MpsValue {
+    fn as_brdb_bool(&self) -> Result<bool, BrdbSchemaError> {
+        match self {
+            MpsValue::Bool(value) => Ok(*value),
+            _ => Err(cast_err("bool", self)),
+        }
+    }
+    fn as_brdb_u8(&self) -> Result<u8, BrdbSchemaError> {
+        self.int("u8")
+    }
+    fn as_brdb_u16(&self) -> Result<u16, BrdbSchemaError> {
+        self.int("u16")
+    }
+    fn as_brdb_u32(&self) -> Result<u32, BrdbSchemaError> {
+        self.int("u32")
+    }
+    fn as_brdb_u64(&self) -> Result<u64, BrdbSchemaError> {
+        self.int("u64")
+    }
+    fn as_brdb_i8(&self) -> Result<i8, BrdbSchemaError> {
+        self.int("i8")
+    }
+    fn as_brdb_i16(&self) -> Result<i16, BrdbSchemaError> {
+        self.int("i16")
+    }
+    fn as_brdb_i32(&self) -> Result<i32, BrdbSchemaError> {
+        self.int("i32")
+    }
+    fn as_brdb_i64(&self) -> Result<i64, BrdbSchemaError> {
+        self.int("i64")
+    }
+    fn as_brdb_f32(&self) -> Result<f32, BrdbSchemaError> {
+        self.float().map(|value| value as f32)
+    }
+    fn as_brdb_f64(&self) -> Result<f64, BrdbSchemaError> {
+        self.float()
+    }
+    fn as_brdb_str(&self) -> Result<&str, BrdbSchemaError> {
+        match self {
+            MpsValue::Str(value) => Ok(value),
+            _ => Err(cast_err("str", self)),
+        }
+    }
+    fn as_brdb_asset(&self, _schema: &BrdbSchema, _ty: &str) -> Result<Option<usize>, BrdbSchemaError> {
+        match self {
+            MpsValue::Asset(value) => Ok(*value),
+            _ => Err(cast_err("asset", self)),
+        }
+    }
+    fn as_brdb_enum(&self, _schema: &BrdbSchema, _def: &BrdbSchemaEnum) -> Result<i32, BrdbSchemaError> {
+        match self {
+            MpsValue::Enum(value) => Ok(*value),
+            _ => Err(cast_err("enum", self)),
+        }
+    }
+    fn as_brdb_wire_variant(&self) -> Result<WireVariant, BrdbSchemaError> {
+        match self {
+            MpsValue::Wire(value) => Ok(value.clone()),
+            _ => Err(cast_err("wire variant", self)),
+        }
+    }
+    fn as_brdb_variant_member(&self) -> Option<&str> {
+        match self {
+            MpsValue::Struct { member, .. } => member.as_deref(),
+            _ => None,
+        }
+    }
+    fn has_brdb_struct_prop(&self, schema: &BrdbSchema, _struct_name: BrdbInterned, prop_name: BrdbInterned) -> bool {
+        self.field(schema, prop_name).is_some()
+    }
+    fn as_brdb_struct_prop_value(
+        &self,
+        schema: &BrdbSchema,
+        struct_name: BrdbInterned,
+        prop_name: BrdbInterned,
+    ) -> Result<&dyn AsBrdbValue, BrdbSchemaError> {
+        Ok(self.field_or_err(schema, struct_name, prop_name)?)
+    }
+    fn as_brdb_struct_prop_array(
+        &self,
+        schema: &BrdbSchema,
+        struct_name: BrdbInterned,
+        prop_name: BrdbInterned,
+    ) -> Result<BrdbArrayIter<'_>, BrdbSchemaError> {
+        match self.field_or_err(schema, struct_name, prop_name)? {
+            MpsValue::Array(values) => Ok(Box::new(values.iter().map(|value| value as &dyn AsBrdbValue))),
+            value => Err(cast_err("array", value)),
+        }
+    }
+    fn as_brdb_struct_prop_map(
+        &self,
+        schema: &BrdbSchema,
+        struct_name: BrdbInterned,
+        prop_name: BrdbInterned,
+    ) -> Result<BrdbMapIter<'_>, BrdbSchemaError> {
+        match self.field_or_err(schema, struct_name, prop_name)? {
+            MpsValue::Map(entries) => Ok(Box::new(
+                entries.iter().map(|(k, v)| (k as &dyn AsBrdbValue, v as &dyn AsBrdbValue)),
+            )),
+            value => Err(cast_err("map", value)),
+        }
+    }
+}
+
+/// the json type name, for error messages
+fn json_type(json: &Value) -> &'static str {
+    match json {
+        Value::Null => "null",
+        Value::Bool(_) => "bool",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn expected(ty: &str, json: &Value) -> String {
+    format!("expected {ty}, found {}", json_type(json))
+}
+
+/// turn json back into a wire variant, the reverse of wire_to_json
+fn wire_from_json(global_data: &BrdbSchemaGlobalData, json: &Value) -> Result<WireVariant, String> {
+    let float = |object: &Map<String, Value>, key: &str| {
+        object.get(key).and_then(Value::as_f64).ok_or_else(|| format!("missing number {key}"))
+    };
+    Ok(match json {
+        Value::Bool(value) => WireVariant::Bool(*value),
+        Value::Number(value) if value.is_f64() => WireVariant::Number(value.as_f64().unwrap_or_default()),
+        Value::Number(value) => match value.as_i64() {
+            Some(value) => WireVariant::Int(value),
+            None => WireVariant::Number(value.as_f64().unwrap_or_default()),
+        },
+        Value::String(value) => WireVariant::Str(value.clone()),
+        Value::Object(object) if object.contains_key("Exec") => WireVariant::Exec,
+        Value::Object(object) if object.contains_key("Object") => {
+            WireVariant::Object(asset_from_json(global_data, &object["Object"])?)
+        }
+        Value::Object(object) if object.contains_key("Pitch") => WireVariant::Rotator {
+            pitch: float(object, "Pitch")?,
+            yaw: float(object, "Yaw")?,
+            roll: float(object, "Roll")?,
+        },
+        Value::Object(object) if object.contains_key("R") => WireVariant::LinearColor {
+            r: float(object, "R")? as f32,
+            g: float(object, "G")? as f32,
+            b: float(object, "B")? as f32,
+            a: float(object, "A")? as f32,
+        },
+        Value::Object(object) if object.contains_key("W") => WireVariant::Quat {
+            x: float(object, "X")?,
+            y: float(object, "Y")?,
+            z: float(object, "Z")?,
+            w: float(object, "W")?,
+        },
+        Value::Object(object) => WireVariant::Vector(Vector3f {
+            x: float(object, "X")? as f32,
+            y: float(object, "Y")? as f32,
+            z: float(object, "Z")? as f32,
+        }),
+        json => return Err(expected("wire value", json)),
+    })
+}
+
+/// look up an asset by its `Type/Name`, or take its index as is
+fn asset_from_json(global_data: &BrdbSchemaGlobalData, json: &Value) -> Result<Option<usize>, String> {
+    match json {
+        Value::Null => Ok(None),
+        Value::Number(index) => index.as_u64().map(|index| Some(index as usize)).ok_or_else(|| expected("asset index", json)),
+        Value::String(name) => {
+            let (asset_type, asset_name) = name.split_once('/').ok_or_else(|| format!("asset names look like Type/Name, found {name}"))?;
+            global_data
+                .external_asset_references
+                .get_index_of(&(asset_type.to_string(), asset_name.to_string()))
+                .map(Some)
+                .ok_or_else(|| format!("unknown asset {name}"))
+        }
+        json => Err(expected("asset", json)),
+    }
+}
+
+/// map keys come back from json as strings, so parse them as the key type
+fn key_from_json(schema: &BrdbSchema, global_data: &BrdbSchemaGlobalData, ty: &str, key: &str) -> Result<MpsValue, String> {
+    let json = match ty {
+        "str" | "bundle_path_ref" => Value::String(key.to_string()),
+        _ if schema.get_enum(ty).is_some() => Value::String(key.to_string()),
+        _ => serde_json::from_str(key).map_err(|_| format!("invalid {ty} map key {key}"))?,
+    };
+    from_json(schema, global_data, ty, &json)
+}
+
+/// convert json into a value the schema writer can encode as the given type
+fn from_json(schema: &BrdbSchema, global_data: &BrdbSchemaGlobalData, ty: &str, json: &Value) -> Result<MpsValue, String> {
+    let number = |json: &Value| match json {
+        Value::Number(value) => Ok(match (value.as_i64(), value.as_u64()) {
+            (Some(value), _) => MpsValue::Int(value),
+            (_, Some(value)) => MpsValue::UInt(value),
+            _ => MpsValue::Float(value.as_f64().unwrap_or_default()),
+        }),
+        json => Err(expected(ty, json)),
+    };
+
+    Ok(match ty {
+        "bool" => MpsValue::Bool(json.as_bool().ok_or_else(|| expected(ty, json))?),
+        "u8" | "u16" | "u32" | "u64" | "i8" | "i16" | "i32" | "i64" => number(json)?,
+        "f32" | "f64" => match json {
+            Value::Null => MpsValue::Nil,
+            json => MpsValue::Float(json.as_f64().ok_or_else(|| expected(ty, json))?),
+        },
+        "str" | "bundle_path_ref" => MpsValue::Str(json.as_str().ok_or_else(|| expected(ty, json))?.to_string()),
+        "class" | "object" | "weak_object" => MpsValue::Asset(asset_from_json(global_data, json)?),
+        "wire_graph_variant" | "wire_graph_prim_math_variant" => MpsValue::Wire(wire_from_json(global_data, json)?),
+        ty if schema.get_variant(ty).is_some() => variant_from_json(schema, global_data, ty, json)?,
+        ty if schema.get_enum(ty).is_some() => {
+            let values = schema.get_enum(ty).unwrap_or_else(|| unreachable!());
+            match json {
+                Value::String(name) => {
+                    let value = schema
+                        .intern
+                        .get(name)
+                        .and_then(|name| values.get(&name))
+                        .ok_or_else(|| format!("{name} is not a value of {ty}"))?;
+                    MpsValue::Enum(*value)
+                }
+                Value::Number(value) => MpsValue::Enum(value.as_i64().ok_or_else(|| expected(ty, json))? as i32),
+                json => return Err(expected(ty, json)),
+            }
+        }
+        ty => {
+            let props = schema.get_struct(ty).ok_or_else(|| format!("unknown type {ty}"))?;
+            let object = json.as_object().ok_or_else(|| expected(ty, json))?;
+            let mut fields = HashMap::new();
+            for (name, value) in object {
+                let prop = schema
+                    .intern
+                    .get(name)
+                    .and_then(|name| props.get(&name))
+                    .ok_or_else(|| format!("{ty} has no field {name}"))?;
+                let lookup = |ty: &BrdbInterned| schema.intern.lookup(*ty).unwrap_or_default();
+                let value = match prop {
+                    BrdbSchemaStructProperty::Type(item) => from_json(schema, global_data, &lookup(item), value),
+                    BrdbSchemaStructProperty::Array(item) | BrdbSchemaStructProperty::FlatArray(item) => value
+                        .as_array()
+                        .ok_or_else(|| expected("array", value))?
+                        .iter()
+                        .enumerate()
+                        .map(|(i, value)| {
+                            from_json(schema, global_data, &lookup(item), value).map_err(|error| format!("[{i}]: {error}"))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                        .map(MpsValue::Array),
+                    BrdbSchemaStructProperty::Map(key, item) => value
+                        .as_object()
+                        .ok_or_else(|| expected("object", value))?
+                        .iter()
+                        .map(|(k, v)| {
+                            Ok((
+                                key_from_json(schema, global_data, &lookup(key), k)?,
+                                from_json(schema, global_data, &lookup(item), v).map_err(|error| format!("{k}: {error}"))?,
+                            ))
+                        })
+                        .collect::<Result<Vec<_>, String>>()
+                        .map(MpsValue::Map),
+                }
+                .map_err(|error| format!("{name}: {error}"))?;
+                fields.insert(name.clone(), value);
+            }
+            MpsValue::Struct { member: None, fields }
+        }
+    })
+}
+
+/// variants either hold one of their member structs or a plain wire value
+fn variant_from_json(schema: &BrdbSchema, global_data: &BrdbSchemaGlobalData, ty: &str, json: &Value) -> Result<MpsValue, String> {
+    if let Value::Object(object) = json {
+        // pick the first member struct that has every field in the object
+        let members = schema.get_variant(ty).into_iter().flatten();
+        for member in members.filter_map(|member| schema.intern.lookup(*member)) {
+            let Some(props) = schema.get_struct(&member) else {
+                continue;
+            };
+            let fits = object
+                .keys()
+                .all(|key| schema.intern.get(key).is_some_and(|key| props.contains_key(&key)));
+            if fits && let MpsValue::Struct { fields, .. } = from_json(schema, global_data, &member, json)? {
+                return Ok(MpsValue::Struct { member: Some(member), fields });
+            }
+        }
+    }
+    Ok(MpsValue::Wire(wire_from_json(global_data, json)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use brdb::{Brdb, schema::BrdbSchema};
+
+    use super::*;
+    use crate::{files_in, testing::world, write::save_file};
+
+    fn mps_files(db: &BrReader<Brdb>) -> Vec<String> {
+        let root = db.get_fs().unwrap();
+        files_in(&root, "").into_iter().map(|(path, _)| path).filter(|path| known(path)).collect()
+    }
+
+    #[test]
+    fn every_kind_of_file_is_in_the_test_world() {
+        let files = mps_files(&world());
+        for part in ["GlobalData.mps", "Owners.mps", "/ChunkIndex.mps", "/Chunks/", "/Components/", "/Wires/", "Entities/Chunks/"] {
+            assert!(files.iter().any(|path| path.contains(part)), "no {part} in {files:?}");
+        }
+    }
+
+    #[test]
+    fn encode_gives_back_the_same_bytes() {
+        let db = world();
+        for path in mps_files(&db) {
+            let json = decode(&db, &path).unwrap();
+            assert_eq!(encode(&db, &path, &json).unwrap(), db.read_file(&path).unwrap(), "{path}");
+        }
+    }
+
+    #[test]
+    fn decode_to_writes_what_decode_gives() {
+        let db = world();
+        for path in mps_files(&db) {
+            let mut out = Vec::new();
+            decode_to(&db, &path, &mut out).unwrap();
+            assert_eq!(serde_json::from_slice::<Value>(&out).unwrap(), decode(&db, &path).unwrap(), "{path}");
+        }
+    }
+
+    #[test]
+    fn every_file_validates() {
+        let db = world();
+        for path in mps_files(&db) {
+            assert!(validate(&db, &path).is_ok(), "{path}");
+        }
+    }
+
+    #[test]
+    fn unknown_enum_values_are_an_error() {
+        let db = world();
+        let schema = mps_files(&db)
+            .into_iter()
+            .map(|path| find(&db, &path, &layout(&path).unwrap()).unwrap().schema)
+            .find(|schema| !schema.enums.is_empty())
+            .expect("some schema has an enum");
+        let (name, values) = schema.enums.iter().next().unwrap();
+        assert!(enum_name(&schema, *name, *values.values().next().unwrap() as u64).unwrap().is_string());
+        assert!(enum_name(&schema, *name, values.len() as u64 + 100).is_err());
+    }
+
+    #[test]
+    fn counters_that_dont_match_the_instances_are_an_error() {
+        let db = world();
+        let path = mps_files(&db).into_iter().find(|path| path.contains("/Components/")).unwrap();
+        let mut json = decode(&db, &path).unwrap();
+        json["instances"].as_array_mut().unwrap().pop();
+        assert!(encode(&db, &path, &json).is_err());
+    }
+
+    /// save a new version of a schema, made from the text of the one the world has now
+    fn resave_schema(db: &BrReader<Brdb>, path: &str, change: impl Fn(String) -> String) {
+        let text = format!("{}", db.read_file(path).unwrap().as_slice().read_brdb_schema().unwrap());
+        let schema = BrdbSchema::new_parsed(&change(text)).unwrap();
+        // revisions are timed to the second, and one in the same second as the world was made would count as
+        // the schema the files were saved with
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        save_file(db, path, schema.to_bytes().unwrap(), "New schema").unwrap();
+    }
+
+    #[test]
+    fn files_are_encoded_with_the_schema_they_were_saved_with() {
+        let db = world();
+        let before = db.read_file("World/0/Owners.mps").unwrap();
+        let json = decode(&db, "World/0/Owners.mps").unwrap();
+        resave_schema(&db, "World/0/Owners.schema", |text| format!("{text}\nstruct Unused {{\n    A: u32,\n}}"));
+        assert_eq!(encode(&db, "World/0/Owners.mps", &json).unwrap(), before);
+    }
+
+    #[test]
+    fn files_the_latest_schema_would_read_differently_are_an_error() {
+        let db = world();
+        let json = decode(&db, "World/0/Owners.mps").unwrap();
+        resave_schema(&db, "World/0/Owners.schema", |text| {
+            text.replace("UserNames", "Swapped").replace("DisplayNames", "UserNames").replace("Swapped", "DisplayNames")
+        });
+        assert!(encode(&db, "World/0/Owners.mps", &json).is_err());
+    }
+}
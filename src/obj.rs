@@ -0,0 +1,75 @@
+use std::{fs, path::Path};
+use brdb::{BrReader, Brdb};
+use indexmap::IndexMap;
+use serde_json::json;
+
+use crate::{bricks::grid_bricks, error::Error, mesh::brick_mesh, output::Output};
+
+/// a rough look for each brickadia material, since .mtl files cant describe the real ones
+fn material(name: &str) -> String {
+    let mut mtl = format!("newmtl {name}\nKd 0.8 0.8 0.8\nKa 0 0 0\n");
+    match name {
+        name if name.contains("Glow") => mtl.push_str("Ke 1 1 1\nNs 10\n"),
+        name if name.contains("Glass") => mtl.push_str("d 0.5\nNs 200\n"),
+        name if name.contains("Metallic") => mtl.push_str("Ks 0.9 0.9 0.9\nNs 500\n"),
+        _ => mtl.push_str("Ks 0.1 0.1 0.1\nNs 10\n"),
+    }
+    mtl
+}
+
+/// save the bricks on the main grid as one triangle mesh in a .obj file, with a .mtl file next to it that has
+/// a material for each brickadia material. positions are scaled by `scale` and made y up.
+/// with `vertex_colors` every vertex also gets its brick's color, which blender reads as a color attribute
+pub fn export(db: &BrReader<Brdb>, out: &Path, vertex_colors: bool, scale: f32) -> Result<Output, Error> {
+    let bricks = grid_bricks(db, 1, None)?;
+
+    // bricks are grouped by material so each group can use its own
+    let mut groups: IndexMap<String, Vec<_>> = IndexMap::new();
+    for saved in bricks.into_iter().filter(|saved| saved.brick.visible) {
+        groups.entry(saved.brick.material.to_string()).or_default().push(saved.brick);
+    }
+
+    let mtl_path = out.with_extension("mtl");
+    let mtl_name = mtl_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    let mut obj = format!("# exported by brdb_cmd\nmtllib {mtl_name}\n");
+    let mut mtl = String::new();
+    let (mut vertices, mut triangles, mut count) = (0, 0, 0);
+    for (name, bricks) in &groups {
+        obj.push_str(&format!("g {name}\nusemtl {name}\n"));
+        mtl.push_str(&material(name));
+        for brick in bricks {
+            let mesh = brick_mesh(brick, scale);
+            let [r, g, b] = [brick.color.r, brick.color.g, brick.color.b].map(|c| c as f32 / 255.0);
+            for [x, y, z] in &mesh.vertices {
+                match vertex_colors {
+                    true => obj.push_str(&format!("v {x:.4} {y:.4} {z:.4} {r:.4} {g:.4} {b:.4}\n")),
+                    false => obj.push_str(&format!("v {x:.4} {y:.4} {z:.4}\n")),
+                }
+            }
+            // obj indices start at 1 and count every vertex in the file
+            for [a, b, c] in &mesh.triangles {
+                obj.push_str(&format!("f {} {} {}\n", vertices + a + 1, vertices + b + 1, vertices + c + 1));
+            }
+            vertices += mesh.vertices.len();
+            triangles += mesh.triangles.len();
+            count += 1;
+        }
+    }
+
+    fs::write(out, obj)?;
+    fs::write(&mtl_path, mtl)?;
+    let text = format!(
+        "exported {count} bricks ({vertices} vertices, {triangles} triangles) to {} and {}",
+        out.display(),
+        mtl_path.display()
+    );
+    let json = json!({
+        "out": out,
+        "mtl": mtl_path,
+        "bricks": count,
+        "vertices": vertices,
+        "triangles": triangles,
+        "materials": groups.keys().collect::<Vec<_>>(),
+    });
+    Ok(Output::new(text, json))
+}
@@ -0,0 +1,163 @@
+use std::{env, fs::File, io, io::BufWriter, io::Write, path::Path, process};
+use serde_json::Value;
+
+/// what a command shows: text for people and the same thing as json for scripts
+pub struct Output {
+    /// usually text, but raw file contents go through as they are
+    pub text: Vec<u8>,
+    pub json: Value,
+    /// the text is file contents that have to come out byte for byte, without a newline after them
+    pub raw: bool,
+    /// the command ran but found something wrong, so brdb_cmd exits with 1 after showing the output
+    pub failed: bool,
+}
+
+impl Output {
+    pub fn new(text: impl Into<Vec<u8>>, json: Value) -> Self {
+        Self { text: text.into(), json, raw: false, failed: false }
+    }
+
+    /// file contents that are written out exactly as they are
+    pub fn raw(bytes: Vec<u8>, json: Value) -> Self {
+        Self { text: bytes, json, raw: true, failed: false }
+    }
+
+    /// the same output, marked as having found problems
+    pub fn failed(self) -> Self {
+        Self { failed: true, ..self }
+    }
+
+    /// for commands like the shell that already showed everything themselves
+    pub fn none() -> Self {
+        Self::new(Vec::new(), Value::Null)
+    }
+
+    /// the text as a string, for places that show it inline
+    pub fn text_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.text).into_owned()
+    }
+
+    /// write the output as text or json
+    fn write_to(&self, out: impl Write, json: bool) -> io::Result<()> {
+        let mut out = BufWriter::new(out);
+        match json {
+            true if self.json.is_null() => return Ok(()),
+            true => writeln!(out, "{}", serde_json::to_string_pretty(&self.json)?)?,
+            false if self.text.is_empty() && self.json.is_null() => return Ok(()),
+            false => {
+                out.write_all(&self.text)?;
+                if !self.raw && !self.text.ends_with(b"\n") {
+                    writeln!(out)?;
+                }
+            }
+        }
+        out.flush()
+    }
+
+    /// print the output as text or json
+    pub fn print(&self, json: bool) -> io::Result<()> {
+        self.write_to(io::stdout().lock(), json)
+    }
+
+    /// show the output through $PAGER like git does, or just print it if there's no pager to run
+    pub fn page(&self, json: bool) -> io::Result<()> {
+        let pager = env::var("PAGER")
+            .ok()
+            .filter(|pager| !pager.trim().is_empty())
+            .unwrap_or_else(|| String::from(if cfg!(windows) { "more" } else { "less" }));
+        let mut words = pager.split_whitespace();
+        let program = words.next().unwrap_or("less");
+
+        // less quits by itself if everything fits on one screen, and keeps the colors
+        let mut child = match process::Command::new(program)
+            .args(words)
+            .env("LESS", env::var("LESS").unwrap_or_else(|_| String::from("FRX")))
+            .stdin(process::Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(_) => return self.print(json),
+        };
+        let written = match child.stdin.take() {
+            Some(stdin) => self.write_to(stdin, json),
+            None => Ok(()),
+        };
+        child.wait()?;
+
+        // quitting the pager before the end closes the pipe, that's not a problem
+        match written {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+            written => written,
+        }
+    }
+
+    /// save the output to a file instead of printing it
+    pub fn save(&self, path: &Path, json: bool) -> io::Result<()> {
+        // commands that wrote the file themselves have nothing left to save
+        if self.text.is_empty() && self.json.is_null() {
+            return Ok(());
+        }
+        self.write_to(File::create(path)?, json)
+    }
+}
+
+/// bytes as a lowercase hex string
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// bytes as an xxd style dump: the offset, 16 bytes in pairs, then the printable ones as text
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut lines = Vec::new();
+    for (i, row) in bytes.chunks(16).enumerate() {
+        let mut groups = row.chunks(2).map(hex).collect::<Vec<_>>().join(" ");
+        // short last rows are padded so the text still lines up
+        groups.push_str(&" ".repeat(39 - groups.len()));
+        let text: String = row
+            .iter()
+            .map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.',
+            })
+            .collect();
+        lines.push(format!("{:08x}: {groups}  {text}", i * 16));
+    }
+    lines.join("\n")
+}
+
+/// rows lined up in columns as wide as their widest value, the first row being the header.
+/// numbers line up on the right, everything else on the left
+pub fn table(rows: &[Vec<String>]) -> String {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let mut widths = vec![0; columns];
+    for row in rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let mut lines = Vec::new();
+    for row in rows {
+        let mut cells = Vec::new();
+        for (i, (value, width)) in row.iter().zip(&widths).enumerate() {
+            let numeric = value.parse::<f64>().is_ok();
+            cells.push(match (numeric, i + 1 == row.len()) {
+                (true, _) => format!("{value:>width$}"),
+                // the last column doesnt need padding after it
+                (false, true) => value.clone(),
+                (false, false) => format!("{value:<width$}"),
+            });
+        }
+        lines.push(cells.join("  "));
+    }
+    lines.join("\n")
+}
+
+/// rows as csv, quoting values that have commas, quotes or newlines in them
+pub fn csv(rows: &[Vec<String>]) -> String {
+    let quote = |value: &String| match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.clone(),
+    };
+    rows.iter().map(|row| row.iter().map(quote).collect::<Vec<_>>().join(",")).collect::<Vec<_>>().join("\n")
+}
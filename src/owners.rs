@@ -0,0 +1,102 @@
+use brdb::{BrFsReader, BrReader, Brdb, OwnerTableSoA};
+use serde_json::{Value, json};
+
+use crate::{
+    error::Error,
+    output::{Output, table},
+    rewrite::{Edit, rewrite_main_grid},
+};
+
+/// the players that own bricks and entities in the world. bricks point at them by their index in here,
+/// and the first one is usually PUBLIC, for things nobody owns
+pub fn owner_table<T: BrFsReader>(db: &BrReader<T>) -> Result<OwnerTableSoA, Error> {
+    Ok(OwnerTableSoA::try_from(&db.owners_soa()?.to_value())?)
+}
+
+/// the index of the owner with this user id or name, where names dont care about case
+pub fn find_owner(owners: &OwnerTableSoA, name_or_id: &str) -> Result<usize, Error> {
+    let found: Vec<usize> = (0..owners.user_ids.len())
+        .filter(|&index| {
+            owners.user_ids[index].uuid().to_string().eq_ignore_ascii_case(name_or_id)
+                || owners.user_names.get(index).is_some_and(|name| name.eq_ignore_ascii_case(name_or_id))
+                || owners.display_names.get(index).is_some_and(|name| name.eq_ignore_ascii_case(name_or_id))
+        })
+        .collect();
+    match found.as_slice() {
+        [index] => Ok(*index),
+        [] => Err(Error::NotFound(format!("owner {name_or_id}"))),
+        _ => Err(Error::Other(format!("more than one owner is called {name_or_id}, use their user id instead"))),
+    }
+}
+
+/// list everyone in the owner table, with the counts the world saved for them
+pub fn owners<T: BrFsReader>(db: &BrReader<T>) -> Result<Output, Error> {
+    let owners = owner_table(db)?;
+    let mut rows = vec![
+        ["INDEX", "USER ID", "NAME", "DISPLAY NAME", "BRICKS", "COMPONENTS", "ENTITIES", "WIRES"]
+            .map(String::from)
+            .to_vec(),
+    ];
+    let mut json = Vec::new();
+    for (index, id) in owners.user_ids.iter().enumerate() {
+        let count = |counts: &[u32]| counts.get(index).copied().unwrap_or(0);
+        let (bricks, components, entities, wires) = (
+            count(&owners.brick_counts),
+            count(&owners.component_counts),
+            count(&owners.entity_counts),
+            count(&owners.wire_counts),
+        );
+        let name = owners.user_names.get(index).cloned().unwrap_or_default();
+        let display_name = owners.display_names.get(index).cloned().unwrap_or_default();
+        let id = id.uuid().to_string();
+
+        rows.push(vec![
+            index.to_string(),
+            id.clone(),
+            name.clone(),
+            display_name.clone(),
+            bricks.to_string(),
+            components.to_string(),
+            entities.to_string(),
+            wires.to_string(),
+        ]);
+        json.push(json!({
+            "index": index,
+            "user_id": id,
+            "user_name": name,
+            "display_name": display_name,
+            "bricks": bricks,
+            "components": components,
+            "entities": entities,
+            "wires": wires,
+        }));
+    }
+    Ok(Output::new(table(&rows), Value::Array(json)))
+}
+
+/// hand every brick on the main grid that `from` owns over to `to`, as a new revision. the counts in the
+/// owner table move along with them
+pub fn reassign(db: &BrReader<Brdb>, from: &str, to: &str) -> Result<Output, Error> {
+    let owners = owner_table(db)?;
+    let (from, to) = (find_owner(&owners, from)?, find_owner(&owners, to)?);
+    let name = |index: usize| owners.user_names.get(index).cloned().unwrap_or_default();
+    if from == to {
+        return Err(Error::Other(format!("{} already owns their own bricks", name(from))));
+    }
+
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| match saved.brick.owner_index.unwrap_or(0) == from {
+        true => Edit::Replace(brdb::Brick { owner_index: Some(to), ..saved.brick.clone() }),
+        false => Edit::Keep,
+    })?;
+    if rewritten.replaced == 0 {
+        return Ok(Output::new(
+            format!("{} doesnt own any bricks, nothing changed", name(from)),
+            json!({ "from": name(from), "to": name(to), "bricks": 0 }),
+        ));
+    }
+    db.write_pending(format!("Reassign {} bricks from {} to {}", rewritten.replaced, name(from), name(to)), pending)?;
+
+    let text = format!("{} bricks went from {} to {}", rewritten.replaced, name(from), name(to));
+    Ok(Output::new(text, json!({ "from": name(from), "to": name(to), "bricks": rewritten.replaced })))
+}
@@ -0,0 +1,117 @@
+use std::{fs, path::Path};
+use brdb::{BrFsReader, Brdb, IntoReader, pending::BrPendingFs};
+use serde_json::{Value, json};
+
+use crate::{error::Error, live, mps, output::Output, progress::Progress, write::file_patch};
+
+/// a file found in the folder being packed
+enum Packed {
+    Raw(Vec<u8>),
+    /// a .mps file that was decoded to json by extract
+    Json(Value),
+}
+
+/// collect every file below a folder, keyed by its path inside the world
+fn collect(dir: &Path, path: &str, files: &mut Vec<(String, Packed)>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let child_path = match path {
+            "" => name.clone(),
+            _ => format!("{path}/{name}"),
+        };
+
+        if entry.file_type()?.is_dir() {
+            collect(&entry.path(), &child_path, files)?;
+            continue;
+        }
+
+        let data = fs::read(entry.path())?;
+        match child_path.strip_suffix(".mps.json") {
+            Some(mps_path) => {
+                let json = serde_json::from_slice(&data)
+                    .map_err(|error| Error::Other(format!("{child_path}: invalid json: {error}")))?;
+                files.push((format!("{mps_path}.mps"), Packed::Json(json)));
+            }
+            None => files.push((child_path, Packed::Raw(data))),
+        }
+    }
+    Ok(())
+}
+
+/// write a set of files into a world as one revision
+fn save_all(db: &Brdb, files: Vec<(String, Vec<u8>)>, description: &str) -> Result<(), Error> {
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    for (path, data) in files {
+        pending.patch(file_patch(&path, data))?;
+    }
+    db.write_pending(description, pending)?;
+    Ok(())
+}
+
+/// encode the json files, using a scratch copy of the world that has the new schemas in it
+fn encode_all(world: &Brdb, files: Vec<(String, Packed)>) -> Result<Vec<(String, Vec<u8>)>, Error> {
+    let mut raw = Vec::new();
+    let mut json = Vec::new();
+    for (path, file) in files {
+        match file {
+            Packed::Raw(data) => raw.push((path, data)),
+            Packed::Json(value) => json.push((path, value)),
+        }
+    }
+    if json.is_empty() {
+        return Ok(raw);
+    }
+
+    let scratch = Brdb::new_memory()?;
+    let existing = world.get_fs()?.to_pending(world)?;
+    if !matches!(&existing, BrPendingFs::Root(files) if files.is_empty()) {
+        scratch.write_pending("Existing", existing)?;
+    }
+    save_all(&scratch, raw.clone(), "Raw files")?;
+
+    // everything else needs the global data, so it has to go in first
+    let (global_data, json): (Vec<_>, Vec<_>) =
+        json.into_iter().partition(|(path, _)| path.ends_with("/GlobalData.mps"));
+    let mut encoded = Vec::new();
+    for (path, value) in global_data {
+        let data = mps::encode(&(&scratch).into_reader(), &path, &value).map_err(|error| Error::Other(format!("{path}: {error}")))?;
+        encoded.push((path, data));
+    }
+    if !encoded.is_empty() {
+        save_all(&scratch, encoded.clone(), "Global data")?;
+    }
+
+    // a fresh reader so the new global data gets picked up
+    let reader = (&scratch).into_reader();
+    let progress = Progress::new("encoding", json.len(), "files");
+    for (path, value) in json {
+        progress.tick();
+        let data = mps::encode(&reader, &path, &value).map_err(|error| Error::Other(format!("{path}: {error}")))?;
+        encoded.push((path, data));
+    }
+
+    raw.extend(encoded);
+    Ok(raw)
+}
+
+/// write the files from a folder made by extract into a world, creating it if needed
+pub fn pack(input: &Path, world_path: &Path) -> Result<Output, Error> {
+    if !input.is_dir() {
+        return Err(Error::Other(format!("not a folder: {}", input.display())));
+    }
+
+    let mut files = Vec::new();
+    collect(input, "", &mut files)?;
+    let paths: Vec<String> = files.iter().map(|(path, _)| format!("/{path}")).collect();
+
+    if world_path.exists() {
+        live::check_writable(world_path)?;
+    }
+    let world = Brdb::new(world_path)?;
+    let files = encode_all(&world, files)?;
+    save_all(&world, files, &format!("Pack {}", input.display()))?;
+
+    let text = format!("packed {} files into {}", paths.len(), world_path.display());
+    Ok(Output::new(text, json!({ "world": world_path, "files": paths })))
+}
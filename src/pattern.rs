@@ -0,0 +1,36 @@
+use brdb::fs::BrFs;
+use glob::{MatchOptions, Pattern};
+
+use crate::{entries_in, error::Error};
+
+/// `*` and `?` stay inside one folder, `**` goes through any number of them
+const OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// whether a path has any glob characters in it
+pub fn is_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// expand a glob pattern into every matching path in the world. plain paths come back as they are
+pub fn expand(fs: &BrFs, path: &str) -> Result<Vec<String>, Error> {
+    let path = path.trim_start_matches('/').trim_end_matches('/');
+    if !is_pattern(path) {
+        return Ok(vec![path.to_string()]);
+    }
+
+    let pattern = Pattern::new(path).map_err(|error| Error::Other(format!("invalid pattern {path}: {error}")))?;
+    let matches: Vec<String> = entries_in(fs, "")
+        .into_iter()
+        .map(|(entry, _)| entry.trim_end_matches('/').to_string())
+        .filter(|entry| pattern.matches_with(entry, OPTIONS))
+        .collect();
+
+    match matches.is_empty() {
+        true => Err(Error::NotFound(format!("/{path}"))),
+        false => Ok(matches),
+    }
+}
@@ -0,0 +1,77 @@
+use std::{
+    fmt::Write,
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+
+/// set by --quiet
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// how long something has to take before it gets a progress line, so quick commands stay quiet
+const AFTER: Duration = Duration::from_millis(500);
+/// how many times a second the line is redrawn
+const EVERY: u8 = 10;
+
+/// stop showing progress, for --quiet
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// a line on stderr that counts how much of something is done, how fast it's going and how long is left.
+/// it only shows up when stderr is a terminal and the work takes more than a moment, and it's cleared
+/// again when it's dropped. it can be moved along from several threads at once
+pub struct Progress {
+    bar: ProgressBar,
+    start: Instant,
+    /// whether the line is drawn yet, or never will be
+    shown: AtomicBool,
+}
+
+impl Progress {
+    /// `label` says what's happening, like "extracting", and `unit` what's counted, like "files"
+    pub fn new(label: &'static str, total: usize, unit: &'static str) -> Self {
+        let show = !QUIET.load(Ordering::Relaxed) && io::stderr().is_terminal();
+        // drawn nowhere until it's been going for long enough
+        let bar = ProgressBar::with_draw_target(Some(total as u64), ProgressDrawTarget::hidden());
+        let style = ProgressStyle::with_template("{prefix} {pos}/{len} {msg} ({rate}{left})")
+            .expect("the progress template is valid")
+            .with_key("rate", |state: &ProgressState, out: &mut dyn Write| {
+                let _ = write!(out, "{:.0}/s", state.per_sec());
+            })
+            .with_key("left", |state: &ProgressState, out: &mut dyn Write| {
+                if state.per_sec() > 0.0 {
+                    let _ = write!(out, ", about {} left", duration(state.eta().as_secs_f64()));
+                }
+            });
+        bar.set_style(style);
+        bar.set_prefix(label);
+        bar.set_message(unit);
+        Self { bar, start: Instant::now(), shown: AtomicBool::new(!show) }
+    }
+
+    /// count one more thing as done
+    pub fn tick(&self) {
+        self.bar.inc(1);
+        if self.start.elapsed() >= AFTER && !self.shown.swap(true, Ordering::Relaxed) {
+            self.bar.set_draw_target(ProgressDrawTarget::stderr_with_hz(EVERY));
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.bar.finish_and_clear();
+    }
+}
+
+/// seconds as something like 1m 05s
+fn duration(seconds: f64) -> String {
+    let seconds = seconds.round() as u64;
+    match seconds {
+        0..60 => format!("{seconds}s"),
+        60..3600 => format!("{}m {:02}s", seconds / 60, seconds % 60),
+        _ => format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60),
+    }
+}
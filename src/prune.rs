@@ -0,0 +1,79 @@
+use std::collections::HashSet;
+use brdb::{BrFsReader, BrReader, Brdb, ChunkMeta};
+use serde_json::json;
+
+use crate::{bricks::grid_ids, error::Error, files_in, output::Output, rewrite::{Rewritten, prune_main_grid}, traverse, write::remove_entry};
+
+/// clear out what a world doesnt need anymore: chunks on the main grid without any bricks, components and wires
+/// on bricks that arent there, and chunk files on any grid that its chunk index doesnt know about or says are empty.
+/// with `dry_run` it only says what would go
+pub fn prune(db: &BrReader<Brdb>, dry_run: bool) -> Result<Output, Error> {
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let root = db.get_fs()?;
+
+    // chunk files are named after their chunk, so any that the index doesnt list are left over from something
+    let mut stray = Vec::new();
+    let mut empty = 0;
+    let grid_ids = grid_ids(db)?;
+    for &grid_id in &grid_ids {
+        if db.find_file_by_path(format!("World/0/Bricks/Grids/{grid_id}/ChunkIndex.mps"))?.is_none() {
+            continue;
+        }
+        let chunks = db.brick_chunk_index(grid_id)?;
+        if grid_id == 1 {
+            empty = chunks.iter().filter(|chunk| chunk.num_bricks == 0).count();
+        }
+        // components and wires files also count when the index says the chunk doesnt have any
+        let listed = |has: fn(&ChunkMeta) -> bool| -> HashSet<String> {
+            chunks.iter().filter(|chunk| has(chunk)).map(|chunk| format!("{}.mps", chunk.index)).collect()
+        };
+        let folders = [
+            ("Chunks", listed(|_| true)),
+            ("Components", listed(|chunk| chunk.num_components > 0)),
+            ("Wires", listed(|chunk| chunk.num_wires > 0)),
+        ];
+        for (folder, known) in folders {
+            let path = format!("World/0/Bricks/Grids/{grid_id}/{folder}");
+            let Ok(fs) = traverse(&root, &path) else {
+                continue;
+            };
+            for (name, _) in files_in(fs, "") {
+                if !known.contains(&name) {
+                    stray.push(format!("{path}/{name}"));
+                }
+            }
+        }
+    }
+    for path in &stray {
+        remove_entry(&mut pending, path)?;
+    }
+
+    let pruned = match db.find_file_by_path("World/0/Bricks/Grids/1/ChunkIndex.mps")? {
+        Some(_) => prune_main_grid(db, &mut pending)?,
+        None => Rewritten::default(),
+    };
+    let removed = empty + stray.len() + pruned.components + pruned.wires;
+    if removed > 0 && !dry_run {
+        db.write_pending(String::from("Prune unused chunks, components and wires"), pending)?;
+    }
+
+    let mut lines: Vec<String> = stray.iter().map(|path| format!("/{path}")).collect();
+    let verb = if dry_run { "would remove" } else { "removed" };
+    lines.push(match removed {
+        0 => String::from("nothing to prune"),
+        _ => format!(
+            "{verb} {empty} empty chunks, {} stray chunk files, {} components and {} wires",
+            stray.len(),
+            pruned.components,
+            pruned.wires
+        ),
+    });
+    let json = json!({
+        "dry_run": dry_run,
+        "empty_chunks": empty,
+        "stray_files": stray.iter().map(|path| format!("/{path}")).collect::<Vec<_>>(),
+        "components": pruned.components,
+        "wires": pruned.wires,
+    });
+    Ok(Output::new(lines.join("\n"), json))
+}
@@ -0,0 +1,52 @@
+use std::path::Path;
+use brdb::{BrFsReader, BrReader, Brdb, Position};
+use serde_json::json;
+
+use crate::{
+    bricks::{SavedBrick, chunk_bricks, save_bricks},
+    chunks::chunk_bounds,
+    error::Error,
+    output::Output,
+};
+
+/// whether a position is inside the box from `min` to `max`, both included
+fn inside(position: &Position, min: &Position, max: &Position) -> bool {
+    (min.x..=max.x).contains(&position.x)
+        && (min.y..=max.y).contains(&position.y)
+        && (min.z..=max.z).contains(&position.z)
+}
+
+/// every brick on the main grid inside the box, only reading the chunks that overlap it
+fn region_bricks<T: BrFsReader>(db: &BrReader<T>, min: &Position, max: &Position) -> Result<Vec<SavedBrick>, Error> {
+    let mut bricks = Vec::new();
+    for chunk in db.brick_chunk_index(1)? {
+        let (low, high) = chunk_bounds(&chunk);
+        let overlaps = (0..3).all(|axis| {
+            let (min, max) = ([min.x, min.y, min.z][axis], [max.x, max.y, max.z][axis]);
+            min < high[axis] && max >= low[axis]
+        });
+        if !overlaps {
+            continue;
+        }
+        let in_chunk = chunk_bricks(db, 1, chunk.index)?;
+        bricks.extend(in_chunk.into_iter().filter(|saved| inside(&saved.brick.position, min, max)));
+    }
+    Ok(bricks)
+}
+
+/// save the bricks on the main grid inside a box to a new world, or to a json file if `out` ends in .json
+pub fn export(db: &BrReader<Brdb>, min: Position, max: Position, out: &Path) -> Result<Output, Error> {
+    if min.x > max.x || min.y > max.y || min.z > max.z {
+        return Err(Error::Other(String::from("--min has to be below --max on every axis")));
+    }
+    if out.exists() {
+        return Err(Error::Other(format!("{} already exists", out.display())));
+    }
+
+    let bricks = region_bricks(db, &min, &max)?;
+    let count = bricks.len();
+    save_bricks(db, bricks, out, json!({ "min": [min.x, min.y, min.z], "max": [max.x, max.y, max.z] }))?;
+
+    let text = format!("exported {count} bricks to {}", out.display());
+    Ok(Output::new(text, json!({ "out": out, "bricks": count })))
+}
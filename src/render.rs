@@ -0,0 +1,230 @@
+use std::{cmp::Reverse, collections::HashMap, fs, io::Write, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb};
+use flate2::{Compression, Crc, write::ZlibEncoder};
+use serde_json::json;
+
+use crate::{bricks::grid_bricks, cli::MapColor, error::Error, mesh::brick_bounds, output::Output};
+
+/// the biggest image side render makes, so a tiny scale on a big world doesnt eat all the memory
+const MAX_SIDE: usize = 8192;
+
+/// an rgba image, row by row from the top
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![[0; 4]; width * height] }
+    }
+
+    /// encode as a png file
+    pub fn png(&self) -> Result<Vec<u8>, Error> {
+        // every row starts with a filter type, and 0 means unfiltered
+        let mut rows = Vec::with_capacity((self.width * 4 + 1) * self.height);
+        for row in self.pixels.chunks(self.width.max(1)) {
+            rows.push(0);
+            rows.extend(row.iter().flatten());
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&rows)?;
+
+        let mut header = Vec::new();
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, rgba, then the default compression, filtering and no interlacing
+        header.extend([8, 6, 0, 0, 0]);
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+        for (kind, data) in [(b"IHDR", header), (b"IDAT", encoder.finish()?), (b"IEND", Vec::new())] {
+            png.extend((data.len() as u32).to_be_bytes());
+            let mut crc = Crc::new();
+            crc.update(kind);
+            crc.update(&data);
+            png.extend(kind);
+            png.extend(data);
+            png.extend(crc.sum().to_be_bytes());
+        }
+        Ok(png)
+    }
+}
+
+/// a color from blue through green and yellow to red, for a value from 0 to 1
+pub fn gradient(value: f32) -> [u8; 4] {
+    let stops = [[40.0, 60.0, 200.0], [40.0, 180.0, 80.0], [240.0, 220.0, 60.0], [210.0, 50.0, 40.0]];
+    let position = value.clamp(0.0, 1.0) * (stops.len() - 1) as f32;
+    let low = (position as usize).min(stops.len() - 2);
+    let t = position - low as f32;
+    let [r, g, b] = [0, 1, 2].map(|i| (stops[low][i] + (stops[low + 1][i] - stops[low][i]) * t) as u8);
+    [r, g, b, 255]
+}
+
+/// draw the bricks on the main grid from above into a png, one pixel for every `scale` world units.
+/// the image covers every brick, or only the part between `min` and `max` when theyre given
+pub fn map(
+    db: &BrReader<Brdb>,
+    out: &Path,
+    scale: u32,
+    color: MapColor,
+    min: Option<[i32; 2]>,
+    max: Option<[i32; 2]>,
+) -> Result<Output, Error> {
+    if scale == 0 {
+        return Err(Error::Other(String::from("--scale has to be at least 1")));
+    }
+    let bricks: Vec<_> = grid_bricks(db, 1, None)?
+        .into_iter()
+        .filter(|saved| saved.brick.visible)
+        .map(|saved| (brick_bounds(&saved.brick), saved.brick))
+        .collect();
+    if bricks.is_empty() && (min.is_none() || max.is_none()) {
+        return Err(Error::Other(String::from("there are no bricks to draw, give --min and --max to draw an empty map")));
+    }
+
+    // the area to draw, defaulting to the box around every brick
+    let mut area = ([f32::MAX; 2], [f32::MIN; 2]);
+    for ((low, high), _) in &bricks {
+        area.0 = [area.0[0].min(low[0]), area.0[1].min(low[1])];
+        area.1 = [area.1[0].max(high[0]), area.1[1].max(high[1])];
+    }
+    let min = min.map_or(area.0, |[x, y]| [x as f32, y as f32]);
+    let max = max.map_or(area.1, |[x, y]| [x as f32, y as f32]);
+    if min[0] >= max[0] || min[1] >= max[1] {
+        return Err(Error::Other(String::from("--min has to be below --max on both axes")));
+    }
+    let scale = scale as f32;
+    let (width, height) = (((max[0] - min[0]) / scale).ceil() as usize, ((max[1] - min[1]) / scale).ceil() as usize);
+    if width > MAX_SIDE || height > MAX_SIDE {
+        return Err(Error::Other(format!("the map would be {width}x{height} pixels, try a bigger --scale")));
+    }
+
+    // the lowest and highest tops, for coloring by height
+    let tops = bricks.iter().fold((f32::MAX, f32::MIN), |(low, high), ((_, top), _)| (low.min(top[2]), high.max(top[2])));
+
+    // x goes right and y goes down, which is how brickadia's map looks from above
+    let mut image = Image::new(width, height);
+    let mut depth = vec![f32::MIN; width * height];
+    let mut drawn = 0;
+    for ((low, high), brick) in &bricks {
+        if high[0] <= min[0] || high[1] <= min[1] || low[0] >= max[0] || low[1] >= max[1] {
+            continue;
+        }
+        // every brick covers at least one pixel, even when its smaller than one
+        let span = |axis: usize, limit: usize| {
+            let start = (((low[axis] - min[axis]) / scale).max(0.0) as usize).min(limit - 1);
+            let end = (((high[axis] - min[axis]) / scale).ceil() as usize).clamp(start + 1, limit);
+            start..end
+        };
+        let (xs, ys) = (span(0, width), span(1, height));
+        let pixel = match color {
+            MapColor::Brick => [brick.color.r, brick.color.g, brick.color.b, 255],
+            MapColor::Height => gradient((high[2] - tops.0) / (tops.1 - tops.0).max(1.0)),
+        };
+        for y in ys {
+            for x in xs.clone() {
+                // only the highest brick on each pixel shows
+                if high[2] >= depth[y * width + x] {
+                    depth[y * width + x] = high[2];
+                    image.pixels[y * width + x] = pixel;
+                }
+            }
+        }
+        drawn += 1;
+    }
+
+    let png = image.png()?;
+    fs::write(out, &png)?;
+    let text = format!("drew {drawn} bricks on a {width}x{height} map in {}", out.display());
+    let json = json!({
+        "out": out,
+        "bricks": drawn,
+        "width": width,
+        "height": height,
+        "min": min,
+        "max": max,
+        "bytes": png.len(),
+    });
+    Ok(Output::new(text, json))
+}
+
+/// color cells by how many bricks are in them, from above. without `cell` the cells are the chunks, which only
+/// needs the chunk index, otherwise every brick is read and counted into cells `cell` world units wide.
+/// each cell is `pixels` pixels wide, and counts go on a log scale so a few huge builds dont drown everything else
+pub fn heatmap(db: &BrReader<Brdb>, out: &Path, cell: Option<u32>, pixels: u32) -> Result<Output, Error> {
+    if cell == Some(0) || pixels == 0 {
+        return Err(Error::Other(String::from("--cell and --pixels have to be at least 1")));
+    }
+    if db.find_file_by_path("World/0/Bricks/Grids/1/ChunkIndex.mps")?.is_none() {
+        return Err(Error::NotFound(String::from("/World/0/Bricks/Grids/1")));
+    }
+
+    // bricks per column of cells, since the map is seen from above
+    let mut counts: HashMap<[i32; 2], usize> = HashMap::new();
+    let size = match cell {
+        Some(cell) => {
+            for saved in grid_bricks(db, 1, None)? {
+                let position = saved.brick.position;
+                let key = [position.x.div_euclid(cell as i32), position.y.div_euclid(cell as i32)];
+                *counts.entry(key).or_default() += 1;
+            }
+            cell as i32
+        }
+        None => {
+            let chunks = db.brick_chunk_index(1)?;
+            for chunk in &chunks {
+                *counts.entry([chunk.x as i32, chunk.y as i32]).or_default() += chunk.num_bricks as usize;
+            }
+            chunks.first().map_or(2048, |chunk| chunk.chunk_size)
+        }
+    };
+    counts.retain(|_, count| *count > 0);
+    let Some(&busiest) = counts.values().max() else {
+        return Err(Error::Other(String::from("there are no bricks to draw")));
+    };
+
+    let low = counts.keys().fold([i32::MAX; 2], |low, key| [low[0].min(key[0]), low[1].min(key[1])]);
+    let high = counts.keys().fold([i32::MIN; 2], |high, key| [high[0].max(key[0]), high[1].max(key[1])]);
+    let pixels = pixels as usize;
+    let (columns, rows) = ((high[0] - low[0] + 1) as usize, (high[1] - low[1] + 1) as usize);
+    let (width, height) = (columns * pixels, rows * pixels);
+    if width > MAX_SIDE || height > MAX_SIDE {
+        return Err(Error::Other(format!("the heatmap would be {width}x{height} pixels, try fewer --pixels")));
+    }
+
+    let mut image = Image::new(width, height);
+    for (key, count) in &counts {
+        let value = (*count as f32).ln_1p() / (busiest as f32).ln_1p();
+        let (column, row) = ((key[0] - low[0]) as usize, (key[1] - low[1]) as usize);
+        for y in row * pixels..(row + 1) * pixels {
+            for x in column * pixels..(column + 1) * pixels {
+                image.pixels[y * width + x] = gradient(value);
+            }
+        }
+    }
+
+    let png = image.png()?;
+    fs::write(out, &png)?;
+    let mut cells: Vec<_> = counts.into_iter().collect();
+    cells.sort_by_key(|(key, count)| (Reverse(*count), *key));
+    let (key, count) = cells[0];
+    let text = format!(
+        "drew {} cells on a {width}x{height} heatmap in {}, the busiest is at {},{} with {count} bricks",
+        cells.len(),
+        out.display(),
+        key[0] * size,
+        key[1] * size,
+    );
+    let json = json!({
+        "out": out,
+        "cell": size,
+        "width": width,
+        "height": height,
+        "cells": cells
+            .iter()
+            .map(|(key, count)| json!({ "x": key[0] * size, "y": key[1] * size, "bricks": count }))
+            .collect::<Vec<_>>(),
+    });
+    Ok(Output::new(text, json))
+}
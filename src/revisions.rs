@@ -0,0 +1,161 @@
+use std::path::Path;
+use brdb::{BrFsReader, Brdb, fs::BrFs, pending::BrPendingFs};
+use rusqlite::OptionalExtension;
+use serde_json::{Value, json};
+
+use crate::{error::Error, format_time, live, output::Output};
+
+/// a saved revision of the world
+pub struct Revision {
+    pub id: i64,
+    pub description: String,
+    pub created_at: i64,
+}
+
+/// the revision that was written at a certain time, which is the latest one at or before it
+pub fn revision_at(db: &Brdb, created_at: i64) -> Result<Option<Revision>, Error> {
+    let revision = db
+        .conn
+        .query_row(
+            "SELECT revision_id, description, created_at FROM revisions
+            WHERE created_at <= ?1 ORDER BY created_at DESC, revision_id DESC LIMIT 1",
+            [created_at],
+            |row| {
+                Ok(Revision {
+                    id: row.get(0)?,
+                    description: row.get(1)?,
+                    created_at: row.get(2)?,
+                })
+            },
+        )
+        .optional()?;
+    Ok(revision)
+}
+
+/// every revision in the world, oldest first
+pub fn all_revisions(db: &Brdb) -> Result<Vec<Revision>, Error> {
+    let mut statement = db
+        .conn
+        .prepare("SELECT revision_id, description, created_at FROM revisions ORDER BY revision_id")?;
+    let revisions = statement
+        .query_map([], |row| {
+            Ok(Revision {
+                id: row.get(0)?,
+                description: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(revisions)
+}
+
+/// list every revision with when it was saved and what it was for
+pub fn revisions(db: &Brdb) -> Result<Output, Error> {
+    let revisions = all_revisions(db)?;
+    let width = revisions.iter().map(|revision| revision.id.to_string().len()).max().unwrap_or(0).max(2);
+
+    let mut lines = vec![format!("{:>width$}  {:<19}  DESCRIPTION", "ID", "DATE (UTC)")];
+    let mut json = Vec::new();
+    for revision in revisions {
+        lines.push(format!(
+            "{:>width$}  {}  {}",
+            revision.id,
+            format_time(revision.created_at),
+            revision.description
+        ));
+        json.push(json!({
+            "id": revision.id,
+            "description": revision.description,
+            "created_at": revision.created_at,
+        }));
+    }
+    Ok(Output::new(lines.join("\n"), Value::Array(json)))
+}
+
+/// open a world as it was at an older revision.
+/// the file and folder tables are shadowed by temporary ones that only have what existed back then,
+/// so everything reading the world sees the old state without the file being touched.
+/// brdb stamps new folders with the time they were made rather than the time of their revision,
+/// so folders holding files from the revision are kept even if they look a bit newer
+pub fn open_at_revision(path: &Path, id: i64) -> Result<Brdb, Error> {
+    let db = Brdb::open_readonly(path).map_err(|error| Error::BadWorld(path.to_path_buf(), error.to_string()))?;
+    at_revision(db, id)
+}
+
+/// make an already open world look like it was at an older revision, see `open_at_revision`
+pub fn at_revision(db: Brdb, id: i64) -> Result<Brdb, Error> {
+    let created_at: i64 = db
+        .conn
+        .query_row("SELECT created_at FROM revisions WHERE revision_id = ?1", [id], |row| row.get(0))
+        .optional()?
+        .ok_or_else(|| Error::Other(format!("there is no revision {id}")))?;
+    // files only say when they were saved, to the second, and not which revision they belong to. so a
+    // later revision saved in the same second cant be told apart from this one, and would get mixed in
+    let later: Option<i64> = db
+        .conn
+        .query_row(
+            "SELECT revision_id FROM revisions WHERE revision_id > ?1 AND created_at = ?2 ORDER BY revision_id DESC LIMIT 1",
+            [id, created_at],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(later) = later {
+        return Err(Error::Other(format!(
+            "revision {id} was saved in the same second as revision {later}, and the world doesnt record which files belong to which of them"
+        )));
+    }
+
+    db.conn.execute_batch(&format!(
+        "CREATE TEMP TABLE files AS
+            SELECT file_id, parent_id, name, content_id, created_at,
+                CASE WHEN deleted_at > {created_at} THEN NULL ELSE deleted_at END AS deleted_at
+            FROM main.files WHERE created_at <= {created_at};
+        CREATE TEMP TABLE folders AS
+            WITH RECURSIVE ancestors(folder_id) AS (
+                SELECT parent_id FROM temp.files WHERE deleted_at IS NULL
+                UNION SELECT folders.parent_id FROM main.folders JOIN ancestors USING (folder_id)
+            )
+            SELECT folder_id, parent_id, name, created_at,
+                CASE WHEN deleted_at > {created_at} THEN NULL ELSE deleted_at END AS deleted_at
+            FROM main.folders
+            WHERE created_at <= {created_at} OR folder_id IN (SELECT folder_id FROM ancestors);
+        CREATE TEMP TABLE revisions AS
+            SELECT * FROM main.revisions WHERE revision_id <= {id};"
+    ))?;
+    Ok(db)
+}
+
+/// a pending filesystem that turns `current` into `target`, only reading the files that changed
+fn pending_towards(target: &BrFs, current: Option<&BrFs>, reader: &impl BrFsReader) -> Result<BrPendingFs, Error> {
+    let children = |map: &indexmap::IndexMap<String, BrFs>| {
+        map.iter()
+            .map(|(name, child)| {
+                let current = match current {
+                    Some(BrFs::Root(current) | BrFs::Folder(_, current)) => current.get(name),
+                    _ => None,
+                };
+                Ok((name.clone(), pending_towards(child, current, reader)?))
+            })
+            .collect::<Result<Vec<_>, Error>>()
+    };
+
+    Ok(match target {
+        BrFs::Root(map) => BrPendingFs::Root(children(map)?),
+        BrFs::Folder(_, map) => BrPendingFs::Folder(Some(children(map)?)),
+        BrFs::File(file) => match current {
+            Some(BrFs::File(current)) if current.content_id == file.content_id => BrPendingFs::File(None),
+            _ => BrPendingFs::File(Some(file.read(reader)?)),
+        },
+    })
+}
+
+/// save a new revision that looks exactly like an older one, keeping everything in between
+pub fn rollback(path: &Path, id: i64) -> Result<Output, Error> {
+    live::check_writable(path)?;
+    let old = open_at_revision(path, id)?;
+    let db = Brdb::open(path).map_err(|error| Error::BadWorld(path.to_path_buf(), error.to_string()))?;
+
+    let pending = pending_towards(&old.get_fs()?, Some(&db.get_fs()?), &old)?;
+    db.write_pending(format!("Rollback to revision {id}"), pending)?;
+    Ok(Output::new(format!("rolled back to revision {id}"), json!({ "rolled_back_to": id })))
+}
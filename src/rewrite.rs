@@ -0,0 +1,442 @@
+use std::collections::{HashMap, HashSet};
+use brdb::{
+    BrFsReader, BrReader, Brdb, Brick, ChunkIndex, LocalWirePortSource, OwnerTableSoA, RemoteWirePortSource,
+    UnsavedGrid, WireChunkSoA, WirePortTarget, pending::BrPendingFs, schema::BrdbValue,
+};
+use serde_json::{Value, json};
+
+use crate::{
+    bricks::{SavedBrick, grid_bricks, grid_ids},
+    error::Error,
+    merge::patch_main_grid,
+    mps,
+    owners::owner_table,
+    write::{file_patch, remove_entry},
+};
+
+/// what to do with a brick on the main grid when it's rewritten
+pub enum Edit {
+    Keep,
+    Remove,
+    /// swap the brick for another one, which can be somewhere else. its components and wires go along with it
+    Replace(Brick),
+}
+
+/// how much a rewrite changed
+#[derive(Default)]
+pub struct Rewritten {
+    pub removed: usize,
+    pub replaced: usize,
+    pub components: usize,
+    pub wires: usize,
+}
+
+/// where a brick ended up after the rewrite, if it's still there, and who owned it before and after
+#[derive(Clone, Copy)]
+struct Slot {
+    to: Option<(ChunkIndex, u32)>,
+    old_owner: usize,
+    new_owner: usize,
+}
+
+/// the components in one chunk, before theyre sorted by type and saved
+#[derive(Default)]
+pub struct Components {
+    /// type index, brick index and the data, for types that have any
+    pub entries: Vec<(u32, u32, Option<Value>)>,
+    /// brick index, then the entity reference, offset and rotation of each joint
+    pub joints: Vec<(u32, [Value; 3])>,
+    /// brick index and the grid reference of each microchip
+    pub microchips: Vec<(u32, Value)>,
+}
+
+fn decrement(counts: &mut [u32], owner: usize) {
+    if let Some(count) = counts.get_mut(owner) {
+        *count = count.saturating_sub(1);
+    }
+}
+
+/// move a count from one owner to another when a brick changed hands. counts the world didnt save arent made up
+fn transfer(counts: &mut [u32], slot: Slot) {
+    if slot.old_owner != slot.new_owner && counts.get(slot.old_owner).is_some_and(|count| *count > 0) {
+        decrement(counts, slot.old_owner);
+        if let Some(count) = counts.get_mut(slot.new_owner) {
+            *count += 1;
+        }
+    }
+}
+
+/// a list in a decoded file, or an empty one if it isnt there
+pub fn array(value: &Value, prop: &str) -> Vec<Value> {
+    value[prop].as_array().cloned().unwrap_or_default()
+}
+
+/// a number in a decoded file, or 0 if it isnt one
+pub fn number(value: &Value) -> u32 {
+    value.as_u64().unwrap_or(0) as u32
+}
+
+fn components_path(chunk: ChunkIndex) -> String {
+    format!("World/0/Bricks/Grids/1/Components/{chunk}.mps")
+}
+
+/// every component, joint and microchip in a components file, on the brick index theyre on in that chunk
+pub fn read_components(db: &BrReader<Brdb>, path: &str) -> Result<Components, Error> {
+    let global_data = db.global_data()?;
+    let file = mps::decode(db, path)?;
+    let soa = &file["soa"];
+
+    // the counters say how many components of each type come in a row, and only types with data have an instance
+    let mut types = Vec::new();
+    for counter in array(soa, "ComponentTypeCounters") {
+        types.extend((0..number(&counter["NumInstances"])).map(|_| number(&counter["TypeIndex"])));
+    }
+    let mut instances = array(&file, "instances").into_iter();
+    let mut components = Components::default();
+    for (ty, brick) in types.into_iter().zip(array(soa, "ComponentBrickIndices")) {
+        let has_data = global_data.component_data_struct_names.get(ty as usize).is_some_and(|name| name != "None");
+        let instance = if has_data { instances.next() } else { None };
+        components.entries.push((ty, number(&brick), instance));
+    }
+
+    let joints = array(soa, "JointBrickIndices").into_iter().zip(array(soa, "JointEntityReferences"));
+    let joints = joints.zip(array(soa, "JointInitialRelativeOffsets")).zip(array(soa, "JointInitialRelativeRotations"));
+    for (((brick, entity), offset), rotation) in joints {
+        components.joints.push((number(&brick), [entity, offset, rotation]));
+    }
+    for (brick, grid) in array(soa, "MicrochipBrickIndices").into_iter().zip(array(soa, "MicrochipBrickGridReferences")) {
+        components.microchips.push((number(&brick), grid));
+    }
+    Ok(components)
+}
+
+/// split the components in one chunk's file up by the chunk their brick ends up in. returns how many were dropped
+fn move_components(
+    db: &BrReader<Brdb>,
+    chunk: ChunkIndex,
+    slots: &[Slot],
+    owners: &mut OwnerTableSoA,
+    moved: &mut HashMap<ChunkIndex, Components>,
+) -> Result<usize, Error> {
+    let components = read_components(db, &components_path(chunk))?;
+    let slot = |brick: u32| slots.get(brick as usize).copied();
+
+    let mut dropped = 0;
+    for (ty, brick, instance) in components.entries {
+        match slot(brick) {
+            Some(slot @ Slot { to: Some((chunk, index)), .. }) => {
+                transfer(&mut owners.component_counts, slot);
+                moved.entry(chunk).or_default().entries.push((ty, index, instance));
+            }
+            other => {
+                if let Some(slot) = other {
+                    decrement(&mut owners.component_counts, slot.old_owner);
+                }
+                dropped += 1;
+            }
+        }
+    }
+    for (brick, joint) in components.joints {
+        if let Some(Slot { to: Some((chunk, index)), .. }) = slot(brick) {
+            moved.entry(chunk).or_default().joints.push((index, joint));
+        }
+    }
+    for (brick, grid) in components.microchips {
+        if let Some(Slot { to: Some((chunk, index)), .. }) = slot(brick) {
+            moved.entry(chunk).or_default().microchips.push((index, grid));
+        }
+    }
+    Ok(dropped)
+}
+
+/// the json of a components file holding these components. the game wants all the components of a type in one
+/// run, so theyre sorted by type and then by brick
+pub fn components_file(mut components: Components) -> Value {
+    components.entries.sort_by_key(|(ty, brick, _)| (*ty, *brick));
+    let mut counters: Vec<Value> = Vec::new();
+    for (ty, _, _) in &components.entries {
+        match counters.last_mut() {
+            Some(last) if number(&last["TypeIndex"]) == *ty => last["NumInstances"] = json!(number(&last["NumInstances"]) + 1),
+            _ => counters.push(json!({ "TypeIndex": ty, "NumInstances": 1 })),
+        }
+    }
+    let (joints, microchips) = (&components.joints, &components.microchips);
+    json!({
+        "soa": {
+            "ComponentTypeCounters": counters,
+            "ComponentBrickIndices": components.entries.iter().map(|(_, brick, _)| brick).collect::<Vec<_>>(),
+            "JointBrickIndices": joints.iter().map(|(brick, _)| brick).collect::<Vec<_>>(),
+            "JointEntityReferences": joints.iter().map(|(_, joint)| &joint[0]).collect::<Vec<_>>(),
+            "JointInitialRelativeOffsets": joints.iter().map(|(_, joint)| &joint[1]).collect::<Vec<_>>(),
+            "JointInitialRelativeRotations": joints.iter().map(|(_, joint)| &joint[2]).collect::<Vec<_>>(),
+            "MicrochipBrickIndices": microchips.iter().map(|(brick, _)| brick).collect::<Vec<_>>(),
+            "MicrochipBrickGridReferences": microchips.iter().map(|(_, grid)| grid).collect::<Vec<_>>(),
+        },
+        "instances": components.entries.iter().filter_map(|(_, _, instance)| instance.as_ref()).collect::<Vec<_>>(),
+    })
+}
+
+/// delete a file from `pending` if the world has it
+fn remove_if_there(db: &BrReader<Brdb>, pending: &mut BrPendingFs, path: &str) -> Result<(), Error> {
+    match db.find_file_by_path(path)? {
+        Some(_) => remove_entry(pending, path),
+        None => Ok(()),
+    }
+}
+
+/// rebuild the main grid with every brick passed through `edit`, and patch the result into `pending`.
+/// bricks go in whichever chunk their position is in now, components and wires follow their bricks
+/// or go with them when theyre removed, and the owner table counts are kept up to date
+pub fn rewrite_main_grid(
+    db: &BrReader<Brdb>,
+    pending: &mut BrPendingFs,
+    edit: impl FnMut(&SavedBrick) -> Edit,
+) -> Result<Rewritten, Error> {
+    rewrite(db, pending, edit, false)
+}
+
+/// rebuild the main grid as it is, going through every chunk's components too, so components and wires
+/// on bricks that arent there anymore are dropped along with chunks that dont have any bricks
+pub fn prune_main_grid(db: &BrReader<Brdb>, pending: &mut BrPendingFs) -> Result<Rewritten, Error> {
+    rewrite(db, pending, |_| Edit::Keep, true)
+}
+
+fn rewrite(
+    db: &BrReader<Brdb>,
+    pending: &mut BrPendingFs,
+    mut edit: impl FnMut(&SavedBrick) -> Edit,
+    every_chunk: bool,
+) -> Result<Rewritten, Error> {
+    let base_chunks = db.brick_chunk_index(1)?;
+    let mut global_data = (*db.global_data()?).clone();
+    let mut owners = owner_table(db)?;
+    let mut rewritten = Rewritten::default();
+
+    let mut grid = UnsavedGrid::default();
+    let mut slots: HashMap<ChunkIndex, Vec<Slot>> = HashMap::new();
+    for saved in grid_bricks(db, 1, None)? {
+        let old_owner = saved.brick.owner_index.unwrap_or(0);
+        let brick = match edit(&saved) {
+            Edit::Keep => saved.brick,
+            Edit::Remove => {
+                decrement(&mut owners.brick_counts, old_owner);
+                slots.entry(saved.chunk).or_default().push(Slot { to: None, old_owner, new_owner: old_owner });
+                rewritten.removed += 1;
+                continue;
+            }
+            Edit::Replace(brick) => {
+                rewritten.replaced += 1;
+                brick
+            }
+        };
+        global_data.add_brick_meta(&brick);
+        let (chunk, index) = grid.add_brick(&global_data, &brick)?;
+        let slot = Slot { to: Some((chunk, index as u32)), old_owner, new_owner: brick.owner_index.unwrap_or(0) };
+        transfer(&mut owners.brick_counts, slot);
+        slots.entry(saved.chunk).or_default().push(slot);
+    }
+    let find_slot = |chunk: &ChunkIndex, index: u32| slots.get(chunk).and_then(|slots| slots.get(index as usize)).copied();
+
+    // chunks where a brick went away, moved or changed hands, along with the chunks those bricks went to,
+    // get their components sorted out again. the others keep the file they had
+    let mut touched = HashSet::new();
+    if every_chunk {
+        touched.extend(base_chunks.iter().map(|base| base.index));
+    }
+    for (chunk, chunk_slots) in &slots {
+        for (index, slot) in chunk_slots.iter().enumerate() {
+            if slot.to != Some((*chunk, index as u32)) || slot.old_owner != slot.new_owner {
+                touched.insert(*chunk);
+                touched.extend(slot.to.map(|(to, _)| to));
+            }
+        }
+    }
+    let mut components: HashMap<ChunkIndex, Components> = HashMap::new();
+    for base in base_chunks.iter().filter(|base| base.num_components > 0 && touched.contains(&base.index)) {
+        let chunk_slots = slots.get(&base.index).map(Vec::as_slice).unwrap_or_default();
+        rewritten.components += move_components(db, base.index, chunk_slots, &mut owners, &mut components)?;
+    }
+    let mut num_components = HashMap::new();
+    for chunk in &touched {
+        let moved = components.remove(chunk).unwrap_or_default();
+        num_components.insert(*chunk, moved.entries.len() as u32);
+        match moved.entries.is_empty() {
+            true => remove_if_there(db, pending, &components_path(*chunk))?,
+            false => pending.patch(file_patch(&components_path(*chunk), mps::encode(db, &components_path(*chunk), &components_file(moved))?))?,
+        }
+    }
+
+    // wires are saved in the chunk of the brick theyre going to. any wire that lost an end is dropped,
+    // and the rest are sorted into the chunks their targets are in now
+    let mut num_wires = HashMap::new();
+    for grid_id in grid_ids(db)? {
+        let mut wires: HashMap<ChunkIndex, WireChunkSoA> = HashMap::new();
+        let mut changed = HashSet::new();
+        for base in db.brick_chunk_index(grid_id)? {
+            if base.num_wires == 0 {
+                continue;
+            }
+            let soa = WireChunkSoA::try_from(&BrdbValue::Struct(Box::new(db.wire_chunk_soa(grid_id, base.index)?)))?;
+            // local sources are remote ones that happen to be in the same chunk
+            let sources = soa.local_wire_sources.into_iter().map(|source| RemoteWirePortSource {
+                grid_persistent_index: grid_id as u32,
+                chunk_index: base.index,
+                brick_index_in_chunk: source.brick_index_in_chunk,
+                component_type_index: source.component_type_index,
+                port_index: source.port_index,
+            });
+            let sources = sources.chain(soa.remote_wire_sources);
+            let targets = soa.local_wire_targets.into_iter().chain(soa.remote_wire_targets);
+            wires.entry(base.index).or_default();
+            for (source, target) in sources.zip(targets) {
+                // only bricks on the main grid move, so the ends on other grids stay where they are
+                let end = |grid: u32, chunk: ChunkIndex, index: u32| match grid {
+                    1 => find_slot(&chunk, index).map(|slot| (slot.to, Some(slot))),
+                    _ => Some((Some((chunk, index)), None)),
+                };
+                let from = end(source.grid_persistent_index, source.chunk_index, source.brick_index_in_chunk);
+                let to = end(grid_id as u32, base.index, target.brick_index_in_chunk);
+                let target_slot = to.and_then(|(_, slot)| slot);
+                let (Some((Some(from), _)), Some((Some(to), _))) = (from, to) else {
+                    if let Some(slot) = target_slot {
+                        decrement(&mut owners.wire_counts, slot.old_owner);
+                    }
+                    changed.insert(base.index);
+                    rewritten.wires += 1;
+                    continue;
+                };
+                if let Some(slot) = target_slot {
+                    transfer(&mut owners.wire_counts, slot);
+                }
+                if from != (source.chunk_index, source.brick_index_in_chunk) || to != (base.index, target.brick_index_in_chunk) {
+                    changed.insert(base.index);
+                    changed.insert(to.0);
+                }
+
+                let target = WirePortTarget { brick_index_in_chunk: to.1, ..target };
+                let chunk = wires.entry(to.0).or_default();
+                match source.grid_persistent_index as usize == grid_id && from.0 == to.0 {
+                    true => chunk.add_local_wire(
+                        LocalWirePortSource {
+                            brick_index_in_chunk: from.1,
+                            component_type_index: source.component_type_index,
+                            port_index: source.port_index,
+                        },
+                        target,
+                    ),
+                    false => chunk.add_remote_wire(
+                        RemoteWirePortSource { chunk_index: from.0, brick_index_in_chunk: from.1, ..source },
+                        target,
+                    ),
+                }
+            }
+        }
+
+        for chunk in changed {
+            let path = format!("World/0/Bricks/Grids/{grid_id}/Wires/{chunk}.mps");
+            let soa = wires.remove(&chunk).unwrap_or_default();
+            let count = soa.local_wire_sources.len() + soa.remote_wire_sources.len();
+            match count {
+                0 => remove_if_there(db, pending, &path)?,
+                _ => pending.patch(file_patch(&path, soa.to_bytes(&*db.wires_schema()?)?))?,
+            }
+            num_wires.insert((grid_id, chunk), count as u32);
+        }
+    }
+
+    // the other grids can only have lost wires, so their chunk index just needs the new counts
+    for grid_id in grid_ids(db)?.into_iter().filter(|grid_id| *grid_id != 1) {
+        if !num_wires.keys().any(|(grid, _)| *grid == grid_id) {
+            continue;
+        }
+        let path = format!("World/0/Bricks/Grids/{grid_id}/ChunkIndex.mps");
+        let mut index = mps::decode(db, &path)?;
+        for (i, chunk) in array(&index, "Chunk3DIndices").iter().enumerate() {
+            let [x, y, z] = ["X", "Y", "Z"].map(|axis| chunk[axis].as_i64().unwrap_or(0) as i16);
+            if let Some(count) = num_wires.get(&(grid_id, ChunkIndex { x, y, z })) {
+                index["NumWires"][i] = json!(count);
+            }
+        }
+        pending.patch(file_patch(&path, mps::encode(db, &path, &index)?))?;
+    }
+
+    let index = &mut grid.chunk_index;
+    for (i, chunk) in index.chunk_3d_indices.iter().enumerate() {
+        let base = base_chunks.iter().find(|base| base.index == *chunk);
+        // the game saves main grid chunks without an offset
+        index.chunk_offsets[i] = base.map_or(brdb::IntVector { x: 0, y: 0, z: 0 }, |base| base.chunk_offset);
+        if let Some(base) = base {
+            index.chunk_sizes[i] = base.chunk_size;
+        }
+        index.num_components[i] = num_components.get(chunk).copied().or(base.map(|base| base.num_components)).unwrap_or(0);
+        index.num_wires[i] = num_wires.get(&(1, *chunk)).copied().or(base.map(|base| base.num_wires)).unwrap_or(0);
+    }
+
+    // chunks without any bricks left are dropped, along with whatever was on them
+    for base in &base_chunks {
+        if grid.chunk_index.chunk_3d_indices.contains(&base.index) {
+            continue;
+        }
+        for folder in ["Chunks", "Components", "Wires"] {
+            remove_if_there(db, pending, &format!("World/0/Bricks/Grids/1/{folder}/{}.mps", base.index))?;
+        }
+    }
+
+    patch_main_grid(db, pending, grid, &global_data, &owners)?;
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use brdb::{BrFsReader, Position};
+
+    use super::*;
+    use crate::testing::world;
+
+    fn rewritten(db: &BrReader<Brdb>, edit: impl FnMut(&SavedBrick) -> Edit) -> Rewritten {
+        let mut pending = db.get_fs().unwrap().to_pending_patch().unwrap();
+        let rewritten = rewrite_main_grid(db, &mut pending, edit).unwrap();
+        db.write_pending("rewrite", pending).unwrap();
+        rewritten
+    }
+
+    #[test]
+    fn a_brick_moved_to_another_chunk_takes_its_component_along_and_its_wire_turns_remote() {
+        let db = world();
+        // the gate is the second brick, and the wire from it is saved with the rerouter it goes to
+        let moved = rewritten(&db, |saved| match saved.index {
+            1 => Edit::Replace(Brick { position: Position::new(5000, 0, 0), ..saved.brick.clone() }),
+            _ => Edit::Keep,
+        });
+        assert_eq!(moved.replaced, 1);
+
+        let chunks = db.brick_chunk_index(1).unwrap();
+        let counts: Vec<_> = chunks.iter().map(|chunk| (chunk.index.x, chunk.num_bricks, chunk.num_components, chunk.num_wires)).collect();
+        assert_eq!(counts, [(0, 1, 1, 1), (2, 1, 1, 0)]);
+        let soa = db.wire_chunk_soa(1, ChunkIndex { x: 0, y: 0, z: 0 }).unwrap();
+        let wires = WireChunkSoA::try_from(&BrdbValue::Struct(Box::new(soa))).unwrap();
+        assert!(wires.local_wire_sources.is_empty());
+        let source = &wires.remote_wire_sources[0];
+        assert_eq!((source.chunk_index, source.brick_index_in_chunk), (ChunkIndex { x: 2, y: 0, z: 0 }, 0));
+        assert_eq!(wires.remote_wire_targets[0].brick_index_in_chunk, 0);
+    }
+
+    #[test]
+    fn a_brick_that_changes_hands_moves_its_counts_to_the_new_owner() {
+        let db = world();
+        rewritten(&db, |saved| Edit::Replace(Brick { owner_index: Some(0), ..saved.brick.clone() }));
+        let owners = owner_table(&db).unwrap();
+        assert_eq!((owners.brick_counts, owners.component_counts), (vec![3, 0], vec![2, 0]));
+    }
+
+    #[test]
+    fn pruning_a_world_with_nothing_dangling_changes_nothing() {
+        let db = world();
+        let mut pending = db.get_fs().unwrap().to_pending_patch().unwrap();
+        let pruned = prune_main_grid(&db, &mut pending).unwrap();
+        db.write_pending("prune", pending).unwrap();
+        assert_eq!((pruned.removed, pruned.components, pruned.wires), (0, 0, 0));
+        let chunks = db.brick_chunk_index(1).unwrap();
+        assert_eq!((chunks[0].num_bricks, chunks[0].num_components, chunks[0].num_wires), (2, 2, 1));
+    }
+}
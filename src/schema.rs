@@ -0,0 +1,304 @@
+use std::sync::Arc;
+use brdb::{
+    BrFsReader, BrReader, Brdb,
+    schema::{BrdbInterned, BrdbSchema, BrdbSchemaStructProperty, ReadBrdbSchema},
+};
+use serde_json::{Value, json};
+
+use crate::{error::Error, files_in, output::{Output, table}};
+
+/// the path of every .schema file in the world
+pub fn schema_files<T: BrFsReader>(db: &BrReader<T>) -> Result<Vec<String>, Error> {
+    let fs = db.get_fs()?;
+    Ok(files_in(&fs, "").into_iter().map(|(path, _)| path).filter(|path| path.ends_with(".schema")).collect())
+}
+
+/// read a .schema file in the world
+pub fn load<T: BrFsReader>(db: &BrReader<T>, path: &str) -> Result<Arc<BrdbSchema>, Error> {
+    let data = db.read_file(path)?;
+    // global data is what the other schemas need, so its own schema is read without it
+    Ok(match path.ends_with("GlobalData.schema") {
+        true => data.as_slice().read_brdb_schema()?,
+        false => data.as_slice().read_brdb_schema_with_data(db.global_data()?)?,
+    })
+}
+
+fn name(schema: &BrdbSchema, interned: BrdbInterned) -> String {
+    schema.intern.lookup(interned).unwrap_or_else(|| String::from("?"))
+}
+
+/// a schema as json: its enums with their values, its variants with the types they can hold,
+/// and its structs with the name and type of every field, all in the order the schema has them
+pub fn schema_json(schema: &BrdbSchema) -> Value {
+    let enums: Vec<Value> = schema
+        .enums
+        .iter()
+        .map(|(enum_name, values)| {
+            let values: serde_json::Map<String, Value> = values.iter().map(|(key, value)| (name(schema, *key), json!(value))).collect();
+            json!({ "name": name(schema, *enum_name), "values": values })
+        })
+        .collect();
+    let variants: Vec<Value> = schema
+        .variants
+        .iter()
+        .map(|(variant, members)| {
+            let members: Vec<String> = members.iter().map(|member| name(schema, *member)).collect();
+            json!({ "name": name(schema, *variant), "members": members })
+        })
+        .collect();
+    let structs: Vec<Value> = schema
+        .structs
+        .iter()
+        .map(|(struct_name, fields)| {
+            let fields: Vec<Value> = fields
+                .iter()
+                .map(|(field, property)| {
+                    let field = name(schema, *field);
+                    match property {
+                        BrdbSchemaStructProperty::Type(ty) => json!({ "name": field, "kind": "value", "type": name(schema, *ty) }),
+                        BrdbSchemaStructProperty::Array(ty) => json!({ "name": field, "kind": "array", "type": name(schema, *ty) }),
+                        BrdbSchemaStructProperty::FlatArray(ty) => {
+                            json!({ "name": field, "kind": "flat_array", "type": name(schema, *ty) })
+                        }
+                        BrdbSchemaStructProperty::Map(key, ty) => {
+                            json!({ "name": field, "kind": "map", "key": name(schema, *key), "type": name(schema, *ty) })
+                        }
+                    }
+                })
+                .collect();
+            json!({ "name": name(schema, *struct_name), "fields": fields })
+        })
+        .collect();
+    json!({ "enums": enums, "variants": variants, "structs": structs })
+}
+
+/// list every .schema file in the world with how many enums, variants and structs it has
+pub fn list(db: &BrReader<Brdb>) -> Result<Output, Error> {
+    let mut rows = vec![["PATH", "ENUMS", "VARIANTS", "STRUCTS"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for path in schema_files(db)? {
+        let schema = load(db, &path)?;
+        let counts = [schema.enums.len(), schema.variants.len(), schema.structs.len()];
+        rows.push(vec![format!("/{path}"), counts[0].to_string(), counts[1].to_string(), counts[2].to_string()]);
+        json.push(json!({ "path": format!("/{path}"), "enums": counts[0], "variants": counts[1], "structs": counts[2] }));
+    }
+    Ok(Output::new(table(&rows), Value::Array(json)))
+}
+
+/// find a schema by its path or the end of it, with or without .schema, like ChunksShared or Bricks/ChunksShared
+fn find_schema(db: &BrReader<Brdb>, wanted: &str) -> Result<String, Error> {
+    let wanted = wanted.trim_start_matches('/');
+    let paths = schema_files(db)?;
+    let matches: Vec<&String> = paths
+        .iter()
+        .filter(|path| *path == wanted || path.ends_with(&format!("/{wanted}")) || path.ends_with(&format!("/{wanted}.schema")))
+        .collect();
+    match matches.as_slice() {
+        [path] => Ok(path.to_string()),
+        [] => Err(Error::NotFound(format!("a schema named {wanted}"))),
+        _ => Err(Error::Other(format!(
+            "{wanted} could be any of {}, give more of the path",
+            matches.iter().map(|path| format!("/{path}")).collect::<Vec<_>>().join(", ")
+        ))),
+    }
+}
+
+/// show one schema as a list of its enums, variants and structs, each with what's in it
+pub fn show(db: &BrReader<Brdb>, wanted: &str) -> Result<Output, Error> {
+    let path = find_schema(db, wanted)?;
+    let schema = load(db, &path)?;
+
+    let mut sections = vec![format!(
+        "/{path}: {} enums, {} variants, {} structs",
+        schema.enums.len(),
+        schema.variants.len(),
+        schema.structs.len()
+    )];
+    for (enum_name, values) in &schema.enums {
+        let rows: Vec<Vec<String>> = values.iter().map(|(key, value)| vec![format!("  {}", name(&schema, *key)), value.to_string()]).collect();
+        sections.push(format!("enum {} ({} values)\n{}", name(&schema, *enum_name), values.len(), table(&rows)));
+    }
+    for (variant, members) in &schema.variants {
+        let members: Vec<String> = members.iter().map(|member| format!("  {}", name(&schema, *member))).collect();
+        sections.push(format!("variant {} ({} types)\n{}", name(&schema, *variant), members.len(), members.join("\n")));
+    }
+    for (struct_name, fields) in &schema.structs {
+        let rows: Vec<Vec<String>> = fields
+            .iter()
+            .map(|(field, property)| vec![format!("  {}", name(&schema, *field)), property.as_string(&schema)])
+            .collect();
+        sections.push(format!("struct {} ({} fields)\n{}", name(&schema, *struct_name), fields.len(), table(&rows)));
+    }
+
+    let mut json = schema_json(&schema);
+    json["path"] = json!(format!("/{path}"));
+    Ok(Output::new(sections.join("\n\n"), json))
+}
+
+/// an enum, variant or struct in a schema: what it is, its name, and what's in it
+type Part = (&'static str, String, Vec<(String, String)>);
+
+/// the enums, variants and structs of a schema by name, each with its values, member types or fields,
+/// and what each of those is: a number for values, nothing for member types and the type for fields
+fn parts(schema: &BrdbSchema) -> Vec<Part> {
+    let enums = schema.enums.iter().map(|(enum_name, values)| {
+        let values = values.iter().map(|(key, value)| (name(schema, *key), value.to_string())).collect();
+        ("enum", name(schema, *enum_name), values)
+    });
+    let variants = schema.variants.iter().map(|(variant, members)| {
+        let members = members.iter().map(|member| (name(schema, *member), String::new())).collect();
+        ("variant", name(schema, *variant), members)
+    });
+    let structs = schema.structs.iter().map(|(struct_name, fields)| {
+        let fields = fields.iter().map(|(field, property)| (name(schema, *field), property.as_string(schema))).collect();
+        ("struct", name(schema, *struct_name), fields)
+    });
+    enums.chain(variants).chain(structs).collect()
+}
+
+/// something that was added, removed, renamed or changed between two versions of a schema
+struct SchemaChange {
+    change: &'static str,
+    kind: &'static str,
+    name: String,
+    old: Option<String>,
+    new: Option<String>,
+}
+
+impl SchemaChange {
+    fn new(change: &'static str, kind: &'static str, name: String, old: Option<&str>, new: Option<&str>) -> Self {
+        Self { change, kind, name, old: old.map(String::from), new: new.map(String::from) }
+    }
+
+    /// the change as a line like `~ struct BrickChunkSoA.Colors: u8 -> u16`
+    fn line(&self) -> String {
+        let (kind, name) = (self.kind, &self.name);
+        let detail = |value: &Option<String>| value.as_deref().filter(|value| !value.is_empty()).map(|value| format!(": {value}"));
+        match self.change {
+            "added" => format!("+ {kind} {name}{}", detail(&self.new).unwrap_or_default()),
+            "removed" => format!("- {kind} {name}{}", detail(&self.old).unwrap_or_default()),
+            "renamed" => format!("~ {kind} {name} renamed to {}", self.new.as_deref().unwrap_or_default()),
+            _ => format!("~ {kind} {name}: {} -> {}", self.old.as_deref().unwrap_or_default(), self.new.as_deref().unwrap_or_default()),
+        }
+    }
+
+    fn json(&self) -> Value {
+        let mut json = json!({ "change": self.change, "kind": self.kind, "name": self.name });
+        if let Some(old) = &self.old {
+            json["old"] = json!(old);
+        }
+        if let Some(new) = &self.new {
+            json["new"] = json!(new);
+        }
+        json
+    }
+}
+
+/// what changed inside one enum, variant or struct. a field that's gone where a new one with the same type
+/// took its place counts as renamed
+fn diff_members(kind: &'static str, parent: &str, a: &[(String, String)], b: &[(String, String)], changes: &mut Vec<SchemaChange>) {
+    let member_kind = match kind {
+        "enum" => "value",
+        "variant" => "member",
+        _ => "field",
+    };
+    let full = |member: &str| format!("{parent}.{member}");
+    for (i, (member, detail)) in a.iter().enumerate() {
+        match b.iter().find(|(other, _)| other == member) {
+            Some((_, other)) if other != detail => {
+                changes.push(SchemaChange::new("changed", member_kind, full(member), Some(detail), Some(other)));
+            }
+            Some(_) => (),
+            None => match b.get(i) {
+                Some((other, other_detail))
+                    if other_detail == detail && kind == "struct" && !a.iter().any(|(name, _)| name == other) =>
+                {
+                    changes.push(SchemaChange::new("renamed", member_kind, full(member), None, Some(other)));
+                }
+                _ => changes.push(SchemaChange::new("removed", member_kind, full(member), Some(detail), None)),
+            },
+        }
+    }
+    for (i, (member, detail)) in b.iter().enumerate() {
+        let renamed = kind == "struct"
+            && a.get(i).is_some_and(|(old, old_detail)| old_detail == detail && !b.iter().any(|(name, _)| name == old));
+        if !renamed && !a.iter().any(|(other, _)| other == member) {
+            changes.push(SchemaChange::new("added", member_kind, full(member), None, Some(detail)));
+        }
+    }
+}
+
+/// what changed between two versions of a schema. an enum, variant or struct that's gone where a new one
+/// with exactly the same contents showed up counts as renamed
+fn diff_schema(a: &BrdbSchema, b: &BrdbSchema) -> Vec<SchemaChange> {
+    let (a, b) = (parts(a), parts(b));
+    let find = |parts: &[Part], kind: &str, wanted: &str| {
+        parts.iter().position(|(other_kind, name, _)| *other_kind == kind && name == wanted)
+    };
+    let mut changes = Vec::new();
+    let mut renamed_to = Vec::new();
+    for (kind, name, members) in &a {
+        match find(&b, kind, name) {
+            Some(index) => diff_members(kind, name, members, &b[index].2, &mut changes),
+            None => {
+                let renamed = b.iter().position(|(other_kind, other, other_members)| {
+                    other_kind == kind && other_members == members && find(&a, kind, other).is_none() && !renamed_to.contains(other)
+                });
+                match renamed {
+                    Some(index) => {
+                        renamed_to.push(b[index].1.clone());
+                        changes.push(SchemaChange::new("renamed", kind, name.clone(), None, Some(&b[index].1)));
+                    }
+                    None => changes.push(SchemaChange::new("removed", kind, name.clone(), None, None)),
+                }
+            }
+        }
+    }
+    for (kind, name, _) in &b {
+        if find(&a, kind, name).is_none() && !renamed_to.contains(name) {
+            changes.push(SchemaChange::new("added", kind, name.clone(), None, None));
+        }
+    }
+    changes
+}
+
+/// compare the schemas in two worlds file by file, showing schemas that are only in one of them
+/// and what was added, removed, renamed or changed in the ones they both have
+pub fn diff(a: &BrReader<Brdb>, b: &BrReader<Brdb>) -> Result<Output, Error> {
+    let (a_paths, b_paths) = (schema_files(a)?, schema_files(b)?);
+    let mut lines = Vec::new();
+    let mut json = Vec::new();
+    let (mut added, mut removed, mut modified) = (0, 0, 0);
+    for path in &a_paths {
+        if !b_paths.contains(path) {
+            lines.push(format!("D /{path}"));
+            json.push(json!({ "path": format!("/{path}"), "status": "removed" }));
+            removed += 1;
+            continue;
+        }
+        let (old, new) = (load(a, path)?, load(b, path)?);
+        let changes = diff_schema(&old, &new);
+        if changes.is_empty() {
+            continue;
+        }
+        lines.push(format!("M /{path}"));
+        lines.extend(changes.iter().map(|change| format!("  {}", change.line())));
+        json.push(json!({
+            "path": format!("/{path}"),
+            "status": "modified",
+            "changes": changes.iter().map(SchemaChange::json).collect::<Vec<_>>(),
+        }));
+        modified += 1;
+    }
+    for path in b_paths.iter().filter(|path| !a_paths.contains(path)) {
+        lines.push(format!("A /{path}"));
+        json.push(json!({ "path": format!("/{path}"), "status": "added" }));
+        added += 1;
+    }
+
+    lines.push(match added + removed + modified {
+        0 => String::from("the schemas are the same"),
+        _ => format!("{added} added, {removed} removed, {modified} modified"),
+    });
+    Ok(Output::new(lines.join("\n"), Value::Array(json)))
+}
@@ -0,0 +1,191 @@
+use std::{cell::RefCell, fs, io, io::Read, io::Write, iter, path::{Path, PathBuf}};
+use brdb::{BrReader, Brdb};
+use clap::{CommandFactory, Parser};
+use serde_json::json;
+
+use crate::{OpenOptions, cli::{Cli, ColorChoice, OutputFormat, WORLD}, error::Error, execute, open_world, output::Output};
+
+/// the world a running script has open, for as long as no command is borrowing it
+struct Opened {
+    path: PathBuf,
+    options: OpenOptions,
+    db: Option<BrReader<Brdb>>,
+}
+
+thread_local! {
+    static OPENED: RefCell<Option<Opened>> = const { RefCell::new(None) };
+}
+
+/// lend the script's world to a command that wants to open the same file the same way. anything else,
+/// like an older revision or another world, gets opened on its own as usual
+pub fn lend(path: &Path, options: OpenOptions) -> Option<BrReader<Brdb>> {
+    OPENED.with(|opened| {
+        let mut opened = opened.borrow_mut();
+        let opened = opened.as_mut()?;
+        let same = opened.path == path
+            && options.revision.is_none()
+            && options.snapshot == opened.options.snapshot
+            && (opened.options.write || !options.write);
+        match same {
+            true => opened.db.take(),
+            false => None,
+        }
+    })
+}
+
+/// a command is done with the world it borrowed
+pub fn give_back(db: BrReader<Brdb>) {
+    OPENED.with(|opened| {
+        if let Some(opened) = opened.borrow_mut().as_mut() {
+            opened.db = Some(db);
+        }
+    });
+}
+
+/// open the world again after a command changed it, since brdb keeps the schemas and global data
+/// it has read around and they could be out of date now
+fn reopen() -> Result<(), Error> {
+    let Some((path, options)) = OPENED.with(|opened| {
+        let mut opened = opened.borrow_mut();
+        let opened = opened.as_mut()?;
+        opened.db = None;
+        Some((opened.path.clone(), opened.options))
+    }) else {
+        return Ok(());
+    };
+    let mut world = open_world(&path, options)?;
+    if let Some(db) = world.db.take() {
+        give_back(db);
+    }
+    Ok(())
+}
+
+/// turn one line of a script into a command line, putting the world's path in after the command's name
+/// for commands that work on a world
+fn parse_line(line: &str, world: &Path) -> Result<Cli, String> {
+    let mut words = shlex::split(line).ok_or_else(|| String::from("unclosed quote"))?;
+    let mut command = Cli::command();
+    let mut at = 0;
+    for (i, word) in words.iter().enumerate() {
+        if word.starts_with('-') {
+            continue;
+        }
+        match command.find_subcommand(word) {
+            Some(subcommand) => {
+                command = subcommand.clone();
+                at = i + 1;
+            }
+            None => break,
+        }
+    }
+    if at == 0 {
+        return Err(match words.iter().find(|word| !word.starts_with('-')) {
+            Some(name) => format!("{name} isnt a command"),
+            None => String::from("theres no command on this line"),
+        });
+    }
+    let takes_world = command
+        .get_positionals()
+        .next()
+        .and_then(|arg| arg.get_value_names())
+        .is_some_and(|names| names.iter().any(|name| name == WORLD));
+    if takes_world {
+        words.insert(at, world.to_string_lossy().into_owned());
+    }
+
+    Cli::try_parse_from(iter::once(String::from("brdb_cmd")).chain(words)).map_err(|error| {
+        let error = error.render().to_string();
+        error.lines().next().unwrap_or_default().trim_start_matches("error: ").to_string()
+    })
+}
+
+/// run every command in a script on one world, opening it just once. every line is checked before anything
+/// runs, so a typo halfway down doesnt leave the world half changed. blank lines and lines starting with # are
+/// skipped. the script stops at the first command that fails unless `--keep-going` is given, and the exit
+/// code is that of the first one that failed
+pub fn run(cli: &Cli, script: &Path, world: &Path) -> i32 {
+    let fail = |error: Error| {
+        let code = error.exit_code();
+        let _ = match cli.json {
+            true => Output::new("", json!({ "error": error.to_string(), "code": code })).print(true),
+            false => writeln!(io::stderr(), "error: {error}"),
+        };
+        code
+    };
+
+    let text = match script.to_str() {
+        Some("-") => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text).map(|_| text)
+        }
+        _ => fs::read_to_string(script),
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(error) => return fail(Error::Io(error)),
+    };
+
+    let mut lines = Vec::new();
+    for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_line(line, world) {
+            Ok(mut line_cli) => {
+                // options given before --script go for every line
+                line_cli.json = match line_cli.output_format {
+                    Some(format) => matches!(format, OutputFormat::Json),
+                    None => line_cli.json || cli.json,
+                };
+                line_cli.snapshot |= cli.snapshot;
+                line_cli.read_only |= cli.read_only;
+                line_cli.quiet |= cli.quiet;
+                line_cli.no_pager = true;
+                line_cli.revision = line_cli.revision.or(cli.revision);
+                if matches!(line_cli.color, ColorChoice::Auto) {
+                    line_cli.color = cli.color;
+                }
+                lines.push((number, line_cli));
+            }
+            Err(error) => return fail(Error::Other(format!("line {number}: {error}"))),
+        }
+    }
+
+    // the world is only opened for writing if some command in the script needs it to be
+    let writes = lines.iter().any(|(_, line)| line.command.as_ref().is_some_and(|command| command.writes()));
+    let options = OpenOptions {
+        revision: None,
+        snapshot: cli.snapshot,
+        write: writes && !cli.snapshot && !cli.read_only,
+        read_only: cli.read_only,
+    };
+    let db = match open_world(world, options) {
+        Ok(mut opened) => opened.db.take(),
+        Err(error) => return fail(error),
+    };
+    OPENED.with(|opened| *opened.borrow_mut() = Some(Opened { path: world.to_path_buf(), options, db }));
+
+    let mut exit_code = 0;
+    for (number, mut line) in lines {
+        let Some(command) = line.command.take() else {
+            continue;
+        };
+        let writes = command.writes();
+        let code = execute(command, &line);
+        if code == 0 && writes && let Err(error) = reopen() {
+            exit_code = fail(error);
+            break;
+        }
+        if code != 0 {
+            if exit_code == 0 {
+                exit_code = code;
+            }
+            if !cli.keep_going {
+                eprintln!("error: stopped at line {number}");
+                break;
+            }
+        }
+    }
+    OPENED.with(|opened| *opened.borrow_mut() = None);
+    exit_code
+}
@@ -0,0 +1,222 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, PoisonError,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+use brdb::fs::BrFs;
+use serde_json::{Value, json};
+
+use crate::{
+    ListOptions, OpenOptions, ReadOptions, World, bricks, error::Error, info, list_dir, live, lookup, open_world,
+    read_file, revisions, stats, traverse,
+};
+
+/// how long a client can take to send its request before it's dropped
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// the longest request line that's read, which is plenty for any path in a world
+const MAX_LINE: u64 = 8 * 1024;
+/// how much the headers can add up to
+const MAX_HEADERS: u64 = 64 * 1024;
+/// how many requests are answered at once. more than that get a 503 straight away
+const MAX_HANDLERS: usize = 32;
+
+/// undo the %xx escapes in part of a url
+fn unescape(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                out.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// the value of `name` in a query string like grid=1&limit=10, as a number
+fn number(query: &str, name: &str) -> Result<Option<usize>, Error> {
+    let Some((_, value)) = query.split('&').filter_map(|pair| pair.split_once('=')).find(|(key, _)| *key == name) else {
+        return Ok(None);
+    };
+    let value = unescape(value);
+    value.parse().map(Some).map_err(|_| Error::Other(format!("{name} has to be a number, not {value}")))
+}
+
+/// when the world was last saved to. a live world saves into its -wal file first, so that counts too
+fn modified(world: &Path) -> io::Result<SystemTime> {
+    let modified = world.metadata()?.modified()?;
+    match live::sidecar(world, "-wal").metadata().and_then(|wal| wal.modified()) {
+        Ok(wal) => Ok(modified.max(wal)),
+        Err(_) => Ok(modified),
+    }
+}
+
+/// the copy of the world that --snapshot reads, which is only copied again once the world has been saved since
+struct Snapshot {
+    dir: tempfile::TempDir,
+    taken: Mutex<Option<SystemTime>>,
+}
+
+impl Snapshot {
+    fn new() -> io::Result<Self> {
+        Ok(Self { dir: tempfile::tempdir()?, taken: Mutex::new(None) })
+    }
+
+    /// the path of the copy, copying the world again first if it changed since the last copy
+    fn fresh(&self, world: &Path) -> Result<PathBuf, Error> {
+        let copy = self.dir.path().join("world.brdb");
+        let mut taken = self.taken.lock().unwrap_or_else(PoisonError::into_inner);
+        let modified = modified(world)?;
+        if *taken != Some(modified) {
+            log::info!("copying {} for --snapshot", world.display());
+            live::copy_to(world, &copy)?;
+            *taken = Some(modified);
+        }
+        Ok(copy)
+    }
+}
+
+/// open the world for one request, again each time so it always shows what's saved in it now
+fn open_for_request(world: &Path, open: OpenOptions, snapshot: Option<&Snapshot>) -> Result<World, Error> {
+    match snapshot {
+        Some(snapshot) => open_world(&snapshot.fresh(world)?, OpenOptions { snapshot: false, ..open }),
+        None => open_world(world, open),
+    }
+}
+
+/// the json for one request
+fn respond(world: &Path, open: OpenOptions, snapshot: Option<&Snapshot>, path: &str, query: &str) -> Result<Value, Error> {
+    let db = open_for_request(world, open, snapshot)?;
+    let output = match path.trim_end_matches('/') {
+        "/fs" => list_dir(&db, &lookup::tree_to(&db, "", false)?, "", ListOptions::default())?,
+        path if path.starts_with("/fs/") => {
+            let path = unescape(&path["/fs/".len()..]);
+            let fs = lookup::tree_to(&db, &path, false)?;
+            match traverse(&fs, &path)? {
+                BrFs::File(_) => read_file(&db, &path, ReadOptions::default())?,
+                _ => list_dir(&db, &fs, &path, ListOptions::default())?,
+            }
+        }
+        "/bricks" => bricks::bricks(&db, number(query, "grid")?.unwrap_or(1), number(query, "limit")?)?,
+        "/stats" => info::info(&db, world)?,
+        "/stats/owners" => stats::owners(&db)?,
+        "/stats/colors" => stats::colors(&db, false, None)?,
+        "/stats/materials" => stats::materials(&db, false, false)?,
+        "/stats/assets" => stats::assets(&db)?,
+        "/stats/dedup" => stats::dedup(&db)?,
+        "/stats/compression" => stats::compression(&db)?,
+        "/revisions" => revisions::revisions(&db)?,
+        _ => return Err(Error::NotFound(path.to_string())),
+    };
+    Ok(output.json)
+}
+
+/// read one request off a connection and answer it
+fn handle(stream: TcpStream, world: &Path, open: OpenOptions, snapshot: Option<&Snapshot>) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut line = String::new();
+    (&mut reader).take(MAX_LINE).read_line(&mut line)?;
+    // the headers dont matter, but theyre read so the client isnt cut off while still sending them
+    let mut headers = (&mut reader).take(MAX_HEADERS);
+    let mut header = String::new();
+    let mut ended = false;
+    while headers.read_line(&mut header)? > 0 {
+        if header.trim_end().is_empty() {
+            ended = true;
+            break;
+        }
+        header.clear();
+    }
+
+    let mut parts = line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let (status, body) = match method {
+        _ if !line.ends_with('\n') => ("414 URI Too Long", json!({ "error": "the request line is too long" })),
+        _ if !ended => ("431 Request Header Fields Too Large", json!({ "error": "the headers are too long" })),
+        "GET" | "HEAD" => match respond(world, open, snapshot, path, query) {
+            Ok(json) => ("200 OK", json),
+            Err(error @ Error::NotFound(_)) => ("404 Not Found", json!({ "error": error.to_string() })),
+            Err(error @ Error::Other(_)) => ("400 Bad Request", json!({ "error": error.to_string() })),
+            Err(error) => ("500 Internal Server Error", json!({ "error": error.to_string() })),
+        },
+        _ => ("405 Method Not Allowed", json!({ "error": "the world can only be read, with GET" })),
+    };
+    log::info!("{method} {target} {status}");
+    write_response(&stream, status, &body, method != "HEAD")
+}
+
+fn write_response(mut stream: &TcpStream, status: &str, body: &Value, with_body: bool) -> io::Result<()> {
+    let body = serde_json::to_string_pretty(body)?;
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+        body.len()
+    )?;
+    if with_body {
+        stream.write_all(body.as_bytes())?;
+    }
+    stream.flush()
+}
+
+/// counts a request as being answered until it's dropped
+struct Busy(Arc<AtomicUsize>);
+
+impl Drop for Busy {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// answer http requests about a world with json until stopped with ctrl-c. every request gets its
+/// own thread, so a slow one like /stats/colors doesnt hold up the others, up to `MAX_HANDLERS` at once
+pub fn serve(world: &Path, open: OpenOptions, host: &str, port: u16) -> Result<(), Error> {
+    // with --snapshot the world is copied once here, and again only after it's been saved
+    let snapshot = open.snapshot.then(Snapshot::new).transpose()?.map(Arc::new);
+    // make sure the world can be opened before saying it's being served
+    drop(open_for_request(world, open, snapshot.as_deref())?);
+    let listener = TcpListener::bind((host, port))?;
+    eprintln!("serving {} at http://{}, press ctrl-c to stop", world.display(), listener.local_addr()?);
+
+    let busy = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if busy.fetch_add(1, Ordering::Relaxed) >= MAX_HANDLERS {
+            busy.fetch_sub(1, Ordering::Relaxed);
+            log::warn!("too many requests at once, turning one away");
+            let _ = write_response(&stream, "503 Service Unavailable", &json!({ "error": "too many requests at once, try again" }), true);
+            continue;
+        }
+        let guard = Busy(busy.clone());
+        let world: PathBuf = world.to_path_buf();
+        let snapshot = snapshot.clone();
+        thread::spawn(move || {
+            let _guard = guard;
+            if let Err(error) = handle(stream, &world, open, snapshot.as_deref()) {
+                log::debug!("couldnt answer a request: {error}");
+            }
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,276 @@
+use std::{io, io::BufRead, io::IsTerminal, io::Write};
+use brdb::{Brdb, fs::BrFs, BrReader};
+use rustyline::{
+    CompletionType, Config, Context, Editor,
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+};
+
+use crate::{ListOptions, ReadOptions, config, list_dir, read_paths, traverse};
+
+/// the commands the shell knows, for tab completion
+const COMMANDS: [&str; 7] = ["ls", "cd", "pwd", "read", "help", "exit", "quit"];
+
+const HELP: &str = "\
+commands:
+  ls [-lRF] [path] list the files in a folder, -l for details, -R to include subfolders, -F to mark folders
+  cd [path]       change the current folder, to the root without a path or back to the last one with -
+  pwd             show the current folder
+  read <path>     read a file
+  help            show this message
+  exit            leave the shell";
+
+/// resolve a path relative to the current folder into a path from the root
+fn resolve_path(cwd: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+
+    // absolute paths start over from the root
+    if !path.starts_with('/') {
+        parts.extend(cwd.split('/').filter(|part| !part.is_empty()));
+    }
+
+    for part in path.split('/') {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            }
+            part => parts.push(part),
+        }
+    }
+
+    parts.join("/")
+}
+
+/// what a word could be completed to: a command if it's the first one on the line, otherwise a path
+/// from the current folder. folders end in / so the next part can be completed straight after
+fn complete(fs: &BrFs, cwd: &str, before: &str) -> (usize, Vec<String>) {
+    let start = before.rfind(char::is_whitespace).map_or(0, |space| space + 1);
+    let word = &before[start..];
+    if before[..start].trim().is_empty() {
+        let commands = COMMANDS.iter().filter(|command| command.starts_with(word)).map(|command| format!("{command} "));
+        return (start, commands.collect());
+    }
+    if word.starts_with('-') {
+        return (start, Vec::new());
+    }
+
+    // the folder part is kept as it was typed, and only the last part is looked up
+    let dir = &word[..word.rfind('/').map_or(0, |slash| slash + 1)];
+    let prefix = &word[dir.len()..];
+    let folder = match resolve_path(cwd, dir).as_str() {
+        "" => Ok(fs),
+        path => traverse(fs, path),
+    };
+    let mut candidates = Vec::new();
+    if let Ok(BrFs::Root(map) | BrFs::Folder(_, map)) = folder {
+        for (name, child) in map.iter().filter(|(name, _)| name.starts_with(prefix)) {
+            match child {
+                BrFs::File(_) => candidates.push(format!("{dir}{name} ")),
+                _ => candidates.push(format!("{dir}{name}/")),
+            }
+        }
+    }
+    (start, candidates)
+}
+
+/// how many lines of history are kept
+const KEEP: usize = 1000;
+
+/// where the shell's history is kept between sessions, next to the config file
+fn history_file() -> Option<std::path::PathBuf> {
+    Some(config::default_path()?.with_file_name("shell_history"))
+}
+
+/// tab completes commands and paths from the folder the shell is in
+struct Completion<'a> {
+    fs: &'a BrFs,
+    cwd: String,
+}
+
+impl Completer for Completion<'_> {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let (start, candidates) = complete(self.fs, &self.cwd, &line[..pos]);
+        // listed by their last part, since the folders before it are the same for all of them
+        let pairs = candidates
+            .into_iter()
+            .map(|candidate| {
+                let trimmed = candidate.trim_end();
+                let name = trimmed.trim_end_matches('/').rsplit('/').next().unwrap_or_default();
+                let display = match trimmed.ends_with('/') {
+                    true => format!("{name}/"),
+                    false => name.to_string(),
+                };
+                Pair { display, replacement: candidate }
+            })
+            .collect();
+        Ok((start, pairs))
+    }
+}
+
+impl Hinter for Completion<'_> {
+    type Hint = String;
+}
+
+impl Highlighter for Completion<'_> {}
+
+impl Validator for Completion<'_> {}
+
+impl rustyline::Helper for Completion<'_> {}
+
+/// lines typed into the shell. on a terminal they can be edited, with history and tab completion,
+/// otherwise theyre read as they come, like from a file of commands piped in
+enum Input<'a> {
+    Editor(Box<Editor<Completion<'a>, DefaultHistory>>),
+    Lines(io::Lines<io::StdinLock<'static>>),
+}
+
+impl<'a> Input<'a> {
+    fn new(fs: &'a BrFs) -> Self {
+        if io::stdin().is_terminal() && io::stdout().is_terminal() {
+            let config = Config::builder().completion_type(CompletionType::List).max_history_size(KEEP).map(|builder| builder.build());
+            match config.and_then(Editor::with_config) {
+                Ok(mut editor) => {
+                    editor.set_helper(Some(Completion { fs, cwd: String::new() }));
+                    if let Some(file) = history_file() {
+                        // theres no history the first time
+                        let _ = editor.load_history(&file);
+                    }
+                    return Self::Editor(Box::new(editor));
+                }
+                Err(error) => log::debug!("couldnt set up line editing: {error}"),
+            }
+        }
+        Self::Lines(io::stdin().lock().lines())
+    }
+
+    /// the next line, or none when theres no more input
+    fn read(&mut self, cwd: &str) -> Option<String> {
+        let prompt = format!("/{cwd}> ");
+        match self {
+            Self::Editor(editor) => {
+                if let Some(helper) = editor.helper_mut() {
+                    helper.cwd = cwd.to_string();
+                }
+                let line = match editor.readline(&prompt) {
+                    Ok(line) => line,
+                    // ctrl-c throws away the line, like it does in other shells
+                    Err(ReadlineError::Interrupted) => return Some(String::new()),
+                    Err(_) => return None,
+                };
+                if !line.trim().is_empty() && editor.add_history_entry(line.trim()).unwrap_or(false) {
+                    save_history(editor);
+                }
+                Some(line)
+            }
+            Self::Lines(lines) => {
+                print!("{prompt}");
+                let _ = io::stdout().flush();
+                lines.next()?.ok()
+            }
+        }
+    }
+}
+
+/// add the line that was just run to the history file
+fn save_history(editor: &mut Editor<Completion<'_>, DefaultHistory>) {
+    let Some(file) = history_file() else {
+        return;
+    };
+    if let Some(dir) = file.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(error) = editor.append_history(&file) {
+        log::debug!("couldnt save the shell history to {}: {error}", file.display());
+    }
+}
+
+/// interactive shell that keeps the world open between commands
+pub fn run(db: &BrReader<Brdb>, fs: &BrFs) {
+    let mut input = Input::new(fs);
+    let mut cwd = String::new();
+    // the folder before the last cd, for cd -
+    let mut previous: Option<String> = None;
+
+    // until the end of input or a broken stdin, either way we're done then
+    while let Some(line) = input.read(&cwd) {
+        // quotes work like in a script, so paths with spaces in them can be typed
+        let Some(words) = shlex::split(&line) else {
+            println!("error: unclosed quote");
+            continue;
+        };
+        let mut words = words.iter().map(String::as_str);
+        let Some(cmd) = words.next() else {
+            continue;
+        };
+        let mut arg = words.next().unwrap_or("");
+
+        // ls flags, which can be combined like -lR
+        let mut options = ListOptions::default();
+        while cmd == "ls" && arg.starts_with('-') && arg.len() > 1 {
+            options.recursive |= arg.contains('R');
+            options.long |= arg.contains('l');
+            options.classify |= arg.contains('F');
+            arg = words.next().unwrap_or("");
+        }
+
+        let output = match cmd {
+            "ls" => list_dir(db, fs, &resolve_path(&cwd, arg), options)
+                .map(|output| output.text_lossy())
+                .map_err(|error| error.to_string()),
+            "cd" => {
+                // like in bash, cd on its own goes back to the root and cd - to the folder before the last cd
+                let path = match arg {
+                    "" => Ok(String::new()),
+                    "-" => previous.clone().ok_or_else(|| String::from("theres no folder to go back to yet")),
+                    _ => Ok(resolve_path(&cwd, arg)),
+                };
+                path.and_then(|path| match path.as_str() {
+                    "" => Ok(path),
+                    _ => match traverse(fs, &path) {
+                        Ok(BrFs::File(_)) => Err(format!("not a folder: {arg}")),
+                        Ok(_) => Ok(path),
+                        Err(error) => Err(error.to_string()),
+                    },
+                })
+                .map(|path| {
+                    // going back says where it went, since it isnt in what was typed
+                    let shown = match arg {
+                        "-" => format!("/{path}"),
+                        _ => String::new(),
+                    };
+                    previous = Some(std::mem::replace(&mut cwd, path));
+                    shown
+                })
+            }
+            "pwd" => Ok(format!("/{cwd}")),
+            "read" if arg.is_empty() => Err(String::from("usage: read <path>")),
+            "read" => read_paths(db, fs, &resolve_path(&cwd, arg), ReadOptions::default())
+                // files can be binary, so they go straight to stdout instead of through a string
+                .and_then(|output| {
+                    output.print(false)?;
+                    // raw files dont end the line themselves, the prompt should still go on a new one
+                    if output.raw {
+                        println!();
+                    }
+                    Ok(String::new())
+                })
+                .map_err(|error| error.to_string()),
+            "help" => Ok(String::from(HELP)),
+            "exit" | "quit" => break,
+            _ => Err(format!("invalid command: {cmd}. type 'help' for a list of commands")),
+        };
+
+        match output {
+            Ok(value) if value.is_empty() => (),
+            Ok(value) => println!("{}", value.trim_end_matches('\n')),
+            Err(error) => println!("error: {error}"),
+        }
+    }
+}
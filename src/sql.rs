@@ -0,0 +1,49 @@
+use brdb::Brdb;
+use rusqlite::types::ValueRef;
+use serde_json::{Map, Value, json};
+
+use crate::{
+    error::Error,
+    output::{Output, hex, table},
+};
+
+/// run a query against the sqlite tables under the world, like folders, files, blobs and revisions
+pub fn sql(db: &Brdb, query: &str) -> Result<Output, Error> {
+    let mut statement = db.conn.prepare(query).map_err(|error| Error::Other(format!("invalid query: {error}")))?;
+    // the world is already opened read-only, this just gives a clearer error than sqlite does
+    if !statement.readonly() {
+        return Err(Error::Other(String::from("only queries that dont change the world can be run")));
+    }
+
+    let columns: Vec<String> = statement.column_names().into_iter().map(String::from).collect();
+    let mut rows = vec![columns.clone()];
+    let mut json = Vec::new();
+    let mut results = statement.query([])?;
+    while let Some(row) = results.next()? {
+        let mut cells = Vec::new();
+        let mut object = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            // blobs are mostly compressed file contents, so the table only shows how big they are
+            let (cell, value) = match row.get_ref(i)? {
+                ValueRef::Null => (String::from("NULL"), Value::Null),
+                ValueRef::Integer(value) => (value.to_string(), json!(value)),
+                ValueRef::Real(value) => (value.to_string(), json!(value)),
+                ValueRef::Text(text) => {
+                    let text = String::from_utf8_lossy(text).into_owned();
+                    (text.clone(), json!(text))
+                }
+                ValueRef::Blob(bytes) => (format!("<{} bytes>", bytes.len()), json!(hex(bytes))),
+            };
+            cells.push(cell);
+            object.insert(column.clone(), value);
+        }
+        rows.push(cells);
+        json.push(Value::Object(object));
+    }
+
+    let text = match columns.is_empty() {
+        true => String::new(),
+        false => table(&rows),
+    };
+    Ok(Output::new(text, Value::Array(json)))
+}
@@ -0,0 +1,101 @@
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use serde_json::{Value, json};
+
+use crate::{error::Error, format_size, format_time, output::{self, Output}, revisions::revision_at, traverse};
+
+/// describe the revision that wrote something at `created_at`, as text and json
+fn describe_revision(db: &Brdb, created_at: i64) -> Result<(String, Value), Error> {
+    Ok(match revision_at(db, created_at)? {
+        Some(revision) => (
+            format!(
+                "{} \"{}\" ({})",
+                revision.id,
+                revision.description,
+                format_time(revision.created_at)
+            ),
+            json!({
+                "id": revision.id,
+                "description": revision.description,
+                "created_at": revision.created_at,
+            }),
+        ),
+        None => (
+            format!("unknown ({})", format_time(created_at)),
+            json!({ "id": null, "created_at": created_at }),
+        ),
+    })
+}
+
+/// show everything the brdb knows about a file or folder
+pub fn stat(db: &BrReader<Brdb>, path: &str) -> Result<Output, Error> {
+    let path = path.trim_end_matches('/');
+    let root = db.get_fs()?;
+    let fs = match path {
+        "" => &root,
+        _ => traverse(&root, path)?,
+    };
+
+    let mut lines = vec![format!("path:        /{path}")];
+    let mut json = json!({ "path": format!("/{path}") });
+    match fs {
+        BrFs::Root(map) => {
+            lines.push(String::from("type:        folder (root)"));
+            lines.push(format!("entries:     {}", map.len()));
+            json["type"] = json!("root");
+            json["entries"] = json!(map.len());
+        }
+        BrFs::Folder(folder, map) => {
+            let (revision, revision_json) = describe_revision(db, folder.created_at)?;
+            lines.push(String::from("type:        folder"));
+            lines.push(format!("entries:     {}", map.len()));
+            lines.push(format!("folder id:   {}", folder.folder_id));
+            lines.push(format!("revision:    {revision}"));
+            json["type"] = json!("folder");
+            json["entries"] = json!(map.len());
+            json["folder_id"] = json!(folder.folder_id);
+            json["revision"] = revision_json;
+        }
+        BrFs::File(file) => {
+            lines.push(String::from("type:        file"));
+            lines.push(format!("file id:     {}", file.file_id));
+            json["type"] = json!("file");
+            json["file_id"] = json!(file.file_id);
+            match file.content_id {
+                Some(blob_id) => {
+                    let blob = db.find_blob(blob_id)?;
+                    let compression = match blob.compression {
+                        0 => String::from("none"),
+                        1 => String::from("zstd"),
+                        other => format!("unknown ({other})"),
+                    };
+                    let hash = output::hex(&blob.hash);
+                    lines.push(format!(
+                        "size:        {} ({} bytes)",
+                        format_size(blob.size_uncompressed as u64),
+                        blob.size_uncompressed
+                    ));
+                    lines.push(format!(
+                        "compressed:  {} ({} bytes, {compression})",
+                        format_size(blob.size_compressed as u64),
+                        blob.size_compressed
+                    ));
+                    lines.push(format!("blob id:     {blob_id}"));
+                    lines.push(format!("hash:        {hash} (blake3)"));
+                    json["size"] = json!(blob.size_uncompressed);
+                    json["compressed"] = json!(blob.size_compressed);
+                    json["compression"] = json!(compression);
+                    json["blob"] = json!(blob_id);
+                    json["hash"] = json!(hash);
+                }
+                None => {
+                    lines.push(String::from("size:        no content"));
+                    json["blob"] = Value::Null;
+                }
+            }
+            let (revision, revision_json) = describe_revision(db, file.created_at)?;
+            lines.push(format!("revision:    {revision}"));
+            json["revision"] = revision_json;
+        }
+    }
+    Ok(Output::new(lines.join("\n"), json))
+}
@@ -0,0 +1,369 @@
+use std::{collections::HashMap, fs, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb, Brick, BrickType, assets::brick_sizes::brick_half_extent};
+use indexmap::IndexMap;
+use serde_json::{Value, json};
+
+use crate::{
+    blob_sizes,
+    bricks::{color, decode_chunks, grid_bricks, grid_ids},
+    color::swatch,
+    error::Error,
+    files_in, format_size,
+    output::{Output, csv, hex, table},
+    owners::owner_table,
+    render::Image,
+};
+
+/// how many pixels wide each color is in a palette image, and how many go in a row
+const PALETTE_SWATCH: usize = 32;
+const PALETTE_COLUMNS: usize = 16;
+
+/// every brick in every grid
+fn all_bricks<T: BrFsReader>(db: &BrReader<T>) -> Result<Vec<Brick>, Error> {
+    let mut bricks = Vec::new();
+    for grid in grid_ids(db)? {
+        bricks.extend(grid_bricks(db, grid, None)?.into_iter().map(|saved| saved.brick));
+    }
+    Ok(bricks)
+}
+
+/// a count as a percentage of the total, with one decimal
+fn percent(count: usize, total: usize) -> String {
+    format!("{:.1}", count as f64 * 100.0 / total.max(1) as f64)
+}
+
+/// how many bricks and components each owner has in the world, counted from the bricks themselves
+/// rather than the totals saved in the owner table, most bricks first
+pub fn owners<T: BrFsReader>(db: &BrReader<T>) -> Result<Output, Error> {
+    let owners = owner_table(db)?;
+    let mut bricks = vec![0u64; owners.user_ids.len()];
+    let mut components = vec![0u64; owners.user_ids.len()];
+
+    for grid in grid_ids(db)? {
+        let chunks = db.brick_chunk_index(grid)?;
+        let indices: Vec<_> = chunks.iter().map(|chunk| chunk.index).collect();
+        for (chunk, in_chunk) in chunks.iter().zip(decode_chunks(db, grid, &indices)?) {
+            let chunk_owners: Vec<usize> = in_chunk.iter().map(|brick| brick.owner_index.unwrap_or(0)).collect();
+            for &owner in &chunk_owners {
+                // bricks can point past the end of a damaged owner table, those still get counted
+                if owner >= bricks.len() {
+                    bricks.resize(owner + 1, 0);
+                    components.resize(owner + 1, 0);
+                }
+                bricks[owner] += 1;
+            }
+
+            // components say which brick in the chunk they're on, which says who owns them
+            if chunk.num_components > 0 {
+                let (components_soa, _) = db.component_chunk(grid, chunk.index)?;
+                for &brick in &components_soa.component_brick_indices {
+                    if let Some(&owner) = chunk_owners.get(brick as usize) {
+                        components[owner] += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let mut order: Vec<usize> = (0..bricks.len()).collect();
+    order.sort_by(|&a, &b| bricks[b].cmp(&bricks[a]).then(components[b].cmp(&components[a])).then(a.cmp(&b)));
+
+    let mut rows = vec![["INDEX", "NAME", "DISPLAY NAME", "BRICKS", "COMPONENTS"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for index in order {
+        let name = owners.user_names.get(index).cloned().unwrap_or_else(|| String::from("?"));
+        let display_name = owners.display_names.get(index).cloned().unwrap_or_else(|| String::from("?"));
+        rows.push(vec![
+            index.to_string(),
+            name.clone(),
+            display_name.clone(),
+            bricks[index].to_string(),
+            components[index].to_string(),
+        ]);
+        json.push(json!({
+            "index": index,
+            "user_id": owners.user_ids.get(index).map(|id| id.uuid().to_string()),
+            "user_name": name,
+            "display_name": display_name,
+            "bricks": bricks[index],
+            "components": components[index],
+        }));
+    }
+    Ok(Output::new(table(&rows), Value::Array(json)))
+}
+
+/// how many bricks use each color, most used first. `swatches` adds a block of each color for terminals that
+/// can show true color, and `palette` saves every color as a square in a png
+pub fn colors<T: BrFsReader>(db: &BrReader<T>, swatches: bool, palette: Option<&Path>) -> Result<Output, Error> {
+    let bricks = all_bricks(db)?;
+    let mut counts: IndexMap<[u8; 3], usize> = IndexMap::new();
+    for brick in &bricks {
+        *counts.entry([brick.color.r, brick.color.g, brick.color.b]).or_default() += 1;
+    }
+    counts.sort_by(|a, a_count, b, b_count| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut rows = vec![["COLOR", "BRICKS", "PERCENT"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for (&[r, g, b], &count) in &counts {
+        let hex = format!("#{r:02x}{g:02x}{b:02x}");
+        let mut row = vec![hex.clone(), count.to_string(), percent(count, bricks.len())];
+        if swatches {
+            row.push(swatch(r, g, b));
+        }
+        rows.push(row);
+        json.push(json!({ "color": hex, "bricks": count }));
+    }
+
+    if let Some(palette) = palette {
+        let columns = counts.len().clamp(1, PALETTE_COLUMNS);
+        let (width, height) = (columns * PALETTE_SWATCH, counts.len().div_ceil(columns).max(1) * PALETTE_SWATCH);
+        let mut image = Image::new(width, height);
+        for (i, &[r, g, b]) in counts.keys().enumerate() {
+            let (left, top) = ((i % columns) * PALETTE_SWATCH, (i / columns) * PALETTE_SWATCH);
+            for y in top..top + PALETTE_SWATCH {
+                image.pixels[y * width + left..y * width + left + PALETTE_SWATCH].fill([r, g, b, 255]);
+            }
+        }
+        fs::write(palette, image.png()?)?;
+    }
+
+    let mut text = table(&rows);
+    if let Some(palette) = palette {
+        text.push_str(&format!("\nsaved {} colors to {}", counts.len(), palette.display()));
+    }
+    Ok(Output::new(text, Value::Array(json)))
+}
+
+/// how many bricks use each material, most used first, or each material and color together with `by_color`.
+/// glass and glow are the slow ones to draw, so this shows where a laggy world is spending it
+pub fn materials<T: BrFsReader>(db: &BrReader<T>, by_color: bool, as_csv: bool) -> Result<Output, Error> {
+    let bricks = all_bricks(db)?;
+    let mut counts: IndexMap<(String, Option<String>), usize> = IndexMap::new();
+    for brick in &bricks {
+        let key = (brick.material.to_string(), by_color.then(|| color(brick)));
+        *counts.entry(key).or_default() += 1;
+    }
+    counts.sort_by(|a, a_count, b, b_count| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut rows = vec![match by_color {
+        true => ["MATERIAL", "COLOR", "BRICKS", "PERCENT"].map(String::from).to_vec(),
+        false => ["MATERIAL", "BRICKS", "PERCENT"].map(String::from).to_vec(),
+    }];
+    let mut json = Vec::new();
+    for ((material, color), &count) in &counts {
+        let mut row = vec![material.clone()];
+        row.extend(color.clone());
+        row.extend([count.to_string(), percent(count, bricks.len())]);
+        rows.push(row);
+        let mut entry = json!({ "material": material, "bricks": count });
+        if let Some(color) = color {
+            entry["color"] = json!(color);
+        }
+        json.push(entry);
+    }
+    let text = match as_csv {
+        true => csv(&rows),
+        false => table(&rows),
+    };
+    Ok(Output::new(text, Value::Array(json)))
+}
+
+/// how many bricks use each brick asset, most used first. procedural bricks are the ones that can be resized,
+/// mesh bricks have a fixed shape. assets the game doesnt ship with probably come from a mod
+pub fn assets<T: BrFsReader>(db: &BrReader<T>) -> Result<Output, Error> {
+    let bricks = all_bricks(db)?;
+    let mut counts: IndexMap<(String, bool), usize> = IndexMap::new();
+    for brick in &bricks {
+        let key = match &brick.asset {
+            BrickType::Procedural { asset, .. } => (asset.to_string(), true),
+            BrickType::Basic(asset) => (asset.to_string(), false),
+        };
+        *counts.entry(key).or_default() += 1;
+    }
+    counts.sort_by(|a, a_count, b, b_count| b_count.cmp(a_count).then(a.cmp(b)));
+
+    let mut rows = vec![["ASSET", "KIND", "BRICKS", "PERCENT", "BUILT IN"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for ((asset, procedural), &count) in &counts {
+        // brdb's size table has every mesh brick the game ships with except the logic gates,
+        // which are all 1x1 bricks with Gate in the name. the game's procedural bricks all start with PB_
+        let built_in = match procedural {
+            true => asset.starts_with("PB_"),
+            false => brick_half_extent(asset).is_some() || (asset.starts_with("B_1x1_") && asset.contains("Gate")),
+        };
+        let kind = if *procedural { "procedural" } else { "mesh" };
+        let built_in_text = if built_in { "yes" } else { "no" };
+        rows.push(vec![asset.clone(), kind.into(), count.to_string(), percent(count, bricks.len()), built_in_text.into()]);
+        json.push(json!({ "asset": asset, "procedural": procedural, "bricks": count, "built_in": built_in }));
+    }
+    Ok(Output::new(table(&rows), Value::Array(json)))
+}
+
+/// the path of every file entry in every revision by its id, and the blob it points at
+fn file_entries(db: &Brdb) -> Result<HashMap<i64, (String, i64)>, Error> {
+    let folders: HashMap<i64, (Option<i64>, String)> = db
+        .conn
+        .prepare("SELECT folder_id, parent_id, name FROM folders")?
+        .query_map([], |row| Ok((row.get(0)?, (row.get(1)?, row.get(2)?))))?
+        .collect::<Result<_, _>>()?;
+    let folder_path = |mut id: Option<i64>| {
+        let mut parts = Vec::new();
+        // a damaged table could have a loop in it, and no real world is nested this deep
+        while let Some((parent, name)) = id.and_then(|id| folders.get(&id)).filter(|_| parts.len() < 256) {
+            parts.push(name.as_str());
+            id = *parent;
+        }
+        parts.reverse();
+        parts.join("/")
+    };
+    let mut statement = db.conn.prepare("SELECT file_id, parent_id, name, content_id FROM files WHERE content_id IS NOT NULL")?;
+    let rows = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<i64>>(1)?, row.get::<_, String>(2)?, row.get::<_, i64>(3)?)))?;
+    let mut entries = HashMap::new();
+    for row in rows {
+        let (id, parent, name, blob) = row?;
+        entries.insert(id, (format!("/{}/{name}", folder_path(parent)).replace("//", "/"), blob));
+    }
+    Ok(entries)
+}
+
+/// how much the blob store saves by sharing blobs between file entries, which happens when a file stays the same
+/// across revisions or two files have the same contents, and which contents are stored more than once anyway
+pub fn dedup(db: &Brdb) -> Result<Output, Error> {
+    let entries = file_entries(db)?;
+    let mut blobs: HashMap<i64, Vec<&str>> = HashMap::new();
+    for (path, blob) in entries.values() {
+        blobs.entry(*blob).or_default().push(path);
+    }
+    let sizes: HashMap<i64, (u64, Vec<u8>)> = db
+        .conn
+        .prepare("SELECT blob_id, size_compressed, hash FROM blobs")?
+        .query_map([], |row| Ok((row.get(0)?, (row.get::<_, i64>(1)? as u64, row.get(2)?))))?
+        .collect::<Result<_, _>>()?;
+
+    // every entry after the first one pointing at a blob would have been another copy of it
+    let mut shared: Vec<(i64, usize, usize, u64, &str)> = Vec::new();
+    for (blob, paths) in &mut blobs {
+        paths.sort();
+        let size = sizes.get(blob).map_or(0, |(size, _)| *size);
+        if paths.len() > 1 {
+            let mut distinct = paths.clone();
+            distinct.dedup();
+            shared.push((*blob, paths.len(), distinct.len(), size * (paths.len() as u64 - 1), paths[0]));
+        }
+    }
+    shared.sort_by(|a, b| b.3.cmp(&a.3).then(a.0.cmp(&b.0)));
+    let saved: u64 = shared.iter().map(|(.., saved, _)| saved).sum();
+
+    // blobs are looked up by hash before being added, but a world written by something else might not do that
+    let mut by_hash: HashMap<&[u8], Vec<i64>> = HashMap::new();
+    for (blob, (_, hash)) in &sizes {
+        by_hash.entry(hash).or_default().push(*blob);
+    }
+    let mut redundant: Vec<(&[u8], Vec<i64>, u64)> = by_hash
+        .into_iter()
+        .filter(|(_, copies)| copies.len() > 1)
+        .map(|(hash, mut copies)| {
+            copies.sort();
+            let wasted = copies[1..].iter().map(|blob| sizes[blob].0).sum();
+            (hash, copies, wasted)
+        })
+        .collect();
+    redundant.sort_by(|a, b| b.2.cmp(&a.2).then(a.1.cmp(&b.1)));
+    let wasted: u64 = redundant.iter().map(|(.., wasted)| wasted).sum();
+
+    let mut text = format!(
+        "{} file entries across every revision point at {} blobs. {} blobs are shared by more than one entry, which saves {}\n",
+        entries.len(),
+        blobs.len(),
+        shared.len(),
+        format_size(saved)
+    );
+    if !shared.is_empty() {
+        let mut rows = vec![["BLOB", "ENTRIES", "PATHS", "SAVED", "PATH"].map(String::from).to_vec()];
+        for (blob, count, paths, saved, path) in &shared {
+            rows.push(vec![blob.to_string(), count.to_string(), paths.to_string(), format_size(*saved), path.to_string()]);
+        }
+        text.push_str(&format!("\n{}\n", table(&rows)));
+    }
+    match redundant.is_empty() {
+        true => text.push_str("\nno contents are stored more than once"),
+        false => {
+            text.push_str(&format!("\n{} contents are stored more than once, wasting {}\n", redundant.len(), format_size(wasted)));
+            let mut rows = vec![["HASH", "BLOBS", "WASTED", "PATH"].map(String::from).to_vec()];
+            for (hash, copies, wasted) in &redundant {
+                let path = copies.iter().find_map(|blob| blobs.get(blob).map(|paths| paths[0])).unwrap_or("(unused)");
+                let copies = copies.iter().map(i64::to_string).collect::<Vec<_>>().join(",");
+                rows.push(vec![hex(&hash[..hash.len().min(8)]), copies, format_size(*wasted), path.to_string()]);
+            }
+            text.push_str(&table(&rows));
+        }
+    }
+
+    let json = json!({
+        "entries": entries.len(),
+        "blobs": blobs.len(),
+        "saved": saved,
+        "shared": shared
+            .iter()
+            .map(|(blob, count, paths, saved, path)| json!({ "blob": blob, "entries": count, "paths": paths, "saved": saved, "path": path }))
+            .collect::<Vec<_>>(),
+        "wasted": wasted,
+        "redundant": redundant
+            .iter()
+            .map(|(hash, copies, wasted)| json!({ "hash": hex(hash), "blobs": copies, "wasted": wasted }))
+            .collect::<Vec<_>>(),
+    });
+    Ok(Output::new(text, json))
+}
+
+/// rows of how big a group of files is with and without compression, biggest first, then a total
+fn compression_rows(label: &str, groups: IndexMap<String, (usize, u64, u64)>) -> (String, Vec<Value>) {
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_by(|a, b| b.1.1.cmp(&a.1.1).then(a.0.cmp(&b.0)));
+    let ratio = |size: u64, compressed: u64| format!("{:.1}", compressed as f64 * 100.0 / size.max(1) as f64);
+
+    let mut rows = vec![[label, "FILES", "SIZE", "COMPRESSED", "PERCENT"].map(String::from).to_vec()];
+    let mut json = Vec::new();
+    for (name, (files, size, compressed)) in groups {
+        rows.push(vec![name.clone(), files.to_string(), format_size(size), format_size(compressed), ratio(size, compressed)]);
+        json.push(json!({ "name": name, "files": files, "size": size, "compressed": compressed }));
+    }
+    (table(&rows), json)
+}
+
+/// how well the files in the world compress, by folder and by extension. PERCENT is how big the compressed
+/// file is next to the real one, so anything near 100 barely compresses at all
+pub fn compression(db: &BrReader<Brdb>) -> Result<Output, Error> {
+    let fs = db.get_fs()?;
+    let mut folders: IndexMap<String, (usize, u64, u64)> = IndexMap::new();
+    let mut extensions: IndexMap<String, (usize, u64, u64)> = IndexMap::new();
+    let mut total = (0, 0);
+    for (path, file) in files_in(&fs, "") {
+        let Some(blob_id) = file.content_id else {
+            continue;
+        };
+        let (compressed, size) = blob_sizes(db, blob_id)?;
+        let folder = path.rsplit_once('/').map_or("/", |(folder, _)| folder);
+        let extension = file.name.rsplit_once('.').map_or("(none)", |(_, extension)| extension);
+        for (groups, key) in [(&mut folders, format!("/{}", folder.trim_start_matches('/'))), (&mut extensions, format!(".{extension}"))] {
+            let group = groups.entry(key).or_default();
+            *group = (group.0 + 1, group.1 + size, group.2 + compressed);
+        }
+        total = (total.0 + size, total.1 + compressed);
+    }
+
+    let (folder_table, folder_json) = compression_rows("FOLDER", folders);
+    let (extension_table, extension_json) = compression_rows("EXTENSION", extensions);
+    let text = format!(
+        "{folder_table}\n\n{extension_table}\n\n{} compresses to {} ({:.1}%)",
+        format_size(total.0),
+        format_size(total.1),
+        total.1 as f64 * 100.0 / total.0.max(1) as f64
+    );
+    let json = json!({
+        "folders": folder_json,
+        "extensions": extension_json,
+        "total": { "size": total.0, "compressed": total.1 },
+    });
+    Ok(Output::new(text, json))
+}
@@ -0,0 +1,33 @@
+//! worlds for the tests to work on
+use brdb::{BrReader, Brdb, Brick, Entity, Guid, IntoReader, Owner, World, assets};
+
+/// a world with every kind of .mps file in it: bricks with components, a wire between them and a brick grid
+pub fn world() -> BrReader<Brdb> {
+    let db = Brdb::new_memory().unwrap().into_reader();
+    let mut world = World::new();
+    world.register_all_components();
+    let user_id = Guid { a: 1, b: 2, c: 3, d: 4 };
+    world.owners.insert(user_id, Owner { user_id, user_name: String::from("builder"), display_name: String::from("Builder") });
+    let (a, a_id) = Brick { asset: assets::bricks::B_REROUTE, owner_index: Some(1), ..Default::default() }
+        .with_component(assets::components::Rerouter)
+        .with_id_split();
+    let (b, b_id) = Brick {
+        position: (15, 0, 1).into(),
+        asset: assets::components::LogicGate::BoolNot.brick(),
+        ..Default::default()
+    }
+    .with_component(assets::components::LogicGate::BoolNot.component())
+    .with_id_split();
+    world.add_bricks([a, b]);
+    world.add_wire_connection(
+        assets::components::LogicGate::BoolNot.output_of(b_id),
+        assets::components::Rerouter::input_of(a_id),
+    );
+    world.add_brick_grid(
+        Entity { frozen: true, location: (0.0, 0.0, 40.0).into(), ..Default::default() },
+        [Brick { position: (0, 0, 3).into(), ..Default::default() }],
+    );
+    db.save("test world", &world).unwrap();
+    db
+}
+
@@ -0,0 +1,34 @@
+use std::{fs, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb};
+use serde_json::json;
+
+use crate::{error::Error, output::Output, write::save_file};
+
+const THUMBNAIL: &str = "Meta/Thumbnail.png";
+
+/// the 8 bytes every png file starts with
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// save the world's preview image to a png file
+pub fn get(db: &BrReader<Brdb>, output: &Path) -> Result<Output, Error> {
+    if db.find_file_by_path(THUMBNAIL)?.is_none() {
+        return Err(Error::NotFound(format!("/{THUMBNAIL}")));
+    }
+    let data = db.read_file(THUMBNAIL)?;
+    fs::write(output, &data)?;
+    let text = format!("saved the thumbnail ({} bytes) to {}", data.len(), output.display());
+    Ok(Output::new(text, json!({ "output": output, "bytes": data.len() })))
+}
+
+/// replace the world's preview image with a png file, as a new revision
+pub fn set(db: &BrReader<Brdb>, input: &Path) -> Result<Output, Error> {
+    let data = fs::read(input)?;
+    // the game only reads pngs here, anything else would leave the world without a thumbnail
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return Err(Error::Other(format!("{} isnt a png image", input.display())));
+    }
+    let size = data.len();
+    save_file(db, THUMBNAIL, data, "Set thumbnail")?;
+    let text = format!("set the thumbnail to {} ({size} bytes)", input.display());
+    Ok(Output::new(text, json!({ "input": input, "bytes": size })))
+}
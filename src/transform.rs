@@ -0,0 +1,242 @@
+use brdb::{BrFsReader, BrReader, Brdb, Brick, Position, pending::BrPendingFs};
+use serde_json::{Value, json};
+
+use crate::{
+    cli::MirrorAxis,
+    error::Error,
+    mesh::turned,
+    mps,
+    output::Output,
+    rewrite::{Edit, rewrite_main_grid},
+    write::file_patch,
+};
+
+/// change every entity in the world by editing its chunk file as json. `edit` gets the name of each list of
+/// vectors or rotations the entities have, like Locations, and one entry of it. returns how many entities there are.
+/// entities stay in the chunk file theyre saved in, even if theyve moved out of it
+fn edit_entities(db: &BrReader<Brdb>, pending: &mut BrPendingFs, edit: impl Fn(&str, &mut Value)) -> Result<usize, Error> {
+    // worlds without any entities dont have an entity index at all
+    if db.find_file_by_path("World/0/Entities/ChunkIndex.mps")?.is_none() {
+        return Ok(0);
+    }
+    let mut count = 0;
+    for chunk in db.entity_chunk_index()? {
+        let path = format!("World/0/Entities/Chunks/{chunk}.mps");
+        let mut file = mps::decode(db, &path)?;
+        for prop in ["Locations", "Rotations", "LinearVelocities", "AngularVelocities"] {
+            if let Some(values) = file["soa"][prop].as_array_mut() {
+                values.iter_mut().for_each(|value| edit(prop, value));
+            }
+        }
+        count += file["soa"]["Locations"].as_array().map_or(0, Vec::len);
+        pending.patch(file_patch(&path, mps::encode(db, &path, &file)?))?;
+    }
+    Ok(count)
+}
+
+/// a vector in an entity's chunk file
+fn vector(value: &Value) -> [f64; 3] {
+    ["X", "Y", "Z"].map(|axis| value[axis].as_f64().unwrap_or(0.0))
+}
+
+/// a position moved by an offset, unless that goes past what a position can hold
+pub fn moved(position: Position, offset: Position) -> Option<Position> {
+    Some(Position::new(
+        position.x.checked_add(offset.x)?,
+        position.y.checked_add(offset.y)?,
+        position.z.checked_add(offset.z)?,
+    ))
+}
+
+/// move every brick on the main grid and every entity by `offset`, as a new revision. bricks that end up in
+/// another chunk are saved there, with their components and wires
+pub fn translate(db: &BrReader<Brdb>, offset: Position) -> Result<Output, Error> {
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let mut overflowed = None;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| match moved(saved.brick.position, offset) {
+        Some(position) => Edit::Replace(Brick { position, ..saved.brick.clone() }),
+        None => {
+            overflowed.get_or_insert_with(|| saved.id());
+            Edit::Keep
+        }
+    })?;
+    // nothing has been written yet, so the world is left as it was
+    if let Some(id) = overflowed {
+        return Err(Error::Other(format!("brick {id} would end up further away than a position can go")));
+    }
+    let entities = edit_entities(db, &mut pending, |prop, value| {
+        if prop == "Locations" {
+            let [x, y, z] = vector(value);
+            *value = json!({ "X": x + offset.x as f64, "Y": y + offset.y as f64, "Z": z + offset.z as f64 });
+        }
+    })?;
+    let by = format!("{},{},{}", offset.x, offset.y, offset.z);
+    db.write_pending(format!("Move everything by {by}"), pending)?;
+
+    let text = format!("moved {} bricks and {entities} entities by {by}", rewritten.replaced);
+    Ok(Output::new(text, json!({ "bricks": rewritten.replaced, "entities": entities, "offset": [offset.x, offset.y, offset.z] })))
+}
+
+/// turn a point around the z axis by some quarter turns, the same way a brick's rotation turns it
+fn quarter_turn<T: Copy + std::ops::Neg<Output = T>>([mut x, mut y, z]: [T; 3], turns: u8) -> [T; 3] {
+    for _ in 0..turns {
+        (x, y) = (-y, x);
+    }
+    [x, y, z]
+}
+
+/// turn an entity's rotation around the z axis by some quarter turns, by multiplying it with the quaternion for that turn
+fn quaternion_turn([x, y, z, w]: [f64; 4], turns: u8) -> [f64; 4] {
+    let (s, c) = (turns as f64 * std::f64::consts::FRAC_PI_4).sin_cos();
+    let turned = [c * x - s * y, c * y + s * x, c * z + s * w, c * w - s * z];
+    // q and -q are the same rotation, keeping w positive just reads nicer. adding 0 turns -0 into 0
+    if turned[3] < 0.0 { turned.map(|v| -v + 0.0) } else { turned.map(|v| v + 0.0) }
+}
+
+/// turn every brick on the main grid and every entity around the z axis through 0,0 by `turns` quarter turns,
+/// as a new revision. bricks face the way theyve been turned and are saved in the chunk they end up in
+pub fn rotate(db: &BrReader<Brdb>, turns: u8) -> Result<Output, Error> {
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| {
+        let position = saved.brick.position;
+        let [x, y, z] = quarter_turn([position.x, position.y, position.z], turns);
+        let (direction, rotation) = turned(&saved.brick, |point| quarter_turn(point, turns));
+        Edit::Replace(Brick { position: Position::new(x, y, z), direction, rotation, ..saved.brick.clone() })
+    })?;
+    let entities = edit_entities(db, &mut pending, |prop, value| {
+        *value = match prop {
+            "Rotations" => {
+                let rotation = ["X", "Y", "Z", "W"].map(|axis| value[axis].as_f64().unwrap_or(0.0));
+                let [x, y, z, w] = quaternion_turn(rotation, turns);
+                json!({ "X": x, "Y": y, "Z": z, "W": w })
+            }
+            _ => {
+                // adding 0 turns -0 into 0
+                let [x, y, z] = quarter_turn(vector(value), turns).map(|v| v + 0.0);
+                json!({ "X": x, "Y": y, "Z": z })
+            }
+        }
+    })?;
+    let degrees = turns as u32 * 90;
+    db.write_pending(format!("Turn everything by {degrees} degrees"), pending)?;
+
+    let text = format!("turned {} bricks and {entities} entities by {degrees} degrees", rewritten.replaced);
+    Ok(Output::new(text, json!({ "bricks": rewritten.replaced, "entities": entities, "degrees": degrees })))
+}
+
+/// flip every brick on the main grid and every entity to the other side of the plane where `axis` is 0, as a
+/// new revision. bricks cant be mirrored themselves, so theyre turned to match their mirrored shape as well as
+/// they can, which is exact for boxes, wedges and ramps
+pub fn mirror(db: &BrReader<Brdb>, axis: MirrorAxis) -> Result<Output, Error> {
+    let axis = match axis {
+        MirrorAxis::X => 0,
+        MirrorAxis::Y => 1,
+    };
+    let flip = |mut point: [f32; 3]| {
+        point[axis] = -point[axis];
+        point
+    };
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    let rewritten = rewrite_main_grid(db, &mut pending, |saved| {
+        let mut position = [saved.brick.position.x, saved.brick.position.y, saved.brick.position.z];
+        position[axis] = -position[axis];
+        let (direction, rotation) = turned(&saved.brick, flip);
+        Edit::Replace(Brick { position: Position::new(position[0], position[1], position[2]), direction, rotation, ..saved.brick.clone() })
+    })?;
+    // a mirror turns the other way around every axis but the flipped one, for rotations and spins alike
+    let entities = edit_entities(db, &mut pending, |prop, value| {
+        *value = match prop {
+            "Rotations" => {
+                let mut rotation = ["X", "Y", "Z", "W"].map(|axis| value[axis].as_f64().unwrap_or(0.0));
+                for (i, v) in rotation.iter_mut().take(3).enumerate() {
+                    if i != axis {
+                        *v = -*v + 0.0;
+                    }
+                }
+                json!({ "X": rotation[0], "Y": rotation[1], "Z": rotation[2], "W": rotation[3] })
+            }
+            _ => {
+                let mut vector = vector(value);
+                for (i, v) in vector.iter_mut().enumerate() {
+                    if (i == axis) != (prop == "AngularVelocities") {
+                        *v = -*v + 0.0;
+                    }
+                }
+                json!({ "X": vector[0], "Y": vector[1], "Z": vector[2] })
+            }
+        }
+    })?;
+    let name = ["x", "y"][axis];
+    db.write_pending(format!("Mirror everything along {name}"), pending)?;
+
+    let text = format!("mirrored {} bricks and {entities} entities along {name}", rewritten.replaced);
+    Ok(Output::new(text, json!({ "bricks": rewritten.replaced, "entities": entities, "axis": name })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bricks::grid_bricks, revisions::all_revisions, testing::world};
+
+    fn positions(db: &BrReader<Brdb>) -> Vec<[i32; 3]> {
+        let mut positions: Vec<_> =
+            grid_bricks(db, 1, None).unwrap().into_iter().map(|saved| [saved.brick.position.x, saved.brick.position.y, saved.brick.position.z]).collect();
+        positions.sort();
+        positions
+    }
+
+    /// how many components and wires the main grid's chunk index says there are
+    fn counts(db: &BrReader<Brdb>) -> (u32, u32) {
+        db.brick_chunk_index(1).unwrap().iter().fold((0, 0), |(c, w), chunk| (c + chunk.num_components, w + chunk.num_wires))
+    }
+
+    #[test]
+    fn translate_moves_every_brick_along_with_its_components_and_wires() {
+        let db = world();
+        let before = positions(&db);
+        translate(&db, Position::new(10000, -10000, 5)).unwrap();
+        let moved: Vec<_> = before.iter().map(|[x, y, z]| [x + 10000, y - 10000, z + 5]).collect();
+        assert_eq!(positions(&db), moved);
+        assert_eq!(counts(&db), (2, 1));
+        assert!(db.brick_chunk_index(1).unwrap().iter().all(|chunk| chunk.index.x > 0));
+    }
+
+    #[test]
+    fn translate_past_the_edge_is_an_error_and_saves_nothing() {
+        let db = world();
+        let revisions = all_revisions(&db).unwrap().len();
+        assert!(translate(&db, Position::new(i32::MAX, 0, 0)).is_err());
+        assert_eq!(all_revisions(&db).unwrap().len(), revisions);
+        assert_eq!(moved(Position::new(i32::MIN, 0, 0), Position::new(-1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rotate_turns_bricks_and_entities_and_keeps_components_and_wires() {
+        let db = world();
+        rotate(&db, 1).unwrap();
+        assert_eq!(positions(&db), [[0, 0, 0], [0, 15, 1]]);
+        assert_eq!(counts(&db), (2, 1));
+        let entities = mps::decode(&db, "World/0/Entities/Chunks/0_0_0.mps").unwrap();
+        let rotation = &entities["soa"]["Rotations"][0];
+        let half = std::f64::consts::FRAC_1_SQRT_2;
+        assert!((rotation["Z"].as_f64().unwrap() - half).abs() < 1e-6 && (rotation["W"].as_f64().unwrap() - half).abs() < 1e-6);
+        assert_eq!(entities["soa"]["Locations"][0], json!({ "X": 0.0, "Y": 0.0, "Z": 40.0 }));
+    }
+
+    #[test]
+    fn quarter_turns_go_counterclockwise_and_come_back_after_four() {
+        assert_eq!(quarter_turn([1, 2, 3], 1), [-2, 1, 3]);
+        assert_eq!(quarter_turn([1, 2, 3], 2), [-1, -2, 3]);
+        assert_eq!(quarter_turn([1, 2, 3], 4), [1, 2, 3]);
+    }
+
+    #[test]
+    fn quaternion_turns_add_up_like_quarter_turns() {
+        let close = |a: [f64; 4], b: [f64; 4]| a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9);
+        // half a turn around z, from no rotation at all
+        assert!(close(quaternion_turn([0.0, 0.0, 0.0, 1.0], 2), [0.0, 0.0, 1.0, 0.0]));
+        let tilted = [0.3, -0.2, 0.1, 0.9273618495495704];
+        assert!(close(quaternion_turn(quaternion_turn(tilted, 1), 2), quaternion_turn(tilted, 3)));
+        assert!(close(quaternion_turn(tilted, 4), tilted));
+    }
+}
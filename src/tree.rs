@@ -0,0 +1,94 @@
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use serde_json::{Value, json};
+
+use crate::{blob_sizes, error::Error, format_size, output::Output, traverse};
+
+/// how many folders and files were drawn
+#[derive(Default)]
+struct Counts {
+    folders: usize,
+    files: usize,
+}
+
+/// draw the children of a folder, with `prefix` in front of every line, and give them back as json
+fn draw(
+    db: &BrReader<Brdb>,
+    fs: &BrFs,
+    prefix: &str,
+    sizes: bool,
+    counts: &mut Counts,
+    buf: &mut String,
+) -> Result<Vec<Value>, Error> {
+    let (BrFs::Root(map) | BrFs::Folder(_, map)) = fs else {
+        return Ok(Vec::new());
+    };
+
+    let mut children = Vec::new();
+    // sorted by name like tree does, instead of the order the world stores them in
+    let mut entries: Vec<_> = map.iter().collect();
+    entries.sort_by_key(|(name, _)| *name);
+
+    for (i, (name, child)) in entries.into_iter().enumerate() {
+        let last = i == map.len() - 1;
+        let (branch, indent) = match last {
+            true => ("└── ", "    "),
+            false => ("├── ", "│   "),
+        };
+
+        match child {
+            BrFs::File(file) => {
+                counts.files += 1;
+                match (sizes, file.content_id) {
+                    (true, Some(blob_id)) => {
+                        let (_, size) = blob_sizes(db, blob_id)?;
+                        buf.push_str(&format!("{prefix}{branch}{name} ({})\n", format_size(size)));
+                        children.push(json!({ "name": name, "type": "file", "size": size }));
+                    }
+                    _ => {
+                        buf.push_str(&format!("{prefix}{branch}{name}\n"));
+                        children.push(json!({ "name": name, "type": "file" }));
+                    }
+                }
+            }
+            _ => {
+                counts.folders += 1;
+                buf.push_str(&format!("{prefix}{branch}{name}/\n"));
+                let grandchildren = draw(db, child, &format!("{prefix}{indent}"), sizes, counts, buf)?;
+                children.push(json!({ "name": name, "type": "folder", "children": grandchildren }));
+            }
+        }
+    }
+    Ok(children)
+}
+
+/// show everything below a folder like the unix `tree` command
+pub fn tree(db: &BrReader<Brdb>, path: &str, sizes: bool) -> Result<Output, Error> {
+    let path = path.trim_end_matches('/');
+    let root = db.get_fs()?;
+    let fs = match path {
+        "" => &root,
+        _ => traverse(&root, path)?,
+    };
+    // like ls, a file is just its own path
+    if let BrFs::File(_) = fs {
+        return Ok(Output::new(format!("/{path}"), json!({ "path": format!("/{path}"), "type": "file" })));
+    }
+
+    let mut buf = format!("/{path}\n");
+    let mut counts = Counts::default();
+    let children = draw(db, fs, "", sizes, &mut counts, &mut buf)?;
+
+    let plural = |count: usize, word: &str| match count {
+        1 => format!("{count} {word}"),
+        _ => format!("{count} {word}s"),
+    };
+    buf.push_str(&format!("\n{}, {}", plural(counts.folders, "folder"), plural(counts.files, "file")));
+    let json = json!({
+        "path": format!("/{path}"),
+        "type": "folder",
+        "folders": counts.folders,
+        "files": counts.files,
+        "children": children,
+    });
+    Ok(Output::new(buf, json))
+}
@@ -0,0 +1,277 @@
+use std::{fs, io, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use ratatui::{
+    DefaultTerminal, Frame,
+    crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::{ReadOptions, error::Error, read_file, traverse};
+
+const KEYS: &str = "up/down move  enter open  backspace back  / search  x hex  e extract  pgup/pgdn scroll  q quit";
+
+/// take out tabs and control characters, which would mess up the lines around them
+fn clean(text: &str) -> String {
+    text.chars().map(|c| if c == '\t' { ' ' } else { c }).filter(|c| !c.is_control()).collect()
+}
+
+/// puts the terminal back how it was when dropped, even if something goes wrong halfway
+struct Restore;
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+/// what the browser is looking at
+struct Browser<'a> {
+    db: &'a BrReader<Brdb>,
+    fs: &'a BrFs,
+    /// the folder shown on the left, from the root without a leading /
+    cwd: String,
+    /// the names in the folder that match the search, folders first, with whether each is a folder
+    entries: Vec<(String, bool)>,
+    /// which entry is selected, and how far the list is scrolled to show it
+    list: ListState,
+    search: String,
+    searching: bool,
+    hex: bool,
+    /// the path the preview is of, and its lines
+    preview: Option<(String, Vec<String>)>,
+    scroll: usize,
+    /// how many lines of the preview fit on the screen, for paging through it
+    rows: usize,
+    message: String,
+}
+
+impl Browser<'_> {
+    fn path(&self, name: &str) -> String {
+        match self.cwd.as_str() {
+            "" => name.to_string(),
+            cwd => format!("{cwd}/{name}"),
+        }
+    }
+
+    /// list the current folder again, keeping only what matches the search
+    fn list(&mut self) {
+        let folder = match self.cwd.as_str() {
+            "" => Ok(self.fs),
+            cwd => traverse(self.fs, cwd),
+        };
+        let search = self.search.to_lowercase();
+        self.entries = match folder {
+            Ok(BrFs::Root(map) | BrFs::Folder(_, map)) => map
+                .iter()
+                .filter(|(name, _)| name.to_lowercase().contains(&search))
+                .map(|(name, child)| (name.clone(), !matches!(child, BrFs::File(_))))
+                .collect(),
+            _ => Vec::new(),
+        };
+        self.entries.sort_by_key(|(_, folder)| !folder);
+        let selected = self.selected().min(self.entries.len().saturating_sub(1));
+        self.list.select(Some(selected));
+        self.preview = None;
+    }
+
+    fn selected(&self) -> usize {
+        self.list.selected().unwrap_or(0)
+    }
+
+    /// decode the selected file for the preview, or list what's in the selected folder
+    fn load_preview(&mut self) {
+        let Some((name, folder)) = self.entries.get(self.selected()) else {
+            self.preview = None;
+            return;
+        };
+        let path = self.path(name);
+        if self.preview.as_ref().is_some_and(|(shown, _)| *shown == path) {
+            return;
+        }
+        let lines = match folder {
+            true => match traverse(self.fs, &path) {
+                Ok(BrFs::Folder(_, map)) => map
+                    .iter()
+                    .map(|(name, child)| match child {
+                        BrFs::File(_) => name.clone(),
+                        _ => format!("{name}/"),
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            },
+            false => {
+                let options = ReadOptions { hex: self.hex, ..ReadOptions::default() };
+                match read_file(self.db, &path, options) {
+                    Ok(output) => match std::str::from_utf8(&output.text) {
+                        Ok(text) => text.lines().map(clean).collect(),
+                        Err(_) => vec![format!("binary file, {} bytes. press x to see it as hex", output.text.len())],
+                    },
+                    Err(error) => vec![format!("error: {error}")],
+                }
+            }
+        };
+        self.preview = Some((path, lines));
+        self.scroll = 0;
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        self.load_preview();
+        let [title, body, footer] = Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let left = (body.width / 3).clamp(16, 40).min(body.width.saturating_sub(4));
+        let [entries, preview] = Layout::horizontal([Constraint::Length(left + 1), Constraint::Min(0)]).areas(body);
+        self.rows = preview.height as usize;
+        let bar = Style::default().add_modifier(Modifier::REVERSED);
+
+        let title_text = format!(" /{}{}", self.cwd, if self.hex { "  [hex]" } else { "" });
+        frame.render_widget(Paragraph::new(title_text).style(bar), title);
+
+        let items: Vec<ListItem> = self
+            .entries
+            .iter()
+            .map(|(name, folder)| match folder {
+                true => ListItem::new(format!(" {}/", clean(name))).style(Style::default().fg(Color::Blue)),
+                false => ListItem::new(format!(" {}", clean(name))),
+            })
+            .collect();
+        let list = List::new(items).block(Block::default().borders(Borders::RIGHT)).highlight_style(bar);
+        frame.render_stateful_widget(list, entries, &mut self.list);
+
+        let lines: Vec<Line> = match &self.preview {
+            Some((_, lines)) => lines.iter().skip(self.scroll).take(self.rows).map(|line| Line::raw(format!(" {line}"))).collect(),
+            None => Vec::new(),
+        };
+        frame.render_widget(Paragraph::new(lines), preview);
+
+        let footer_text = match (self.searching, self.message.is_empty()) {
+            (true, _) => format!(" search: {}", self.search),
+            (false, false) => format!(" {}", self.message),
+            (false, true) => format!(" {KEYS}"),
+        };
+        frame.render_widget(Paragraph::new(footer_text).style(bar), footer);
+    }
+
+    /// save the selected file next to where brdb_cmd was run, as it's stored in the world
+    fn extract(&mut self) -> Result<String, Error> {
+        let Some((name, false)) = self.entries.get(self.selected()) else {
+            return Err(Error::Other(String::from("only files can be extracted")));
+        };
+        let target = Path::new(name);
+        if target.exists() {
+            return Err(Error::Other(format!("{name} already exists here")));
+        }
+        let data = self.db.read_file(self.path(name))?;
+        fs::write(target, &data)?;
+        Ok(format!("saved {} bytes to ./{name}", data.len()))
+    }
+
+    /// handle one key, and say whether to keep going
+    fn press(&mut self, key: KeyEvent) -> bool {
+        self.message.clear();
+        if self.searching {
+            match key.code {
+                KeyCode::Enter => self.searching = false,
+                KeyCode::Esc => {
+                    self.searching = false;
+                    self.search.clear();
+                    self.list();
+                }
+                KeyCode::Backspace => {
+                    self.search.pop();
+                    self.list();
+                }
+                KeyCode::Char(c) => {
+                    self.search.push(c);
+                    self.list();
+                }
+                _ => (),
+            }
+            return true;
+        }
+        let page = self.rows.max(1);
+        let last = self.entries.len().saturating_sub(1);
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            KeyCode::Char('q') => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.list.select(Some(self.selected().saturating_sub(1))),
+            KeyCode::Down | KeyCode::Char('j') => self.list.select(Some((self.selected() + 1).min(last))),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(page),
+            KeyCode::PageDown => {
+                let lines = self.preview.as_ref().map_or(0, |(_, lines)| lines.len());
+                self.scroll = (self.scroll + page).min(lines.saturating_sub(1));
+            }
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+                if let Some((name, true)) = self.entries.get(self.selected()) {
+                    self.cwd = self.path(name);
+                    self.search.clear();
+                    self.list.select(Some(0));
+                    self.list();
+                }
+            }
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') if !self.cwd.is_empty() => {
+                let (parent, name) = self.cwd.rsplit_once('/').unwrap_or(("", &self.cwd));
+                let name = name.to_string();
+                self.cwd = parent.to_string();
+                self.search.clear();
+                self.list();
+                // land back on the folder that was just left
+                self.list.select(Some(self.entries.iter().position(|(entry, _)| *entry == name).unwrap_or(0)));
+            }
+            KeyCode::Esc if !self.search.is_empty() => {
+                self.search.clear();
+                self.list();
+            }
+            KeyCode::Char('/') => {
+                self.searching = true;
+                self.search.clear();
+            }
+            KeyCode::Char('x') => {
+                self.hex = !self.hex;
+                self.preview = None;
+            }
+            KeyCode::Char('e') => {
+                self.message = self.extract().unwrap_or_else(|error| format!("error: {error}"));
+            }
+            _ => (),
+        }
+        true
+    }
+}
+
+/// browse the world with the folders on the left and the selected file decoded on the right
+pub fn run(db: &BrReader<Brdb>, fs: &BrFs) -> Result<(), Error> {
+    use std::io::IsTerminal;
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(Error::Other(String::from("tui needs a terminal to run in")));
+    }
+    let mut browser = Browser {
+        db,
+        fs,
+        cwd: String::new(),
+        entries: Vec::new(),
+        list: ListState::default().with_selected(Some(0)),
+        search: String::new(),
+        searching: false,
+        hex: false,
+        preview: None,
+        scroll: 0,
+        rows: 0,
+        message: String::new(),
+    };
+    browser.list();
+
+    let mut terminal: DefaultTerminal = ratatui::try_init()?;
+    let _restore = Restore;
+    loop {
+        terminal.draw(|frame| browser.draw(frame))?;
+        // only presses, since some terminals also send when keys are let go
+        if let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+            && !browser.press(key)
+        {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::panic::{self, AssertUnwindSafe};
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs};
+use serde_json::json;
+
+use crate::{error::Error, files_in, mps, output::Output, pattern, traverse};
+
+/// the .mps files a path or glob points to: the file itself, or every one below a folder
+fn targets(db: &BrReader<Brdb>, paths: &[String]) -> Result<Vec<String>, Error> {
+    let root = db.get_fs()?;
+    let mut files = Vec::new();
+    for path in paths {
+        for path in pattern::expand(&root, path)? {
+            let fs = match path.as_str() {
+                "" => &root,
+                _ => traverse(&root, &path)?,
+            };
+            match fs {
+                BrFs::File(_) => files.push(path),
+                _ => files.extend(files_in(fs, &path).into_iter().map(|(path, _)| path).filter(|path| path.ends_with(".mps"))),
+            }
+        }
+    }
+    files.dedup();
+    Ok(files)
+}
+
+/// decode a file, turning a panic somewhere inside brdb into a problem with the file like any other
+fn validate_file(db: &BrReader<Brdb>, path: &str) -> Result<usize, mps::Invalid> {
+    // the default hook would print the panic on top of the output
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| ()));
+    let result = panic::catch_unwind(AssertUnwindSafe(|| mps::validate(db, path)));
+    panic::set_hook(hook);
+    result.unwrap_or_else(|panic| {
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        Err(mps::Invalid { at: String::new(), offset: None, problem: format!("decoding panicked: {message}") })
+    })
+}
+
+/// fully decode .mps files with the schema they were written with, and say which file, struct and byte
+/// offset it fails at for any that dont decode
+pub fn validate(db: &BrReader<Brdb>, paths: &[String]) -> Result<Output, Error> {
+    let files = targets(db, paths)?;
+    if files.is_empty() {
+        return Err(Error::Other(String::from("there are no .mps files to validate there")));
+    }
+
+    let (mut lines, mut results) = (Vec::new(), Vec::new());
+    let mut bad = 0;
+    for path in &files {
+        match validate_file(db, path) {
+            Ok(records) => {
+                lines.push(format!("/{path}: OK"));
+                results.push(json!({ "path": format!("/{path}"), "ok": true, "records": records }));
+            }
+            Err(invalid) => {
+                let mut line = format!("/{path}: FAILED");
+                if !invalid.at.is_empty() {
+                    line.push_str(&format!(" in {}", invalid.at));
+                }
+                if let Some(offset) = invalid.offset {
+                    line.push_str(&format!(" at byte {offset}"));
+                }
+                lines.push(format!("{line}: {}", invalid.problem));
+                results.push(json!({
+                    "path": format!("/{path}"),
+                    "ok": false,
+                    "at": invalid.at,
+                    "offset": invalid.offset,
+                    "problem": invalid.problem,
+                }));
+                bad += 1;
+            }
+        }
+    }
+
+    lines.push(match bad {
+        0 => format!("all {} files are valid", files.len()),
+        _ => format!("{bad} of {} files are not valid", files.len()),
+    });
+    let output = Output::new(lines.join("\n"), json!({ "files": results, "bad": bad }));
+    Ok(if bad > 0 { output.failed() } else { output })
+}
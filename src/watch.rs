@@ -0,0 +1,103 @@
+use std::{fs, io, io::Write, path::Path, thread, time::{Duration, SystemTime}};
+use indexmap::IndexMap;
+use serde_json::json;
+
+use crate::{
+    diff::file_hashes,
+    error::Error,
+    format_time, live,
+    output::Output,
+    revisions::{Revision, all_revisions},
+};
+
+/// when the world and its -wal file were last changed and how big they are, which is cheap to check
+/// and changes whenever anything saves into it
+fn stamp(path: &Path) -> Result<Vec<Option<(SystemTime, u64)>>, Error> {
+    let files = [Some(fs::metadata(path)?), fs::metadata(live::sidecar(path, "-wal")).ok()];
+    Ok(files.iter().map(|file| file.as_ref().and_then(|file| Some((file.modified().ok()?, file.len())))).collect())
+}
+
+/// a consistent copy of the world's revisions and the hash of every file in it
+struct State {
+    revisions: Vec<Revision>,
+    hashes: IndexMap<String, Option<Vec<u8>>>,
+}
+
+fn read_state(path: &Path) -> Result<State, Error> {
+    let db = live::snapshot(path)?;
+    Ok(State { revisions: all_revisions(&db)?, hashes: file_hashes(&db)? })
+}
+
+/// print a line and make sure it goes out right away, since whoever is reading is waiting for it
+fn emit(line: &str) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{line}")?;
+    stdout.flush()
+}
+
+/// keep an eye on a world and print which files changed every time something saves a new revision
+/// into it, until stopped with ctrl-c. the file is checked every `interval` seconds but only read when
+/// it changed. with `json` every save is one line of json, so the output can be piped into other tools
+pub fn watch(path: &Path, interval: u64, json: bool) -> Result<Output, Error> {
+    let State { revisions, mut hashes } = read_state(path)?;
+    let mut last = revisions.last().map_or(0, |revision| revision.id);
+    let mut seen = stamp(path)?;
+    eprintln!("watching {} from revision {last}, press ctrl-c to stop", path.display());
+
+    loop {
+        thread::sleep(Duration::from_secs(interval.max(1)));
+        let now = match stamp(path) {
+            Ok(now) => now,
+            Err(_) if !path.exists() => return Err(Error::NotFound(path.display().to_string())),
+            Err(error) => return Err(error),
+        };
+        if now == seen {
+            continue;
+        }
+        // the game could be halfway through saving, in which case it's tried again next time
+        let Ok(State { revisions, hashes: new_hashes }) = read_state(path) else {
+            continue;
+        };
+        seen = now;
+        let revisions: Vec<Revision> = revisions.into_iter().filter(|revision| revision.id > last).collect();
+        let Some(newest) = revisions.last() else {
+            continue;
+        };
+        last = newest.id;
+
+        let removed: Vec<String> = hashes.keys().filter(|file| !new_hashes.contains_key(*file)).map(|file| format!("/{file}")).collect();
+        let added: Vec<String> = new_hashes.keys().filter(|file| !hashes.contains_key(*file)).map(|file| format!("/{file}")).collect();
+        let modified: Vec<String> = new_hashes
+            .iter()
+            .filter(|(file, hash)| hashes.get(*file).is_some_and(|old| old != *hash))
+            .map(|(file, _)| format!("/{file}"))
+            .collect();
+        hashes = new_hashes;
+
+        let written = match json {
+            true => {
+                let revisions: Vec<_> = revisions
+                    .iter()
+                    .map(|revision| json!({ "id": revision.id, "description": revision.description, "created_at": revision.created_at }))
+                    .collect();
+                let event = json!({ "revisions": revisions, "added": added, "removed": removed, "modified": modified });
+                emit(&event.to_string())
+            }
+            false => {
+                let mut lines: Vec<String> = revisions
+                    .iter()
+                    .map(|revision| format!("revision {} at {}: {}", revision.id, format_time(revision.created_at), revision.description))
+                    .collect();
+                lines.extend(removed.iter().map(|file| format!("D {file}")));
+                lines.extend(modified.iter().map(|file| format!("M {file}")));
+                lines.extend(added.iter().map(|file| format!("A {file}")));
+                emit(&lines.join("\n"))
+            }
+        };
+        match written {
+            // whoever was reading has stopped, so theres no one left to watch for
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => return Ok(Output::none()),
+            written => written?,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use brdb::{BrFsReader, BrReader, WireChunkSoA, schema::BrdbValue};
+use indexmap::IndexMap;
+use serde_json::{Value, json};
+
+use crate::{bricks::grid_ids, cli::GraphFormat, error::Error, output::Output};
+
+/// one end of a wire: a port on a component on a brick
+struct Port {
+    grid: usize,
+    brick: String,
+    component: String,
+    port: String,
+}
+
+impl Port {
+    /// the component the port is on, which is a node in the graph
+    fn node(&self) -> String {
+        match self.grid {
+            1 => format!("{} {}", self.brick, self.component),
+            grid => format!("{grid}/{} {}", self.brick, self.component),
+        }
+    }
+}
+
+/// every wire in the world, from its source port to its target port
+fn all_wires<T: BrFsReader>(db: &BrReader<T>) -> Result<Vec<(Port, Port)>, Error> {
+    let global_data = db.global_data()?;
+    let component = |index: u16| {
+        global_data
+            .component_type_names
+            .get_index(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("unknown type {index}"))
+    };
+    let port = |index: u16| {
+        global_data
+            .component_wire_port_names
+            .get_index(index as usize)
+            .cloned()
+            .unwrap_or_else(|| format!("port {index}"))
+    };
+
+    let mut wires = Vec::new();
+    for grid in grid_ids(db)? {
+        for chunk in db.brick_chunk_index(grid)? {
+            if chunk.num_wires == 0 {
+                continue;
+            }
+            let soa = WireChunkSoA::try_from(&BrdbValue::Struct(Box::new(db.wire_chunk_soa(grid, chunk.index)?)))?;
+            // targets are always on bricks in this chunk, local sources are too
+            let target = |target: &brdb::WirePortTarget| Port {
+                grid,
+                brick: format!("{}:{}", chunk.index, target.brick_index_in_chunk),
+                component: component(target.component_type_index),
+                port: port(target.port_index),
+            };
+            for (source, to) in soa.local_wire_sources.iter().zip(&soa.local_wire_targets) {
+                let from = Port {
+                    grid,
+                    brick: format!("{}:{}", chunk.index, source.brick_index_in_chunk),
+                    component: component(source.component_type_index),
+                    port: port(source.port_index),
+                };
+                wires.push((from, target(to)));
+            }
+            // remote sources are in another chunk, or on another grid
+            for (source, to) in soa.remote_wire_sources.iter().zip(&soa.remote_wire_targets) {
+                let from = Port {
+                    grid: source.grid_persistent_index as usize,
+                    brick: format!("{}:{}", source.chunk_index, source.brick_index_in_chunk),
+                    component: component(source.component_type_index),
+                    port: port(source.port_index),
+                };
+                wires.push((from, target(to)));
+            }
+        }
+    }
+    Ok(wires)
+}
+
+/// a component type without the prefixes every one of them has, for labels
+fn short_name(component: &str) -> &str {
+    let name = component.strip_prefix("BrickComponentType_").unwrap_or(component);
+    let name = name.strip_prefix("WireGraph_").unwrap_or(name);
+    name.strip_prefix("Component_").unwrap_or(name)
+}
+
+/// quote a string for a dot file
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+/// the wire graph of the world, with the components as nodes and the wires between their ports as edges
+pub fn export<T: BrFsReader>(db: &BrReader<T>, format: GraphFormat) -> Result<Output, Error> {
+    let wires = all_wires(db)?;
+
+    // every component with a wire on it, in the order they're first seen
+    let mut nodes = IndexMap::new();
+    for port in wires.iter().flat_map(|(from, to)| [from, to]) {
+        nodes.entry(port.node()).or_insert_with(|| {
+            json!({ "id": port.node(), "grid": port.grid, "brick": port.brick, "component": port.component })
+        });
+    }
+    let edges: Vec<Value> = wires
+        .iter()
+        .map(|(from, to)| {
+            json!({
+                "source": { "node": from.node(), "port": from.port },
+                "target": { "node": to.node(), "port": to.port },
+            })
+        })
+        .collect();
+    let json = json!({ "nodes": nodes.values().collect::<Vec<_>>(), "edges": edges });
+
+    let text = match format {
+        GraphFormat::Json => serde_json::to_string_pretty(&json)?,
+        GraphFormat::Dot => {
+            let mut lines = vec![String::from("digraph wires {"), String::from("    rankdir=LR;")];
+            lines.push(String::from("    node [shape=box];"));
+            for node in nodes.values() {
+                let (id, brick, component) = (node["id"].as_str(), node["brick"].as_str(), node["component"].as_str());
+                let label = format!("{}\n{}", short_name(component.unwrap_or_default()), brick.unwrap_or_default());
+                lines.push(format!("    {} [label={}];", dot_string(id.unwrap_or_default()), dot_string(&label)));
+            }
+            for (from, to) in &wires {
+                lines.push(format!(
+                    "    {} -> {} [label={}];",
+                    dot_string(&from.node()),
+                    dot_string(&to.node()),
+                    dot_string(&format!("{} -> {}", from.port, to.port)),
+                ));
+            }
+            lines.push(String::from("}"));
+            lines.join("\n")
+        }
+    };
+    Ok(Output::new(text, json))
+}
@@ -0,0 +1,60 @@
+use std::{env, fs, path::{Path, PathBuf}, time::UNIX_EPOCH};
+use serde_json::json;
+
+use crate::{config, error::Error, format_size, format_time, output::{Output, table}};
+
+/// where brickadia keeps its saved worlds: %LOCALAPPDATA%\Brickadia\Saved\Worlds on windows,
+/// and ~/.config/Epic/Brickadia/Saved/Worlds on linux
+pub fn default_dir() -> Option<PathBuf> {
+    let saved = match cfg!(windows) {
+        true => PathBuf::from(env::var_os("LOCALAPPDATA")?).join("Brickadia"),
+        false => config::home()?.join(".config").join("Epic").join("Brickadia"),
+    };
+    Some(saved.join("Saved").join("Worlds"))
+}
+
+/// every .brdb file in a folder and the folders in it
+fn find_worlds(dir: &Path, worlds: &mut Vec<PathBuf>) -> Result<(), Error> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_worlds(&path, worlds)?;
+        } else if path.extension().is_some_and(|extension| extension == "brdb") {
+            worlds.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// list the worlds brickadia has saved, or the ones in `dir`, with how big they are and when they were last saved
+pub fn list(dir: Option<&Path>) -> Result<Output, Error> {
+    let dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => default_dir().ok_or_else(|| Error::Other(String::from("cant tell where brickadia keeps its worlds, give the folder with --dir")))?,
+    };
+    if !dir.is_dir() {
+        return Err(Error::NotFound(dir.display().to_string()));
+    }
+    let mut paths = Vec::new();
+    find_worlds(&dir, &mut paths)?;
+    paths.sort();
+
+    let mut rows = vec![["NAME", "SIZE", "MODIFIED"].map(String::from).to_vec()];
+    let mut worlds = Vec::new();
+    for path in &paths {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata.modified().ok().and_then(|time| time.duration_since(UNIX_EPOCH).ok()).map(|time| time.as_secs() as i64);
+        // worlds in folders are named with the folder, like Servers/main
+        let name = path.strip_prefix(&dir).unwrap_or(path).with_extension("");
+        let name = name.to_string_lossy().replace('\\', "/");
+        rows.push(vec![name.clone(), format_size(metadata.len()), modified.map(format_time).unwrap_or_default()]);
+        worlds.push(json!({ "name": name, "path": path, "size": metadata.len(), "modified": modified }));
+    }
+
+    let text = match paths.is_empty() {
+        true => format!("there are no worlds in {}", dir.display()),
+        false => format!("{} world{} in {}\n{}", paths.len(), if paths.len() == 1 { "" } else { "s" }, dir.display(), table(&rows)),
+    };
+    Ok(Output::new(text, json!({ "dir": dir, "worlds": worlds })))
+}
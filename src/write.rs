@@ -0,0 +1,264 @@
+use std::{fs, path::Path};
+use brdb::{BrFsReader, BrReader, Brdb, fs::BrFs, pending::BrPendingFs};
+use serde_json::json;
+
+use crate::{confirm, diff::blob_hash, error::Error, output::Output, pattern, strings_to_lines, traverse};
+
+/// build a patch that puts a file or folder at the given path, with every folder above it
+pub fn entry_patch(path: &str, entry: BrPendingFs) -> BrPendingFs {
+    let mut parts = path.split('/').filter(|part| !part.is_empty()).rev();
+    let name = parts.next().unwrap_or_default().to_string();
+
+    let mut patch = (name, entry);
+    for folder in parts {
+        patch = (folder.to_string(), BrPendingFs::Folder(Some(vec![patch])));
+    }
+    BrPendingFs::Root(vec![patch])
+}
+
+/// take an entry out of a pending filesystem, so it gets deleted when written
+pub fn remove_entry(pending: &mut BrPendingFs, path: &str) -> Result<(), Error> {
+    let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+    match pending.cd_mut(parent)? {
+        BrPendingFs::Root(children) | BrPendingFs::Folder(Some(children)) => {
+            children.retain(|(child, _)| child != name);
+            Ok(())
+        }
+        _ => Err(Error::Other(format!("/{parent} is not a folder"))),
+    }
+}
+
+/// build a patch that puts a file at the given path, with every folder above it
+pub fn file_patch(path: &str, data: Vec<u8>) -> BrPendingFs {
+    entry_patch(path, BrPendingFs::File(Some(data)))
+}
+
+/// brdb only reads folders from the root, so a file put there would vanish
+fn check_not_root_file(path: &str, entry: &BrPendingFs) -> Result<(), Error> {
+    match entry {
+        BrPendingFs::File(_) if !path.contains('/') => {
+            Err(Error::Other(format!("files cant be put in the root folder: /{path}")))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// write a file into the brdb as a new revision, creating parent folders as needed
+pub fn save_file(db: &BrReader<Brdb>, path: &str, data: Vec<u8>, description: &str) -> Result<(), Error> {
+    let file = BrPendingFs::File(Some(data));
+    check_not_root_file(path, &file)?;
+    let mut pending = db.get_fs()?.to_pending_patch()?;
+    pending.patch(entry_patch(path, file))?;
+    db.write_pending(description, pending)?;
+    Ok(())
+}
+
+/// copy a file from disk into the brdb
+pub fn write_file(db: &BrReader<Brdb>, local: &Path, path: &str) -> Result<Output, Error> {
+    if path.is_empty() || path.ends_with('/') {
+        return Err(Error::Other(format!("not a file path: /{path}")));
+    }
+    if let Ok(BrFs::Root(_) | BrFs::Folder(..)) = db.get_fs()?.cd(path) {
+        return Err(Error::Other(format!("/{path} is a folder")));
+    }
+
+    let data = fs::read(local)?;
+    let size = data.len();
+    save_file(db, path, data, &format!("Write {path}"))?;
+    Ok(Output::new(format!("wrote {size} bytes to /{path}"), json!({ "path": format!("/{path}"), "bytes": size })))
+}
+
+/// the path an entry ends up at when copied or moved to `dst`, which is inside it if it's a folder
+fn destination(root: &BrFs, src: &str, dst: &str) -> String {
+    let name = src.rsplit('/').next().unwrap_or(src);
+    match dst {
+        "" => name.to_string(),
+        _ => match traverse(root, dst) {
+            Ok(BrFs::Root(_) | BrFs::Folder(..)) => format!("{dst}/{name}"),
+            _ => dst.to_string(),
+        },
+    }
+}
+
+/// whether two paths point at the same world file, however theyre written, like w.brdb and ./w.brdb
+pub fn same_file(a: &Path, b: &Path) -> Result<bool, Error> {
+    Ok(fs::canonicalize(a)? == fs::canonicalize(b)?)
+}
+
+/// copy a file, or a folder with everything in it, from one brdb into another (or the same one, when
+/// `same_world` is set)
+pub fn copy_to(
+    from: &BrReader<Brdb>,
+    src: &str,
+    to: &BrReader<Brdb>,
+    dst: &str,
+    recursive: bool,
+    same_world: bool,
+) -> Result<Output, Error> {
+    let src = src.trim_start_matches('/').trim_end_matches('/');
+    let dst = dst.trim_start_matches('/').trim_end_matches('/');
+    if src.is_empty() {
+        return Err(Error::Other(String::from("cant copy the root folder")));
+    }
+
+    let from_root = from.get_fs()?;
+    let entry = traverse(&from_root, src)?;
+    if !recursive && !matches!(entry, BrFs::File(_)) {
+        return Err(Error::Other(format!("/{src} is a folder, use -r to copy it")));
+    }
+
+    let to_root = to.get_fs()?;
+    let target = destination(&to_root, src, dst);
+    if same_world && (target == src || target.starts_with(&format!("{src}/"))) {
+        return Err(Error::Other(format!("cant copy /{src} into itself")));
+    }
+
+    let entry = entry.to_pending(&**from)?;
+    check_not_root_file(&target, &entry)?;
+    let mut pending = to_root.to_pending_patch()?;
+    pending.patch(entry_patch(&target, entry))?;
+    to.write_pending(format!("Copy {src} to {target}"), pending)?;
+    let json = json!({ "src": format!("/{src}"), "dst": format!("/{target}") });
+    Ok(Output::new(format!("copied /{src} to /{target}"), json))
+}
+
+/// put a file, or a folder with everything in it, back the way it is in a backup (or any other world), as a
+/// new revision. unlike copying, a restored folder loses anything in it the backup doesnt have
+pub fn restore(
+    backup: &BrReader<Brdb>,
+    backup_path: &Path,
+    path: &str,
+    world: &BrReader<Brdb>,
+    recursive: bool,
+) -> Result<Output, Error> {
+    let path = path.trim_start_matches('/').trim_end_matches('/');
+    if path.is_empty() {
+        return Err(Error::Other(String::from("cant restore the whole world, copy the backup over it or use rollback")));
+    }
+    let backup_root = backup.get_fs()?;
+    let entry = traverse(&backup_root, path)
+        .map_err(|_| Error::NotFound(format!("/{path} in {}", backup_path.display())))?;
+    if !recursive && !matches!(entry, BrFs::File(_)) {
+        return Err(Error::Other(format!("/{path} is a folder, use -r to restore it")));
+    }
+
+    let root = world.get_fs()?;
+    let json = json!({ "path": format!("/{path}"), "from": backup_path });
+    if let (BrFs::File(old), Ok(BrFs::File(current))) = (entry, traverse(&root, path))
+        && blob_hash(backup, old.content_id)? == blob_hash(world, current.content_id)?
+    {
+        return Ok(Output::new(format!("/{path} is already the same as in the backup"), json));
+    }
+
+    let entry = entry.to_pending(&**backup)?;
+    check_not_root_file(path, &entry)?;
+    let mut pending = root.to_pending_patch()?;
+    if traverse(&root, path).is_ok() {
+        remove_entry(&mut pending, path)?;
+    }
+    pending.patch(entry_patch(path, entry))?;
+    let name = backup_path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    world.write_pending(format!("Restore {path} from {name}"), pending)?;
+    Ok(Output::new(format!("restored /{path} from {}", backup_path.display()), json))
+}
+
+/// copy a file, or a folder with everything in it, to somewhere else in the brdb
+pub fn copy(db: &BrReader<Brdb>, src: &str, dst: &str, recursive: bool) -> Result<Output, Error> {
+    copy_to(db, src, db, dst, recursive, true)
+}
+
+/// move or rename a file or folder inside the brdb
+pub fn move_entry(db: &BrReader<Brdb>, src: &str, dst: &str) -> Result<Output, Error> {
+    let src = src.trim_start_matches('/').trim_end_matches('/');
+    let dst = dst.trim_start_matches('/').trim_end_matches('/');
+    if src.is_empty() {
+        return Err(Error::Other(String::from("cant move the root folder")));
+    }
+
+    let root = db.get_fs()?;
+    let entry = traverse(&root, src)?;
+    let target = destination(&root, src, dst);
+    if target == src || target.starts_with(&format!("{src}/")) {
+        return Err(Error::Other(format!("cant move /{src} into itself")));
+    }
+    if traverse(&root, &target).is_ok() {
+        return Err(Error::Other(format!("/{target} already exists")));
+    }
+
+    let entry = entry.to_pending(&**db)?;
+    check_not_root_file(&target, &entry)?;
+    let mut pending = root.to_pending_patch()?;
+    remove_entry(&mut pending, src)?;
+    pending.patch(entry_patch(&target, entry))?;
+    db.write_pending(format!("Move {src} to {target}"), pending)?;
+    let json = json!({ "src": format!("/{src}"), "dst": format!("/{target}") });
+    Ok(Output::new(format!("moved /{src} to /{target}"), json))
+}
+
+/// delete files or folders from the brdb, asking first unless `force` is set
+pub fn remove(db: &BrReader<Brdb>, path: &str, recursive: bool, force: bool) -> Result<Output, Error> {
+    let root = db.get_fs()?;
+    let paths = pattern::expand(&root, path)?;
+    // ** can match a folder along with the things in it, and removing the folder already takes those with it
+    let paths: Vec<String> =
+        (paths.iter()).filter(|path| !paths.iter().any(|folder| path.starts_with(&format!("{folder}/")))).cloned().collect();
+
+    for path in &paths {
+        match path.as_str() {
+            "" => return Err(Error::Other(String::from("cant remove the root folder"))),
+            _ => match traverse(&root, path)? {
+                BrFs::File(_) => (),
+                _ if recursive => (),
+                _ => return Err(Error::Other(format!("/{path} is a folder, use -r to remove it"))),
+            },
+        }
+    }
+
+    let question = match paths.as_slice() {
+        [path] => format!("remove /{path}?"),
+        _ => format!("remove {} entries?", paths.len()),
+    };
+    if !force && !confirm(&question, "-f")? {
+        return Ok(Output::new("nothing removed", json!({ "removed": [] })));
+    }
+
+    let mut pending = root.to_pending_patch()?;
+    for path in &paths {
+        remove_entry(&mut pending, path)?;
+    }
+    let description = match paths.as_slice() {
+        [path] => format!("Remove {path}"),
+        _ => format!("Remove {}", paths.join(", ")),
+    };
+    db.write_pending(description, pending)?;
+
+    let removed: Vec<String> = paths.iter().map(|path| format!("/{path}")).collect();
+    let text = strings_to_lines(removed.iter().map(|path| format!("removed {path}")));
+    Ok(Output::new(text, json!({ "removed": removed })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::world;
+
+    #[test]
+    fn removing_a_folder_and_everything_under_it_only_removes_the_folder() {
+        let db = world();
+        let output = remove(&db, "World/0/Bricks/**", true, true).unwrap();
+        let removed = output.json["removed"].as_array().unwrap();
+        assert!(removed.contains(&json!("/World/0/Bricks/Grids")), "{removed:?}");
+        assert!(!removed.iter().any(|path| path.as_str().unwrap().starts_with("/World/0/Bricks/Grids/")), "{removed:?}");
+        assert!(traverse(&db.get_fs().unwrap(), "World/0/Bricks/Grids").is_err());
+    }
+
+    #[test]
+    fn the_same_world_written_two_ways_is_the_same_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let world = dir.path().join("w.brdb");
+        fs::write(&world, "").unwrap();
+        fs::write(dir.path().join("other.brdb"), "").unwrap();
+        assert!(same_file(&world, &dir.path().join(".").join("w.brdb")).unwrap());
+        assert!(!same_file(&world, &dir.path().join("other.brdb")).unwrap());
+    }
+}